@@ -1,8 +1,95 @@
+use crate::client::{parse_gh_ts, GHOST_AUTHOR};
+use crate::config::{
+    BusinessHours, ChangeComposition, ChangeKind, CommunityHealthWeights, MetricsConfig,
+};
+use crate::contributors::role_sql;
 use anyhow::Result;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use regex::Regex;
 use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::LazyLock;
 
-pub fn compute_metrics(conn: &Connection) -> Result<()> {
+/// Minimum token-set similarity for two open issue titles to be flagged as potential duplicates.
+const DUPLICATE_TITLE_THRESHOLD: f64 = 0.8;
+
+/// Trailing window for the bus factor calculation.
+const BUS_FACTOR_WINDOW_DAYS: i64 = 90;
+
+/// `contributor_leaderboard` periods and their trailing windows in days; `None` is all-time.
+const LEADERBOARD_PERIODS: &[(&str, Option<i64>)] = &[
+    ("7d", Some(7)),
+    ("30d", Some(30)),
+    ("90d", Some(90)),
+    ("all", None),
+];
+
+/// Leaderboard score weights for PRs opened, commits, reviews given and comments.
+const LEADERBOARD_WEIGHTS: (f64, f64, f64, f64) = (3.0, 1.0, 2.0, 0.5);
+
+/// Merges into the same branch at most this far apart are counted as one `merge_batches` batch.
+const MERGE_BATCH_GAP_MINUTES: i64 = 5;
+
+/// Trailing window, by request date, for `reviewer_latency_pct`.
+const REVIEWER_LATENCY_WINDOW_DAYS: i64 = 90;
+
+/// Trailing window for the community health components.
+const COMMUNITY_HEALTH_WINDOW_DAYS: i64 = 30;
+
+/// Actors whose `closed` events mean "went stale", not "resolved".
+/// `github-actions[bot]` is the actor when the actions/stale workflow closes an issue.
+const STALE_BOT_ACTORS: &[&str] = &["stale[bot]", "github-actions[bot]"];
+
+/// Count columns summed across repos into `org_daily_metrics`.
+pub const ORG_SUM_COLUMNS: &[&str] = &[
+    "prs_opened",
+    "prs_merged",
+    "issues_opened",
+    "issues_closed",
+    "issues_completed",
+    "issues_not_planned",
+    "issues_closed_unknown_reason",
+    "issues_stale_closed",
+    "issues_resolved_closed",
+    "churn_additions",
+    "churn_deletions",
+    "ci_failures",
+    "ci_runs",
+    "stars",
+    "open_items_count",
+    "open_issues_count",
+    "open_prs_count",
+    "open_items_over_7d",
+    "open_items_over_30d",
+    "open_items_over_90d",
+    "qa_discussions_opened",
+    "qa_discussions_answered",
+    "open_critical_vulnerabilities",
+    "open_high_vulnerabilities",
+    "bypassed_reviews",
+    "prs_merged_internal",
+    "prs_merged_external",
+];
+
+/// Time columns averaged across repos into `org_daily_metrics`, each weighted by the count of
+/// items it was averaged over (approximated where no exact count column exists).
+pub const ORG_WEIGHTED_COLUMNS: &[(&str, &str)] = &[
+    ("time_to_first_response", "prs_opened + issues_opened"),
+    ("avg_issue_resolution_time", "issues_resolved_closed"),
+    ("avg_pr_resolution_time", "prs_merged"),
+    ("avg_pr_age_at_merge", "prs_merged"),
+    ("time_to_merge_internal", "prs_merged_internal"),
+    ("time_to_merge_external", "prs_merged_external"),
+];
+
+/// Conventional commit subject, e.g. "fix(client): handle 409". The scope is optional.
+static CONVENTIONAL_COMMIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(feat|fix|chore|docs|test|refactor|perf)(\(.*\))?!?:").unwrap());
+
+pub fn compute_metrics(conn: &Connection, config: &MetricsConfig) -> Result<()> {
     // Smart detect of dirty window
     let last_metric_date: Option<String> = conn
         .query_row("SELECT max(date) FROM daily_metrics", [], |row| row.get(0))
@@ -32,6 +119,8 @@ pub fn compute_metrics(conn: &Connection) -> Result<()> {
          SELECT 
             parent.repo,
             date(parent.created_at) as created_date,
+            parent.created_at,
+            MIN(activity.activity_at) as responded_at,
             (julianday(MIN(activity.activity_at)) - julianday(parent.created_at)) * 24 as hours_to_response
          FROM (
             SELECT id, repo, number, author, created_at FROM issues
@@ -53,6 +142,126 @@ pub fn compute_metrics(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    // Issues whose most recent close was done by a stale bot
+    let stale_actors = STALE_BOT_ACTORS
+        .iter()
+        .map(|a| format!("'{}'", a))
+        .collect::<Vec<_>>()
+        .join(", ");
+    conn.execute(
+        &format!(
+            "CREATE TEMP TABLE IF NOT EXISTS temp_stale_closed AS
+             SELECT DISTINCT e.repo, e.issue_number
+             FROM issue_events e
+             WHERE e.event = 'closed'
+               AND e.actor IN ({})
+               AND e.created_at = (
+                   SELECT MAX(x.created_at) FROM issue_events x
+                   WHERE x.repo = e.repo AND x.issue_number = e.issue_number AND x.event = 'closed'
+               )",
+            stale_actors
+        ),
+        [],
+    )?;
+
+    // Open issue/PR counts per (date, repo) for the dirty window, as a running sum of +1 on the
+    // day an item was created and -1 on the day it closed. Counts from before the window are
+    // folded into a baseline row the day before it starts.
+    conn.execute(
+        "CREATE TEMP TABLE IF NOT EXISTS temp_open_counts AS
+         WITH RECURSIVE days(date) AS (
+            SELECT date(?1)
+            UNION ALL
+            SELECT date(date, '+1 day') FROM days WHERE date < date('now')
+         ),
+         deltas AS (
+            SELECT repo, date(created_at) as date, 1 as issues, 0 as prs FROM issues
+            UNION ALL
+            SELECT repo, date(closed_at), -1, 0 FROM issues WHERE closed_at IS NOT NULL
+            UNION ALL
+            SELECT repo, date(created_at), 0, 1 FROM pull_requests
+            UNION ALL
+            SELECT repo, date(closed_at), 0, -1 FROM pull_requests WHERE closed_at IS NOT NULL
+         ),
+         per_day AS (
+            SELECT repo, date, SUM(issues) as issues, SUM(prs) as prs
+            FROM deltas GROUP BY repo, date
+         ),
+         grid AS (
+            SELECT days.date, repos.repo, COALESCE(p.issues, 0) as issues, COALESCE(p.prs, 0) as prs
+            FROM days
+            CROSS JOIN (SELECT DISTINCT repo FROM per_day) repos
+            LEFT JOIN per_day p ON p.repo = repos.repo AND p.date = days.date
+            UNION ALL
+            SELECT date(?1, '-1 day'), repo, SUM(issues), SUM(prs)
+            FROM per_day WHERE date < date(?1) GROUP BY repo
+         )
+         SELECT date, repo,
+            SUM(issues) OVER w as open_issues,
+            SUM(prs) OVER w as open_prs
+         FROM grid
+         WINDOW w AS (PARTITION BY repo ORDER BY date)",
+        params![start_date_str],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS temp_open_counts_repo_date ON temp_open_counts (repo, date)",
+        [],
+    )?;
+
+    // Gap to the previous comment on the same issue (PR conversation comments excluded)
+    conn.execute(
+        "CREATE TEMP TABLE IF NOT EXISTS temp_comment_gaps AS
+         SELECT repo, issue_number, created_at, gap_hours FROM (
+            SELECT
+                c.repo,
+                c.issue_number,
+                c.created_at,
+                (julianday(c.created_at) - julianday(LAG(c.created_at) OVER (
+                    PARTITION BY c.repo, c.issue_number ORDER BY c.created_at
+                ))) * 24 as gap_hours
+            FROM issue_comments c
+            JOIN issues i ON i.repo = c.repo AND i.number = c.issue_number
+         )
+         WHERE gap_hours IS NOT NULL",
+        [],
+    )?;
+
+    // Inline comments per submitted review, linked through the comment's pull_request_review_id.
+    // Reviews without inline comments get 0.
+    conn.execute(
+        "CREATE TEMP TABLE IF NOT EXISTS temp_review_depth AS
+         SELECT r.repo, date(r.submitted_at) as submitted_date, COALESCE(c.comments, 0) as comments
+         FROM pr_reviews r
+         LEFT JOIN (
+            SELECT repo, json_extract(data, '$.pull_request_review_id') as review_id, count(*) as comments
+            FROM pr_review_comments
+            GROUP BY 1, 2
+         ) c ON c.repo = r.repo AND c.review_id = r.id
+         WHERE r.submitted_at IS NOT NULL",
+        [],
+    )?;
+
+    // First and second PR of each external author per repo, for contributor retention.
+    // "External" is judged by the association on their first PR.
+    conn.execute(
+        "CREATE TEMP TABLE IF NOT EXISTS temp_external_first_prs AS
+         WITH ranked AS (
+            SELECT repo, author, created_at,
+                   json_extract(data, '$.author_association') as association,
+                   ROW_NUMBER() OVER (PARTITION BY repo, author ORDER BY created_at) as n
+            FROM pull_requests
+            WHERE deleted_at IS NULL AND author NOT LIKE '%[bot]'
+         )
+         SELECT f.repo, f.author, f.created_at as first_at, s.created_at as second_at
+         FROM ranked f
+         LEFT JOIN ranked s ON s.repo = f.repo AND s.author = f.author AND s.n = 2
+         WHERE f.n = 1
+           AND f.association IN ('CONTRIBUTOR', 'NONE', 'FIRST_TIME_CONTRIBUTOR', 'FIRST_TIMER')",
+        [],
+    )?;
+
+    let size_bucket = config.pr_size_buckets.case_sql("(additions + deletions)");
+
     let now = Utc::now();
     let num_days = (now - start_date).num_days();
 
@@ -81,6 +290,179 @@ pub fn compute_metrics(conn: &Connection) -> Result<()> {
             params![date_str],
         )?;
 
+        // Closes split by GitHub's state_reason; issues closed before it existed are unknown
+        conn.execute(
+            "UPDATE daily_metrics
+             SET issues_completed = (SELECT count(*) FROM issues WHERE repo = daily_metrics.repo AND closed_at IS NOT NULL AND date(closed_at) = date(daily_metrics.date) AND state_reason = 'completed'),
+                 issues_not_planned = (SELECT count(*) FROM issues WHERE repo = daily_metrics.repo AND closed_at IS NOT NULL AND date(closed_at) = date(daily_metrics.date) AND state_reason = 'not_planned'),
+                 issues_closed_unknown_reason = (SELECT count(*) FROM issues WHERE repo = daily_metrics.repo AND closed_at IS NOT NULL AND date(closed_at) = date(daily_metrics.date) AND COALESCE(state_reason, '') NOT IN ('completed', 'not_planned'))
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Stale-bot closes vs real resolutions
+        conn.execute(
+            "UPDATE daily_metrics
+             SET issues_stale_closed = (
+                 SELECT count(*) FROM issues
+                 WHERE repo = daily_metrics.repo AND closed_at IS NOT NULL AND date(closed_at) = date(daily_metrics.date)
+                   AND EXISTS (SELECT 1 FROM temp_stale_closed s WHERE s.repo = issues.repo AND s.issue_number = issues.number)
+             ),
+                 issues_resolved_closed = (
+                 SELECT count(*) FROM issues
+                 WHERE repo = daily_metrics.repo AND closed_at IS NOT NULL AND date(closed_at) = date(daily_metrics.date)
+                   AND NOT EXISTS (SELECT 1 FROM temp_stale_closed s WHERE s.repo = issues.repo AND s.issue_number = issues.number)
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Merged PRs by size bucket (PRs without diff stats are skipped)
+        for bucket in ["XS", "S", "M", "L", "XL"] {
+            conn.execute(
+                &format!(
+                    "UPDATE daily_metrics
+                     SET prs_merged_{} = (
+                         SELECT count(*) FROM pull_requests
+                         WHERE repo = daily_metrics.repo
+                           AND merged_at IS NOT NULL
+                           AND date(merged_at) = date(daily_metrics.date)
+                           AND additions IS NOT NULL
+                           AND {} = ?2
+                     )
+                     WHERE date = ?1",
+                    bucket.to_lowercase(),
+                    size_bucket
+                ),
+                params![date_str, bucket],
+            )?;
+        }
+
+        // Merged PRs that name issues they close, and the share of those whose linked issues
+        // all closed on the merge day. PRs synced before `linked_issues` existed count as unlinked.
+        conn.execute(
+            "UPDATE daily_metrics
+             SET prs_with_linked_issues = (
+                 SELECT count(*) FROM pull_requests
+                 WHERE repo = daily_metrics.repo
+                   AND merged_at IS NOT NULL
+                   AND date(merged_at) = date(daily_metrics.date)
+                   AND json_array_length(COALESCE(linked_issues, '[]')) > 0
+             ),
+                 linked_issue_close_rate = (
+                 SELECT AVG(NOT EXISTS (
+                     SELECT 1 FROM json_each(p.linked_issues) l
+                     WHERE NOT EXISTS (
+                         SELECT 1 FROM issues i
+                         WHERE i.repo = p.repo AND i.number = l.value
+                           AND date(i.closed_at) = date(p.merged_at)
+                     )
+                 ))
+                 FROM pull_requests p
+                 WHERE p.repo = daily_metrics.repo
+                   AND p.merged_at IS NOT NULL
+                   AND date(p.merged_at) = date(daily_metrics.date)
+                   AND json_array_length(COALESCE(p.linked_issues, '[]')) > 0
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Lines changed per file touched, averaged over merged PRs. Large values (>100) usually
+        // mean a big refactor or generated code rather than ordinary feature work.
+        conn.execute(
+            "UPDATE daily_metrics
+             SET avg_pr_churn_per_file = (
+                 SELECT AVG(CAST(additions + deletions AS REAL) / NULLIF(changed_files, 0))
+                 FROM pull_requests
+                 WHERE repo = daily_metrics.repo
+                   AND merged_at IS NOT NULL
+                   AND date(merged_at) = date(daily_metrics.date)
+                   AND additions IS NOT NULL
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Share of merged PRs with a non-trivial description (and a linked issue, if required).
+        // Payloads synced with --redact-bodies carry `body_length` instead of `body`.
+        let linked_issue_clause = if config.pr_description.require_linked_issue {
+            "AND EXISTS (SELECT 1 FROM pr_closes_issue c WHERE c.repo = pull_requests.repo AND c.pr_number = pull_requests.number)"
+        } else {
+            ""
+        };
+        conn.execute(
+            &format!(
+                "UPDATE daily_metrics
+                 SET pr_description_quality = (
+                     SELECT CAST(SUM(CASE WHEN COALESCE(json_extract(data, '$.body_length'), length(trim(COALESCE(json_extract(data, '$.body'), '')))) >= ?2 {} THEN 1 ELSE 0 END) AS REAL) / count(*)
+                     FROM pull_requests
+                     WHERE repo = daily_metrics.repo
+                       AND merged_at IS NOT NULL
+                       AND date(merged_at) = date(daily_metrics.date)
+                 )
+                 WHERE date = ?1",
+                linked_issue_clause
+            ),
+            params![date_str, config.pr_description.min_length],
+        )?;
+
+        conn.execute(
+            "UPDATE daily_metrics
+             SET avg_review_depth = (
+                 SELECT AVG(comments) FROM temp_review_depth
+                 WHERE repo = daily_metrics.repo AND submitted_date = date(daily_metrics.date)
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Of the external contributors whose first PR landed 180-360 days ago (so their
+        // 180-day window has fully passed), the share who opened a second PR within 180 days.
+        conn.execute(
+            "UPDATE daily_metrics
+             SET external_contributor_retention_rate = (
+                 SELECT AVG(second_at IS NOT NULL AND julianday(second_at) - julianday(first_at) <= 180)
+                 FROM temp_external_first_prs
+                 WHERE repo = daily_metrics.repo
+                   AND date(first_at) > date(daily_metrics.date, '-360 days')
+                   AND date(first_at) <= date(daily_metrics.date, '-180 days')
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Share of merged PRs whose body is substantially longer than the repo's PR template.
+        // Repos without a template stay NULL.
+        conn.execute(
+            "UPDATE daily_metrics
+             SET pr_template_compliance_rate = (
+                 SELECT AVG(COALESCE(json_extract(p.data, '$.body_length'), length(trim(COALESCE(json_extract(p.data, '$.body'), '')))) > length(trim(t.body)) * ?2)
+                 FROM pull_requests p
+                 JOIN pr_templates t ON t.repo = p.repo
+                 WHERE p.repo = daily_metrics.repo
+                   AND p.merged_at IS NOT NULL
+                   AND date(p.merged_at) = date(daily_metrics.date)
+             )
+             WHERE date = ?1",
+            params![date_str, config.pr_template.min_body_ratio],
+        )?;
+
+        // Q&A discussions: opened vs answered (by answer date), like issues opened/closed
+        conn.execute(
+            "UPDATE daily_metrics
+             SET qa_discussions_opened = (SELECT count(*) FROM discussions WHERE repo = daily_metrics.repo AND is_answerable = 1 AND date(created_at) = date(daily_metrics.date)),
+                 qa_discussions_answered = (SELECT count(*) FROM discussions WHERE repo = daily_metrics.repo AND is_answerable = 1 AND answer_chosen_at IS NOT NULL AND date(answer_chosen_at) = date(daily_metrics.date))
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+        conn.execute(
+            "UPDATE daily_metrics
+             SET discussions_answered_rate = CAST(qa_discussions_answered AS REAL) / NULLIF(qa_discussions_opened, 0)
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
         conn.execute(
             "UPDATE daily_metrics 
              SET churn_additions = (SELECT COALESCE(SUM(additions), 0) FROM commits WHERE repo = daily_metrics.repo AND date(date) = date(daily_metrics.date)),
@@ -97,46 +479,120 @@ pub fn compute_metrics(conn: &Connection) -> Result<()> {
             params![date_str],
         )?;
 
+        // Status checks posted by CI outside GitHub Actions. Skipped and neutral checks don't count.
         conn.execute(
             "UPDATE daily_metrics
-             SET stars = (
-                 SELECT count(*) FROM stargazers
-                 WHERE repo = daily_metrics.repo AND date(starred_at) <= date(daily_metrics.date)
+             SET external_ci_failure_rate = (
+                 SELECT CAST(SUM(conclusion IN ('failure', 'timed_out')) AS REAL) / count(*)
+                 FROM check_runs
+                 WHERE repo = daily_metrics.repo
+                   AND status = 'completed'
+                   AND conclusion NOT IN ('skipped', 'neutral')
+                   AND COALESCE(app, '') != 'github-actions'
+                   AND date(completed_at) = date(daily_metrics.date)
              )
              WHERE date = ?1",
             params![date_str],
         )?;
 
-        // Open items snapshot (combined issues + PRs for backward compatibility)
+        // CI queue (created -> started) vs run time (started -> last update), in minutes.
+        // Runs without run_started_at are left out of both; duration only counts finished runs.
         conn.execute(
             "UPDATE daily_metrics
-             SET open_items_count = (
-                 (SELECT count(*) FROM issues WHERE repo = daily_metrics.repo AND date(created_at) <= date(daily_metrics.date) AND (closed_at IS NULL OR date(closed_at) > date(daily_metrics.date)))
-                 +
-                 (SELECT count(*) FROM pull_requests WHERE repo = daily_metrics.repo AND date(created_at) <= date(daily_metrics.date) AND (closed_at IS NULL OR date(closed_at) > date(daily_metrics.date)))
+             SET avg_ci_queue_time = (
+                 SELECT AVG((julianday(run_started_at) - julianday(created_at)) * 1440)
+                 FROM workflow_runs
+                 WHERE repo = daily_metrics.repo AND run_started_at IS NOT NULL AND date(created_at) = date(daily_metrics.date)
+             ),
+                 avg_ci_duration = (
+                 SELECT AVG((julianday(updated_at) - julianday(run_started_at)) * 1440)
+                 FROM workflow_runs
+                 WHERE repo = daily_metrics.repo AND run_started_at IS NOT NULL AND conclusion != 'in_progress' AND date(created_at) = date(daily_metrics.date)
              )
              WHERE date = ?1",
-            params![date_str]
+            params![date_str],
         )?;
 
-        // Open issues count (just issues, no PRs)
         conn.execute(
             "UPDATE daily_metrics
-             SET open_issues_count = (
-                 SELECT count(*) FROM issues WHERE repo = daily_metrics.repo AND date(created_at) <= date(daily_metrics.date) AND (closed_at IS NULL OR date(closed_at) > date(daily_metrics.date))
+             SET stars = (
+                 SELECT count(*) FROM stargazers
+                 WHERE repo = daily_metrics.repo AND date(starred_at) <= date(daily_metrics.date)
              )
              WHERE date = ?1",
-            params![date_str]
+            params![date_str],
+        )?;
+
+        // Open items snapshot (combined issues + PRs for backward compatibility)
+        conn.execute(
+            "UPDATE daily_metrics
+             SET open_items_count = COALESCE((
+                 SELECT open_issues + open_prs FROM temp_open_counts t
+                 WHERE t.repo = daily_metrics.repo AND t.date = daily_metrics.date
+             ), 0),
+                 open_issues_count = COALESCE((
+                 SELECT open_issues FROM temp_open_counts t
+                 WHERE t.repo = daily_metrics.repo AND t.date = daily_metrics.date
+             ), 0),
+                 open_prs_count = COALESCE((
+                 SELECT open_prs FROM temp_open_counts t
+                 WHERE t.repo = daily_metrics.repo AND t.date = daily_metrics.date
+             ), 0)
+             WHERE date = ?1",
+            params![date_str],
         )?;
 
-        // Open PRs count
+        // Dependabot alerts open at the end of the day, by severity
+        for severity in ["critical", "high"] {
+            conn.execute(
+                &format!(
+                    "UPDATE daily_metrics
+                     SET open_{severity}_vulnerabilities = (
+                         SELECT count(*) FROM dependabot_alerts
+                         WHERE repo = daily_metrics.repo AND severity = '{severity}'
+                           AND date(created_at) <= date(daily_metrics.date)
+                           AND (fixed_at IS NULL OR date(fixed_at) > date(daily_metrics.date))
+                           AND (dismissed_at IS NULL OR date(dismissed_at) > date(daily_metrics.date))
+                     )
+                     WHERE date = ?1"
+                ),
+                params![date_str],
+            )?;
+        }
+
+        // Aged open items: open on the date and older than each threshold (cumulative, not buckets)
+        for days in [7, 30, 90] {
+            conn.execute(
+                &format!(
+                    "UPDATE daily_metrics
+                     SET open_items_over_{days}d = (
+                         (SELECT count(*) FROM issues WHERE repo = daily_metrics.repo AND date(created_at) <= date(daily_metrics.date) AND (closed_at IS NULL OR date(closed_at) > date(daily_metrics.date))
+                            AND julianday(daily_metrics.date) - julianday(date(created_at)) > {days})
+                         +
+                         (SELECT count(*) FROM pull_requests WHERE repo = daily_metrics.repo AND date(created_at) <= date(daily_metrics.date) AND (closed_at IS NULL OR date(closed_at) > date(daily_metrics.date))
+                            AND julianday(daily_metrics.date) - julianday(date(created_at)) > {days})
+                     )
+                     WHERE date = ?1"
+                ),
+                params![date_str],
+            )?;
+        }
+
+        // Open PRs past the age threshold with no comment, review or review comment by the date.
+        // Commit pushes aren't synced per PR, so a PR that only received commits still counts.
         conn.execute(
             "UPDATE daily_metrics
-             SET open_prs_count = (
-                 SELECT count(*) FROM pull_requests WHERE repo = daily_metrics.repo AND date(created_at) <= date(daily_metrics.date) AND (closed_at IS NULL OR date(closed_at) > date(daily_metrics.date))
+             SET abandoned_prs = (
+                 SELECT count(*) FROM pull_requests p
+                 WHERE p.repo = daily_metrics.repo AND p.deleted_at IS NULL
+                   AND (p.closed_at IS NULL OR date(p.closed_at) > date(daily_metrics.date))
+                   AND julianday(daily_metrics.date) - julianday(date(p.created_at)) > ?2
+                   AND NOT EXISTS (SELECT 1 FROM issue_comments c WHERE c.repo = p.repo AND c.issue_number = p.number AND date(c.created_at) <= date(daily_metrics.date))
+                   AND NOT EXISTS (SELECT 1 FROM pr_reviews r WHERE r.repo = p.repo AND r.pr_number = p.number AND date(r.submitted_at) <= date(daily_metrics.date))
+                   AND NOT EXISTS (SELECT 1 FROM pr_review_comments rc WHERE rc.repo = p.repo AND rc.pr_number = p.number AND date(rc.created_at) <= date(daily_metrics.date))
              )
              WHERE date = ?1",
-            params![date_str]
+            params![date_str, config.abandoned_prs.min_age_days],
         )?;
 
         // Response time stats - Optimized to use Temp Table
@@ -160,6 +616,7 @@ pub fn compute_metrics(conn: &Connection) -> Result<()> {
                  WHERE repo = daily_metrics.repo
                    AND closed_at IS NOT NULL
                    AND date(closed_at) = date(daily_metrics.date)
+                   AND NOT EXISTS (SELECT 1 FROM temp_stale_closed s WHERE s.repo = issues.repo AND s.issue_number = issues.number)
              )
              WHERE date = ?1",
             params![date_str],
@@ -178,6 +635,200 @@ pub fn compute_metrics(conn: &Connection) -> Result<()> {
              params![date_str],
         )?;
 
+        // Share of PRs merged this date from a branch in the same repo whose head branch has
+        // since been deleted (GitHub's `head_ref_deleted` issue event). Fork PRs are left out:
+        // their branches aren't the repo's clutter.
+        conn.execute(
+            "UPDATE daily_metrics
+             SET branch_cleanup_rate = (
+                 SELECT AVG(EXISTS (
+                     SELECT 1 FROM issue_events e
+                     WHERE e.repo = p.repo AND e.issue_number = p.number
+                       AND e.event = 'head_ref_deleted'
+                 ))
+                 FROM pull_requests p
+                 WHERE p.repo = daily_metrics.repo
+                   AND p.merged_at IS NOT NULL
+                   AND date(p.merged_at) = date(daily_metrics.date)
+                   AND json_extract(p.data, '$.head.repo.id') = json_extract(p.data, '$.base.repo.id')
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Lead time from each commit's author date to the first non-draft release published
+        // after it, keyed by the release date. Commits not released yet are left out.
+        conn.execute(
+            "UPDATE daily_metrics
+             SET avg_commit_to_release_hours = (
+                 SELECT AVG((julianday(r.published_at) - julianday(c.date)) * 24)
+                 FROM commits c
+                 JOIN releases r ON r.repo = c.repo
+                 WHERE c.repo = daily_metrics.repo
+                   AND r.draft = 0
+                   AND r.published_at IS NOT NULL
+                   AND date(r.published_at) = date(daily_metrics.date)
+                   AND julianday(c.date) < julianday(r.published_at)
+                   AND NOT EXISTS (
+                       SELECT 1 FROM releases r2
+                       WHERE r2.repo = c.repo
+                         AND r2.draft = 0
+                         AND r2.published_at IS NOT NULL
+                         AND julianday(r2.published_at) > julianday(c.date)
+                         AND julianday(r2.published_at) < julianday(r.published_at)
+                   )
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // avg_pr_resolution_time above mixes both outcomes; these split it by terminal event.
+        conn.execute(
+            "UPDATE daily_metrics
+             SET avg_pr_age_at_merge = (
+                 SELECT AVG((julianday(merged_at) - julianday(created_at)) * 24)
+                 FROM pull_requests
+                 WHERE repo = daily_metrics.repo
+                   AND merged_at IS NOT NULL
+                   AND date(merged_at) = date(daily_metrics.date)
+             ),
+             avg_pr_age_at_close_unmerged = (
+                 SELECT AVG((julianday(closed_at) - julianday(created_at)) * 24)
+                 FROM pull_requests
+                 WHERE repo = daily_metrics.repo
+                   AND merged_at IS NULL
+                   AND closed_at IS NOT NULL
+                   AND date(closed_at) = date(daily_metrics.date)
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Creation to first assignment, for issues and PRs first assigned on this date
+        conn.execute(
+            "UPDATE daily_metrics
+             SET avg_time_to_assign_hours = (
+                 SELECT AVG((julianday(a.assigned_at) - julianday(parent.created_at)) * 24)
+                 FROM (
+                     SELECT issue_number, MIN(created_at) as assigned_at
+                     FROM issue_events
+                     WHERE repo = daily_metrics.repo AND event = 'assigned'
+                     GROUP BY issue_number
+                 ) a
+                 JOIN (
+                     SELECT number, created_at FROM issues WHERE repo = daily_metrics.repo
+                     UNION ALL
+                     SELECT number, created_at FROM pull_requests WHERE repo = daily_metrics.repo
+                 ) parent ON parent.number = a.issue_number
+                 WHERE date(a.assigned_at) = date(daily_metrics.date)
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Share of individual review requests on PRs merged this date that got a review back.
+        // Team requests are skipped: there's no single login to match a review against.
+        conn.execute(
+            "UPDATE daily_metrics
+             SET review_request_fulfillment_rate = (
+                 SELECT CAST(SUM(EXISTS (
+                     SELECT 1 FROM pr_reviews r
+                     WHERE r.repo = rr.repo AND r.pr_number = rr.pr_number
+                       AND r.author = rr.reviewer AND r.submitted_at >= rr.requested_at
+                 )) AS REAL) / count(*)
+                 FROM (
+                     SELECT repo, pr_number, reviewer, MIN(requested_at) as requested_at
+                     FROM review_requests
+                     WHERE repo = daily_metrics.repo AND is_team = 0
+                     GROUP BY repo, pr_number, reviewer
+                 ) rr
+                 JOIN pull_requests p ON p.repo = rr.repo AND p.number = rr.pr_number
+                 WHERE p.merged_at IS NOT NULL AND date(p.merged_at) = date(daily_metrics.date)
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Merged PRs with an individual review request still outstanding at merge: the
+        // reviewer's latest request before the merge got no review from them and wasn't
+        // withdrawn (`review_request_removed`) before the merge either.
+        conn.execute(
+            "UPDATE daily_metrics
+             SET bypassed_reviews = (
+                 SELECT count(DISTINCT p.number)
+                 FROM pull_requests p
+                 JOIN (
+                     SELECT q.repo, q.pr_number, q.reviewer, MAX(q.requested_at) as requested_at
+                     FROM review_requests q
+                     JOIN pull_requests m ON m.repo = q.repo AND m.number = q.pr_number
+                     WHERE q.repo = daily_metrics.repo AND q.is_team = 0
+                       AND julianday(q.requested_at) <= julianday(m.merged_at)
+                     GROUP BY q.repo, q.pr_number, q.reviewer
+                 ) rr ON rr.repo = p.repo AND rr.pr_number = p.number
+                 WHERE p.repo = daily_metrics.repo
+                   AND p.merged_at IS NOT NULL
+                   AND date(p.merged_at) = date(daily_metrics.date)
+                   AND NOT EXISTS (
+                       SELECT 1 FROM pr_reviews r
+                       WHERE r.repo = p.repo AND r.pr_number = p.number AND r.author = rr.reviewer
+                         AND julianday(r.submitted_at) >= julianday(rr.requested_at)
+                         AND julianday(r.submitted_at) <= julianday(p.merged_at)
+                   )
+                   AND NOT EXISTS (
+                       SELECT 1 FROM issue_events e
+                       WHERE e.repo = p.repo AND e.issue_number = p.number
+                         AND e.event = 'review_request_removed'
+                         AND lower(json_extract(e.data, '$.requested_reviewer.login')) = rr.reviewer
+                         AND julianday(e.created_at) >= julianday(rr.requested_at)
+                         AND julianday(e.created_at) <= julianday(p.merged_at)
+                   )
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // For issues commented on this date: average gap between successive comments so far,
+        // averaged per issue first so long threads don't dominate
+        conn.execute(
+            "UPDATE daily_metrics
+             SET avg_issue_comment_lag_hours = (
+                 SELECT AVG(issue_lag) FROM (
+                     SELECT AVG(g.gap_hours) as issue_lag
+                     FROM temp_comment_gaps g
+                     WHERE g.repo = daily_metrics.repo
+                       AND date(g.created_at) <= date(daily_metrics.date)
+                       AND g.issue_number IN (
+                           SELECT issue_number FROM temp_comment_gaps
+                           WHERE repo = daily_metrics.repo AND date(created_at) = date(daily_metrics.date)
+                       )
+                     GROUP BY g.issue_number
+                 )
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
+        // Last approval to merge, for approved PRs merged on this date
+        conn.execute(
+            "UPDATE daily_metrics
+             SET avg_time_to_merge_after_approve_hours = (
+                 SELECT AVG((julianday(p.merged_at) - julianday(a.approved_at)) * 24)
+                 FROM pull_requests p
+                 JOIN (
+                     SELECT r.repo, r.pr_number, MAX(r.submitted_at) as approved_at
+                     FROM pr_reviews r
+                     JOIN pull_requests m ON m.repo = r.repo AND m.number = r.pr_number
+                     WHERE r.repo = daily_metrics.repo AND r.state = 'APPROVED' AND r.submitted_at <= m.merged_at
+                     GROUP BY r.repo, r.pr_number
+                 ) a ON a.repo = p.repo AND a.pr_number = p.number
+                 WHERE p.repo = daily_metrics.repo
+                   AND p.merged_at IS NOT NULL
+                   AND date(p.merged_at) = date(daily_metrics.date)
+             )
+             WHERE date = ?1",
+            params![date_str],
+        )?;
+
         // Internal vs external merge times
         conn.execute(
              "UPDATE daily_metrics
@@ -188,6 +839,14 @@ pub fn compute_metrics(conn: &Connection) -> Result<()> {
                    AND merged_at IS NOT NULL
                    AND date(merged_at) = date(daily_metrics.date)
                    AND json_extract(data, '$.author_association') IN ('OWNER', 'MEMBER', 'COLLABORATOR')
+              ),
+                  prs_merged_internal = (
+                 SELECT count(*)
+                 FROM pull_requests
+                 WHERE repo = daily_metrics.repo
+                   AND merged_at IS NOT NULL
+                   AND date(merged_at) = date(daily_metrics.date)
+                   AND json_extract(data, '$.author_association') IN ('OWNER', 'MEMBER', 'COLLABORATOR')
               )
               WHERE date = ?1",
              params![date_str],
@@ -202,14 +861,2542 @@ pub fn compute_metrics(conn: &Connection) -> Result<()> {
                    AND merged_at IS NOT NULL
                    AND date(merged_at) = date(daily_metrics.date)
                    AND json_extract(data, '$.author_association') NOT IN ('OWNER', 'MEMBER', 'COLLABORATOR')
+              ),
+                  prs_merged_external = (
+                 SELECT count(*)
+                 FROM pull_requests
+                 WHERE repo = daily_metrics.repo
+                   AND merged_at IS NOT NULL
+                   AND date(merged_at) = date(daily_metrics.date)
+                   AND json_extract(data, '$.author_association') NOT IN ('OWNER', 'MEMBER', 'COLLABORATOR')
               )
               WHERE date = ?1",
              params![date_str],
         )?;
     }
 
-    // Cleanup temp table
+    compute_cross_repo_percentile(conn, config, &start_date_str)?;
+    compute_business_response_time(conn, &config.business_hours, &start_date_str)?;
+    compute_community_health(conn, &config.community_health, &start_date_str)?;
+    compute_commit_quality(conn, &start_date_str)?;
+    compute_issue_response_rate(conn)?;
+    compute_org_rollup(conn, config, &start_date_str)?;
+    compute_org_membership_changes(conn, &start_date_str)?;
+    compute_milestone_delivery(conn, config, &start_date_str)?;
+    compute_merge_time_by_role(conn, &start_date_str)?;
+    compute_review_wait_by_size(conn, config, &start_date_str)?;
+    compute_newcomer_pr_size(conn, config, &start_date_str)?;
+    compute_review_fanout(conn, &start_date_str)?;
+    compute_author_wip(conn, &start_date_str)?;
+
+    // Cleanup temp tables
     conn.execute("DROP TABLE IF EXISTS temp_response_times", [])?;
+    conn.execute("DROP TABLE IF EXISTS temp_stale_closed", [])?;
+    conn.execute("DROP TABLE IF EXISTS temp_comment_gaps", [])?;
+    conn.execute("DROP TABLE IF EXISTS temp_open_counts", [])?;
+    conn.execute("DROP TABLE IF EXISTS temp_external_first_prs", [])?;
+    conn.execute("DROP TABLE IF EXISTS temp_review_depth", [])?;
+
+    compute_label_dwell_time(conn)?;
+    compute_resolution_time_by_label(conn)?;
+    compute_potential_duplicates(conn)?;
+    compute_bus_factor(conn)?;
+    compute_review_pairing(conn)?;
+    compute_reviewer_latency_pct(conn)?;
+    compute_merge_hour_distribution(conn, &config.business_hours)?;
+    compute_merge_batches(conn)?;
+    compute_contributor_leaderboard(conn)?;
+    compute_change_composition(conn, &config.change_composition)?;
+    compute_triage_sla(conn, config, &start_date_str)?;
+
+    Ok(())
+}
+
+/// Merged PR count and average open-to-merge hours per day, repo and author role
+/// (see `contributors::role_sql`).
+pub fn compute_merge_time_by_role(conn: &Connection, start_date: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM merge_time_by_role WHERE date >= ?1",
+        params![start_date],
+    )?;
+    conn.execute(
+        &format!(
+            "INSERT INTO merge_time_by_role (date, repo, role, prs_merged, avg_hours)
+             SELECT date(merged_at), repo, {}, count(*),
+                    AVG((julianday(merged_at) - julianday(created_at)) * 24)
+             FROM pull_requests
+             WHERE merged_at IS NOT NULL AND deleted_at IS NULL AND date(merged_at) >= ?1
+             GROUP BY 1, 2, 3",
+            role_sql("author")
+        ),
+        params![start_date],
+    )?;
+    Ok(())
+}
+
+/// Hours from opening to the first review by someone other than the author, averaged per day
+/// the PR was opened, repo and size bucket. Bot PRs and bot reviews are left out, as are PRs
+/// without diff stats or reviews.
+pub fn compute_review_wait_by_size(
+    conn: &Connection,
+    config: &MetricsConfig,
+    start_date: &str,
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM review_wait_by_size WHERE date >= ?1",
+        params![start_date],
+    )?;
+    conn.execute(
+        &format!(
+            "INSERT INTO review_wait_by_size (date, repo, size_bucket, avg_first_review_hours, count)
+             SELECT date(p.created_at), p.repo, {}, AVG((julianday(f.first_review) - julianday(p.created_at)) * 24), count(*)
+             FROM pull_requests p
+             JOIN (
+                SELECT r.repo, r.pr_number, MIN(r.submitted_at) as first_review
+                FROM pr_reviews r
+                JOIN pull_requests pr ON pr.repo = r.repo AND pr.number = r.pr_number
+                WHERE r.author != pr.author AND r.author NOT LIKE '%[bot]'
+                GROUP BY r.repo, r.pr_number
+             ) f ON f.repo = p.repo AND f.pr_number = p.number
+             WHERE p.additions IS NOT NULL
+               AND p.deleted_at IS NULL
+               AND p.author NOT LIKE '%[bot]'
+               AND date(p.created_at) >= ?1
+             GROUP BY 1, 2, 3",
+            config
+                .pr_size_buckets
+                .case_sql("(p.additions + p.deletions)")
+        ),
+        params![start_date],
+    )?;
+    Ok(())
+}
 
+/// Average open-to-close time of closed issues, grouped by their current labels.
+/// An issue with several labels counts once under each.
+pub fn compute_resolution_time_by_label(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM resolution_time_by_label", [])?;
+    conn.execute(
+        "INSERT INTO resolution_time_by_label (repo, label, avg_resolution_hours, count)
+         SELECT l.repo, l.label,
+                AVG((julianday(i.closed_at) - julianday(i.created_at)) * 24),
+                COUNT(*)
+         FROM item_labels l
+         JOIN issues i ON i.repo = l.repo AND i.number = l.item_number
+         WHERE l.item_type = 'issue'
+           AND i.closed_at IS NOT NULL
+           AND i.deleted_at IS NULL
+         GROUP BY l.repo, l.label",
+        [],
+    )?;
+    Ok(())
+}
+
+/// PRs opened per day and size bucket, split by whether it was the author's first PR to the
+/// repo. Bots and PRs without size data are left out.
+pub fn compute_newcomer_pr_size(
+    conn: &Connection,
+    config: &MetricsConfig,
+    start_date: &str,
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM newcomer_pr_size WHERE date >= ?1",
+        params![start_date],
+    )?;
+    conn.execute(
+        &format!(
+            "INSERT INTO newcomer_pr_size (date, repo, is_first_time, size_bucket, count)
+             SELECT date(p.created_at), p.repo,
+                    NOT EXISTS (
+                        SELECT 1 FROM pull_requests e
+                        WHERE e.repo = p.repo AND e.author = p.author
+                          AND e.created_at < p.created_at
+                    ),
+                    {}, count(*)
+             FROM pull_requests p
+             WHERE p.additions IS NOT NULL
+               AND p.deleted_at IS NULL
+               AND p.author NOT LIKE '%[bot]'
+               AND date(p.created_at) >= ?1
+             GROUP BY 1, 2, 3, 4",
+            config
+                .pr_size_buckets
+                .case_sql("(p.additions + p.deletions)")
+        ),
+        params![start_date],
+    )?;
+    Ok(())
+}
+
+/// Review requests vs reviews that came back, per day a PR was merged or closed and repo.
+/// An individual request counts as answered when that reviewer reviewed after being asked. A
+/// team request counts as answered when someone who wasn't individually requested reviewed
+/// after the team was asked, since team membership at the time isn't known.
+pub fn compute_review_fanout(conn: &Connection, start_date: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM review_fanout WHERE date >= ?1",
+        params![start_date],
+    )?;
+    conn.execute(
+        "INSERT INTO review_fanout (date, repo, requested, responded, team_requested, team_responded)
+         SELECT date(COALESCE(p.merged_at, p.closed_at)) as d, p.repo,
+                SUM(rr.is_team = 0), SUM(rr.is_team = 0 AND rr.responded),
+                SUM(rr.is_team = 1), SUM(rr.is_team = 1 AND rr.responded)
+         FROM (
+             SELECT q.repo, q.pr_number, q.is_team,
+                    CASE WHEN q.is_team = 0 THEN EXISTS (
+                        SELECT 1 FROM pr_reviews r
+                        WHERE r.repo = q.repo AND r.pr_number = q.pr_number
+                          AND r.author = q.reviewer AND r.submitted_at >= q.requested_at
+                    ) ELSE EXISTS (
+                        SELECT 1 FROM pr_reviews r
+                        WHERE r.repo = q.repo AND r.pr_number = q.pr_number
+                          AND r.submitted_at >= q.requested_at
+                          AND NOT EXISTS (
+                              SELECT 1 FROM review_requests i
+                              WHERE i.repo = r.repo AND i.pr_number = r.pr_number
+                                AND i.is_team = 0 AND i.reviewer = r.author
+                          )
+                    ) END as responded
+             FROM (
+                 SELECT repo, pr_number, reviewer, is_team, MIN(requested_at) as requested_at
+                 FROM review_requests
+                 GROUP BY repo, pr_number, reviewer, is_team
+             ) q
+         ) rr
+         JOIN pull_requests p ON p.repo = rr.repo AND p.number = rr.pr_number
+         WHERE p.deleted_at IS NULL
+           AND COALESCE(p.merged_at, p.closed_at) IS NOT NULL
+           AND date(COALESCE(p.merged_at, p.closed_at)) >= ?1
+         GROUP BY d, p.repo",
+        params![start_date],
+    )?;
+    Ok(())
+}
+
+/// PRs each author had open at the end of each day (opened on or before it, not yet closed),
+/// per repo, for WIP limits. Bots are left out, and authors with nothing open get no row.
+/// `daily_metrics` gets the max and average across the authors with something open.
+pub fn compute_author_wip(conn: &Connection, start_date: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM author_wip WHERE date >= ?1",
+        params![start_date],
+    )?;
+    // Walk each PR's open days rather than joining every PR against every day.
+    conn.execute(
+        "INSERT INTO author_wip (date, repo, author, open_prs)
+         WITH RECURSIVE open_days(repo, author, date, last_date) AS (
+             SELECT repo, author, max(date(created_at), date(?1)),
+                    min(COALESCE(date(closed_at, '-1 day'), date('now')), date('now'))
+             FROM pull_requests
+             WHERE deleted_at IS NULL
+               AND author NOT LIKE '%[bot]'
+               AND (closed_at IS NULL OR date(closed_at) > date(?1))
+             UNION ALL
+             SELECT repo, author, date(date, '+1 day'), last_date
+             FROM open_days WHERE date < last_date
+         )
+         SELECT date, repo, author, count(*)
+         FROM open_days
+         WHERE date <= last_date
+         GROUP BY date, repo, author",
+        params![start_date],
+    )?;
+    conn.execute(
+        "UPDATE daily_metrics
+         SET max_author_wip = COALESCE((
+                 SELECT MAX(open_prs) FROM author_wip w
+                 WHERE w.repo = daily_metrics.repo AND w.date = daily_metrics.date
+             ), 0),
+             avg_author_wip = (
+                 SELECT AVG(open_prs) FROM author_wip w
+                 WHERE w.repo = daily_metrics.repo AND w.date = daily_metrics.date
+             )
+         WHERE date >= ?1",
+        params![start_date],
+    )?;
     Ok(())
 }
+
+/// Average time an issue carries each label, from `labeled` to the next matching
+/// `unlabeled` event. Labels that are still applied are measured up to now.
+pub fn compute_label_dwell_time(conn: &Connection) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+
+    conn.execute("DELETE FROM label_dwell_time", [])?;
+    conn.execute(
+        "INSERT INTO label_dwell_time (repo, label, avg_hours, count)
+         SELECT repo, label, AVG(hours), COUNT(*)
+         FROM (
+            SELECT
+                l.repo,
+                l.label,
+                (julianday(COALESCE(
+                    (SELECT MIN(u.created_at) FROM issue_events u
+                     WHERE u.repo = l.repo
+                       AND u.issue_number = l.issue_number
+                       AND u.label = l.label
+                       AND u.event = 'unlabeled'
+                       AND u.created_at >= l.created_at),
+                    ?1
+                )) - julianday(l.created_at)) * 24 as hours
+            FROM issue_events l
+            WHERE l.event = 'labeled' AND l.label IS NOT NULL
+         )
+         GROUP BY repo, label",
+        params![now],
+    )?;
+
+    Ok(())
+}
+
+/// Heuristic duplicate detection: flags pairs of open issues in the same repo whose normalized
+/// titles have a Jaccard token-set similarity at or above `DUPLICATE_TITLE_THRESHOLD`.
+/// The newer issue is recorded as `issue_number`, the older one as `similar_to`.
+pub fn compute_potential_duplicates(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT repo, number, title FROM issues
+         WHERE state = 'open' AND deleted_at IS NULL
+         ORDER BY repo, number",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut by_repo: HashMap<String, Vec<(i64, BTreeSet<String>)>> = HashMap::new();
+    for (repo, number, title) in rows {
+        let tokens = title_tokens(&title);
+        if !tokens.is_empty() {
+            by_repo.entry(repo).or_default().push((number, tokens));
+        }
+    }
+
+    conn.execute("DELETE FROM potential_duplicates", [])?;
+    for (repo, issues) in &by_repo {
+        for (i, (newer, newer_tokens)) in issues.iter().enumerate() {
+            for (older, older_tokens) in &issues[..i] {
+                let score = jaccard(newer_tokens, older_tokens);
+                if score >= DUPLICATE_TITLE_THRESHOLD {
+                    conn.execute(
+                        "INSERT OR REPLACE INTO potential_duplicates (repo, issue_number, similar_to, score)
+                         VALUES (?1, ?2, ?3, ?4)",
+                        params![repo, newer, older, score],
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Lowercased alphanumeric words of a title.
+fn title_tokens(title: &str) -> BTreeSet<String> {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Bus factor: the fewest commit authors that together account for at least half of a repo's
+/// commits in the trailing window. Authors are matched case-insensitively and bots are excluded.
+pub fn compute_bus_factor(conn: &Connection) -> Result<()> {
+    let window_start = (Utc::now() - Duration::days(BUS_FACTOR_WINDOW_DAYS))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let mut stmt = conn.prepare(
+        "SELECT repo, lower(trim(author)) as who, count(*) as n
+         FROM commits
+         WHERE date(date) >= ?1 AND author NOT LIKE '%[bot]'
+         GROUP BY repo, who
+         ORDER BY repo, n DESC",
+    )?;
+    let rows = stmt
+        .query_map(params![window_start], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut by_repo: HashMap<String, Vec<i64>> = HashMap::new();
+    for (repo, n) in rows {
+        by_repo.entry(repo).or_default().push(n);
+    }
+
+    let now = Utc::now().to_rfc3339();
+    conn.execute("DELETE FROM repo_risk", [])?;
+    for (repo, counts) in &by_repo {
+        let total: i64 = counts.iter().sum();
+        conn.execute(
+            "INSERT INTO repo_risk (repo, window_days, bus_factor, total_commits, computed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![repo, BUS_FACTOR_WINDOW_DAYS, bus_factor(counts), total, now],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// `counts` must be sorted descending.
+fn bus_factor(counts: &[i64]) -> i64 {
+    let total: i64 = counts.iter().sum();
+    let mut covered = 0;
+    for (i, n) in counts.iter().enumerate() {
+        covered += n;
+        if covered * 2 >= total {
+            return i as i64 + 1;
+        }
+    }
+    counts.len() as i64
+}
+
+/// All-time merges per repo by hour of day, once in UTC and once in the business-hours
+/// timezone (a single set of rows when that is UTC). A broad spread suggests a distributed team.
+pub fn compute_merge_hour_distribution(conn: &Connection, calendar: &BusinessHours) -> Result<()> {
+    let tz = calendar.tz()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT repo, merged_at FROM pull_requests
+         WHERE merged_at IS NOT NULL AND deleted_at IS NULL",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut counts: BTreeMap<(String, String, u32), i64> = BTreeMap::new();
+    for (repo, merged_at) in rows {
+        let Some(merged) = parse_gh_ts(&merged_at) else {
+            continue;
+        };
+        *counts
+            .entry((repo.clone(), "UTC".to_string(), merged.hour()))
+            .or_default() += 1;
+        if tz != Tz::UTC {
+            *counts
+                .entry((
+                    repo,
+                    calendar.timezone.clone(),
+                    merged.with_timezone(&tz).hour(),
+                ))
+                .or_default() += 1;
+        }
+    }
+
+    conn.execute("DELETE FROM merge_hour_distribution", [])?;
+    for ((repo, timezone, hour), count) in counts {
+        conn.execute(
+            "INSERT INTO merge_hour_distribution (repo, timezone, hour, count) VALUES (?1, ?2, ?3, ?4)",
+            params![repo, timezone, hour, count],
+        )?;
+    }
+    Ok(())
+}
+
+/// Groups merges into the same base branch into batches, a merge joining the current batch
+/// when it lands within `MERGE_BATCH_GAP_MINUTES` of the previous one. This is a heuristic for
+/// merge-queue throughput: a queue lands its batch back to back, while hand merges are mostly
+/// batches of one. PRs without a `merge_commit_sha` are left out.
+pub fn compute_merge_batches(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT repo, COALESCE(json_extract(data, '$.base.ref'), ''), merged_at
+         FROM pull_requests
+         WHERE merged_at IS NOT NULL AND deleted_at IS NULL
+           AND json_extract(data, '$.merge_commit_sha') IS NOT NULL",
+    )?;
+    let mut merges = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter_map(|(repo, base, merged_at)| Some((repo, base, parse_gh_ts(&merged_at)?)))
+        .collect::<Vec<_>>();
+    merges.sort();
+
+    struct Batch {
+        repo: String,
+        base_ref: String,
+        first: DateTime<Utc>,
+        last: DateTime<Utc>,
+        size: i64,
+    }
+
+    let mut batches: Vec<Batch> = Vec::new();
+    for (repo, base_ref, merged) in merges {
+        match batches.last_mut() {
+            Some(b)
+                if b.repo == repo
+                    && b.base_ref == base_ref
+                    && merged - b.last <= Duration::minutes(MERGE_BATCH_GAP_MINUTES) =>
+            {
+                b.last = merged;
+                b.size += 1;
+            }
+            _ => batches.push(Batch {
+                repo,
+                base_ref,
+                first: merged,
+                last: merged,
+                size: 1,
+            }),
+        }
+    }
+
+    conn.execute("DELETE FROM merge_batches", [])?;
+    for b in batches {
+        conn.execute(
+            "INSERT OR REPLACE INTO merge_batches (repo, base_ref, batch_time, batch_size)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![b.repo, b.base_ref, b.first.to_rfc3339(), b.size],
+        )?;
+    }
+    Ok(())
+}
+
+/// Counts reviews per PR author/reviewer pair, then records per repo the share of reviews that
+/// go through the single busiest pair. A high share points at a review silo.
+/// Self-reviews, bots and reviews on deleted PRs are left out.
+pub fn compute_review_pairing(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM review_pairing", [])?;
+    conn.execute(
+        "INSERT INTO review_pairing (repo, author, reviewer, count)
+         SELECT p.repo, p.author, r.author, count(*)
+         FROM pr_reviews r
+         JOIN pull_requests p ON p.repo = r.repo AND p.number = r.pr_number
+         WHERE p.deleted_at IS NULL
+           AND lower(r.author) != lower(p.author)
+           AND r.author NOT LIKE '%[bot]'
+           AND p.author NOT LIKE '%[bot]'
+         GROUP BY p.repo, p.author, r.author",
+        [],
+    )?;
+
+    conn.execute("DELETE FROM review_concentration", [])?;
+    conn.execute(
+        "INSERT INTO review_concentration (repo, total_reviews, distinct_pairs, top_pair_share, computed_at)
+         SELECT repo, SUM(count), COUNT(*), CAST(MAX(count) AS REAL) / SUM(count), ?1
+         FROM review_pairing
+         GROUP BY repo",
+        params![Utc::now().to_rfc3339()],
+    )?;
+
+    Ok(())
+}
+
+/// `triage_sla_met_rate`: of the issues opened each day, the share first labeled or commented
+/// on by someone other than the author (bots aside) within `triage.sla_hours`. Issues still
+/// inside their window and untriaged are left out until the outcome is known. Days back to one
+/// SLA window before `start_date` are redone, since their issues may have been triaged since.
+pub fn compute_triage_sla(
+    conn: &Connection,
+    config: &MetricsConfig,
+    start_date: &str,
+) -> Result<()> {
+    let sla_hours = config.triage.sla_hours;
+    let lookback_days = (sla_hours / 24.0).ceil() as i64;
+    conn.execute(
+        "UPDATE daily_metrics
+         SET triage_sla_met_rate = (
+             SELECT AVG(t.triaged_at IS NOT NULL
+                        AND (julianday(t.triaged_at) - julianday(t.created_at)) * 24 <= ?2)
+             FROM (
+                 SELECT i.created_at, MIN(x.at) as triaged_at
+                 FROM issues i
+                 LEFT JOIN (
+                     SELECT repo, issue_number, created_at as at, actor as who
+                     FROM issue_events WHERE event = 'labeled'
+                     UNION ALL
+                     SELECT repo, issue_number, created_at, author FROM issue_comments
+                 ) x ON x.repo = i.repo AND x.issue_number = i.number
+                    AND x.at >= i.created_at
+                    AND x.who != i.author AND x.who NOT LIKE '%[bot]'
+                 WHERE i.repo = daily_metrics.repo
+                   AND i.deleted_at IS NULL
+                   AND date(i.created_at) = date(daily_metrics.date)
+                 GROUP BY i.id
+             ) t
+             WHERE t.triaged_at IS NOT NULL
+                OR (julianday('now') - julianday(t.created_at)) * 24 > ?2
+         )
+         WHERE date >= date(?1, '-' || ?3 || ' days')",
+        params![start_date, sla_hours, lookback_days],
+    )?;
+    Ok(())
+}
+
+/// Rebuilds `contributor_leaderboard`: per period and repo, each contributor's PRs opened,
+/// commits, reviews on others' PRs and issue/review comments, plus a weighted score. Logins are
+/// already lowercased at sync; bots, `GHOST_AUTHOR` and deleted PRs are left out.
+pub fn compute_contributor_leaderboard(conn: &Connection) -> Result<()> {
+    let (pr_w, commit_w, review_w, comment_w) = LEADERBOARD_WEIGHTS;
+    let computed_at = Utc::now().to_rfc3339();
+
+    conn.execute("DELETE FROM contributor_leaderboard", [])?;
+    for (period, days) in LEADERBOARD_PERIODS {
+        conn.execute(
+            &format!(
+                "INSERT INTO contributor_leaderboard
+                 (period, repo, contributor, prs, commits, reviews, comments, score, computed_at)
+                 SELECT ?1, repo, who,
+                        SUM(kind = 'pr'), SUM(kind = 'commit'), SUM(kind = 'review'), SUM(kind = 'comment'),
+                        SUM(kind = 'pr') * {} + SUM(kind = 'commit') * {} + SUM(kind = 'review') * {} + SUM(kind = 'comment') * {},
+                        ?3
+                 FROM (
+                     SELECT repo, lower(trim(author)) AS who, 'pr' AS kind, created_at AS at
+                     FROM pull_requests WHERE deleted_at IS NULL
+                     UNION ALL
+                     SELECT repo, lower(trim(author)), 'commit', date FROM commits
+                     UNION ALL
+                     SELECT r.repo, lower(trim(r.author)), 'review', r.submitted_at
+                     FROM pr_reviews r
+                     JOIN pull_requests p ON p.repo = r.repo AND p.number = r.pr_number
+                     WHERE p.deleted_at IS NULL AND lower(r.author) != lower(p.author)
+                     UNION ALL
+                     SELECT repo, lower(trim(author)), 'comment', created_at FROM issue_comments
+                     UNION ALL
+                     SELECT repo, lower(trim(author)), 'comment', created_at FROM pr_review_comments
+                 )
+                 WHERE who NOT LIKE '%[bot]' AND who != ?4
+                   AND (?2 IS NULL OR date(at) > date('now', '-' || ?2 || ' days'))
+                 GROUP BY repo, who",
+                pr_w, commit_w, review_w, comment_w
+            ),
+            params![period, days, computed_at, GHOST_AUTHOR],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Share of lines changed by commits that went to docs, tests and source, per repo over the
+/// leaderboard periods. Needs `commit_files`, so commits synced before it existed don't count.
+pub fn compute_change_composition(conn: &Connection, patterns: &ChangeComposition) -> Result<()> {
+    let now = Utc::now();
+    let computed_at = now.to_rfc3339();
+    let mut stmt = conn.prepare(
+        "SELECT c.repo, c.date, f.filename, f.additions + f.deletions
+         FROM commit_files f JOIN commits c ON c.sha = f.sha",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+
+    // (repo, period) -> lines changed in docs, tests, src
+    let mut totals: BTreeMap<(String, &str), [i64; 3]> = BTreeMap::new();
+    for row in rows {
+        let (repo, date, filename, lines) = row?;
+        let Some(committed_at) = parse_gh_ts(&date) else {
+            continue;
+        };
+        let bucket = match patterns.classify(&filename) {
+            ChangeKind::Docs => 0,
+            ChangeKind::Tests => 1,
+            ChangeKind::Src => 2,
+        };
+        for (period, days) in LEADERBOARD_PERIODS {
+            if days.is_some_and(|d| committed_at < now - Duration::days(d)) {
+                continue;
+            }
+            totals.entry((repo.clone(), period)).or_default()[bucket] += lines;
+        }
+    }
+
+    conn.execute("DELETE FROM change_composition", [])?;
+    for ((repo, period), [docs, tests, src]) in totals {
+        let total = docs + tests + src;
+        if total == 0 {
+            continue;
+        }
+        let pct = |lines: i64| lines as f64 * 100.0 / total as f64;
+        conn.execute(
+            "INSERT INTO change_composition
+             (repo, period, docs_pct, tests_pct, src_pct, lines_changed, computed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                repo,
+                period,
+                pct(docs),
+                pct(tests),
+                pct(src),
+                total,
+                computed_at
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Per reviewer, p50/p90 hours from an individual review request to their first review on that
+/// PR, over requests made in the trailing window. Unanswered requests, team requests and bot
+/// reviewers are left out.
+pub fn compute_reviewer_latency_pct(conn: &Connection) -> Result<()> {
+    let window_start = (Utc::now() - Duration::days(REVIEWER_LATENCY_WINDOW_DAYS))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let mut stmt = conn.prepare(
+        "SELECT rr.reviewer,
+                (julianday(MIN(r.submitted_at)) - julianday(rr.requested_at)) * 24 as hours
+         FROM (
+             SELECT repo, pr_number, reviewer, MIN(requested_at) as requested_at
+             FROM review_requests
+             WHERE is_team = 0 AND reviewer NOT LIKE '%[bot]'
+             GROUP BY repo, pr_number, reviewer
+         ) rr
+         JOIN pr_reviews r ON r.repo = rr.repo AND r.pr_number = rr.pr_number
+             AND r.author = rr.reviewer AND r.submitted_at >= rr.requested_at
+         WHERE date(rr.requested_at) >= ?1
+         GROUP BY rr.repo, rr.pr_number, rr.reviewer",
+    )?;
+    let rows = stmt
+        .query_map(params![window_start], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut by_reviewer: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for (reviewer, hours) in rows {
+        by_reviewer.entry(reviewer).or_default().push(hours);
+    }
+
+    let computed_at = Utc::now().to_rfc3339();
+    conn.execute("DELETE FROM reviewer_latency_pct", [])?;
+    for (reviewer, mut hours) in by_reviewer {
+        hours.sort_by(|a, b| a.total_cmp(b));
+        conn.execute(
+            "INSERT INTO reviewer_latency_pct (reviewer, p50_hours, p90_hours, count, computed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                reviewer,
+                percentile(&hours, 0.5),
+                percentile(&hours, 0.9),
+                hours.len() as i64,
+                computed_at
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Org-wide p50/p90/p99 of `time_to_first_response`, pooling items from every non-meta repo by
+/// the day they were created. Reads `temp_response_times`, so it must run inside `compute_metrics`.
+fn compute_cross_repo_percentile(
+    conn: &Connection,
+    config: &MetricsConfig,
+    start_date: &str,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT created_date, hours_to_response FROM temp_response_times
+         WHERE created_date >= ?1 AND hours_to_response IS NOT NULL AND {}
+         ORDER BY created_date",
+        config.meta_repos.filter_sql("repo")
+    ))?;
+    let rows = stmt
+        .query_map(params![start_date], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut by_date: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for (date, hours) in rows {
+        by_date.entry(date).or_default().push(hours);
+    }
+
+    conn.execute(
+        "DELETE FROM org_percentile_metrics WHERE metric = 'time_to_first_response' AND date >= ?1",
+        params![start_date],
+    )?;
+    for (date, mut values) in by_date {
+        values.sort_by(|a, b| a.total_cmp(b));
+        conn.execute(
+            "INSERT OR REPLACE INTO org_percentile_metrics (date, metric, p50, p90, p99)
+             VALUES (?1, 'time_to_first_response', ?2, ?3, ?4)",
+            params![
+                date,
+                percentile(&values, 0.5),
+                percentile(&values, 0.9),
+                percentile(&values, 0.99)
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// `time_to_first_response` counting only business days: weekends and configured holidays in
+/// the configured timezone are left out. Reads `temp_response_times`, so it must run inside
+/// `compute_metrics`.
+fn compute_business_response_time(
+    conn: &Connection,
+    calendar: &BusinessHours,
+    start_date: &str,
+) -> Result<()> {
+    let tz = calendar.tz()?;
+    let holidays: HashSet<NaiveDate> = calendar.holidays.iter().copied().collect();
+
+    let mut stmt = conn.prepare(
+        "SELECT repo, created_date, created_at, responded_at FROM temp_response_times
+         WHERE created_date >= ?1",
+    )?;
+    let rows = stmt
+        .query_map(params![start_date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut by_day: BTreeMap<(String, String), Vec<f64>> = BTreeMap::new();
+    for (repo, date, created_at, responded_at) in rows {
+        let (Some(start), Some(end)) = (parse_gh_ts(&created_at), parse_gh_ts(&responded_at))
+        else {
+            continue;
+        };
+        by_day
+            .entry((repo, date))
+            .or_default()
+            .push(business_hours_between(start, end, tz, &holidays));
+    }
+
+    for ((repo, date), hours) in by_day {
+        conn.execute(
+            "UPDATE daily_metrics SET time_to_first_response_business = ?3
+             WHERE repo = ?1 AND date = ?2",
+            params![repo, date, hours.iter().sum::<f64>() / hours.len() as f64],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Hours between `start` and `end` that fall on business days, i.e. not a Saturday, Sunday or
+/// one of `holidays`, with days bounded by local midnight in `tz`. Zero if `end` is not after
+/// `start`.
+pub fn business_hours_between(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    tz: Tz,
+    holidays: &HashSet<NaiveDate>,
+) -> f64 {
+    if end <= start {
+        return 0.0;
+    }
+
+    let local_midnight = |day: NaiveDate| -> DateTime<Utc> {
+        // On DST gaps at midnight, take the earliest valid instant of the day.
+        tz.from_local_datetime(&day.and_time(NaiveTime::MIN))
+            .earliest()
+            .or_else(|| {
+                tz.from_local_datetime(&(day.and_time(NaiveTime::MIN) + Duration::hours(1)))
+                    .earliest()
+            })
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|| day.and_time(NaiveTime::MIN).and_utc())
+    };
+
+    let mut total = Duration::zero();
+    let mut day = start.with_timezone(&tz).date_naive();
+    let last_day = end.with_timezone(&tz).date_naive();
+    while day <= last_day {
+        let is_weekend = matches!(day.weekday(), Weekday::Sat | Weekday::Sun);
+        if !is_weekend && !holidays.contains(&day) {
+            let day_start = local_midnight(day).max(start);
+            let day_end = local_midnight(day + Duration::days(1)).min(end);
+            if day_end > day_start {
+                total += day_end - day_start;
+            }
+        }
+        day += Duration::days(1);
+    }
+
+    total.num_seconds() as f64 / 3600.0
+}
+
+/// Average first-line length and conventional-commit share of each day's commits.
+/// Done in Rust because SQLite has no built-in REGEXP.
+pub fn compute_commit_quality(conn: &Connection, start_date: &str) -> Result<()> {
+    let mut stmt =
+        conn.prepare("SELECT repo, date(date), message FROM commits WHERE date(date) >= ?1 AND message IS NOT NULL")?;
+    let rows = stmt
+        .query_map(params![start_date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // (repo, date) -> (commits, total subject length, conventional commits)
+    let mut by_day: HashMap<(String, String), (i64, usize, i64)> = HashMap::new();
+    for (repo, date, message) in rows {
+        let subject = message.lines().next().unwrap_or("").trim();
+        let entry = by_day.entry((repo, date)).or_default();
+        entry.0 += 1;
+        entry.1 += subject.chars().count();
+        if CONVENTIONAL_COMMIT.is_match(subject) {
+            entry.2 += 1;
+        }
+    }
+
+    for ((repo, date), (commits, length, conventional)) in by_day {
+        conn.execute(
+            "UPDATE daily_metrics
+             SET avg_commit_message_length = ?3, conventional_commit_rate = ?4
+             WHERE date = ?1 AND repo = ?2",
+            params![
+                date,
+                repo,
+                length as f64 / commits as f64,
+                conventional as f64 / commits as f64
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Share of issues created each day that have received any comment from someone other than
+/// the author or a `[bot]` account, since bot replies (welcome messages, triage labels) aren't
+/// a response from the project. A late first reply changes the rate for the day the issue was opened, so every
+/// date is recomputed, not just the dirty window.
+pub fn compute_issue_response_rate(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TEMP TABLE IF NOT EXISTS temp_issue_response AS
+         SELECT
+            i.repo,
+            date(i.created_at) as created_date,
+            AVG(EXISTS (
+                SELECT 1 FROM issue_comments c
+                WHERE c.repo = i.repo AND c.issue_number = i.number AND c.author != i.author
+                  AND c.author NOT LIKE '%[bot]'
+            )) as rate
+         FROM issues i
+         WHERE i.deleted_at IS NULL
+         GROUP BY i.repo, created_date",
+        [],
+    )?;
+    conn.execute(
+        "UPDATE daily_metrics
+         SET issue_response_rate = (
+             SELECT rate FROM temp_issue_response t
+             WHERE t.repo = daily_metrics.repo AND t.created_date = daily_metrics.date
+         )",
+        [],
+    )?;
+    conn.execute("DROP TABLE IF EXISTS temp_issue_response", [])?;
+    Ok(())
+}
+
+/// Org-wide totals per day in `org_daily_metrics`: `ORG_SUM_COLUMNS` summed and
+/// `ORG_WEIGHTED_COLUMNS` weight-averaged across every non-meta repo.
+pub fn compute_org_rollup(
+    conn: &Connection,
+    config: &MetricsConfig,
+    start_date: &str,
+) -> Result<()> {
+    let mut columns = vec!["date".to_string()];
+    let mut selects = vec!["date".to_string()];
+    for col in ORG_SUM_COLUMNS {
+        columns.push(col.to_string());
+        selects.push(format!("SUM({})", col));
+    }
+    for (col, weight) in ORG_WEIGHTED_COLUMNS {
+        columns.push(col.to_string());
+        selects.push(format!(
+            "SUM({col} * ({weight})) / NULLIF(SUM(CASE WHEN {col} IS NOT NULL THEN {weight} END), 0)"
+        ));
+    }
+
+    conn.execute(
+        "DELETE FROM org_daily_metrics WHERE date >= ?1",
+        params![start_date],
+    )?;
+    conn.execute(
+        &format!(
+            "INSERT INTO org_daily_metrics ({})
+             SELECT {} FROM daily_metrics
+             WHERE date >= ?1 AND {}
+             GROUP BY date",
+            columns.join(", "),
+            selects.join(", "),
+            config.meta_repos.filter_sql("repo")
+        ),
+        params![start_date],
+    )?;
+
+    Ok(())
+}
+
+/// People added to or removed from the org per day, from `OrganizationEvent`s in `org_events`.
+/// `MemberEvent`s are left out: they track repo collaborators, not org membership.
+pub fn compute_org_membership_changes(conn: &Connection, start_date: &str) -> Result<()> {
+    // Membership changes can land on days without any repo activity.
+    conn.execute(
+        "INSERT OR IGNORE INTO org_daily_metrics (date)
+         SELECT DISTINCT date(created_at) FROM org_events WHERE date(created_at) >= ?1",
+        params![start_date],
+    )?;
+    conn.execute(
+        "UPDATE org_daily_metrics
+         SET team_additions = (
+             SELECT count(*) FROM org_events
+             WHERE date(created_at) = date(org_daily_metrics.date)
+               AND event_type = 'OrganizationEvent'
+               AND json_extract(payload, '$.action') = 'member_added'
+         ),
+             team_departures = (
+             SELECT count(*) FROM org_events
+             WHERE date(created_at) = date(org_daily_metrics.date)
+               AND event_type = 'OrganizationEvent'
+               AND json_extract(payload, '$.action') = 'member_removed'
+         )
+         WHERE date >= ?1",
+        params![start_date],
+    )?;
+    Ok(())
+}
+
+/// For milestones due on each day, the share closed on or before their due date. Milestones
+/// still open (or closed late) count as missed; ones due in the future are ignored.
+pub fn compute_milestone_delivery(
+    conn: &Connection,
+    config: &MetricsConfig,
+    start_date: &str,
+) -> Result<()> {
+    let repo_filter = config.meta_repos.filter_sql("repo");
+    conn.execute(
+        &format!(
+            "INSERT OR IGNORE INTO org_daily_metrics (date)
+             SELECT DISTINCT date(due_on) FROM milestones
+             WHERE due_on IS NOT NULL AND date(due_on) >= ?1 AND date(due_on) <= date('now')
+               AND {}",
+            repo_filter
+        ),
+        params![start_date],
+    )?;
+    conn.execute(
+        &format!(
+            "UPDATE org_daily_metrics
+             SET milestone_delivery_rate = (
+                 SELECT AVG(closed_at IS NOT NULL AND date(closed_at) <= date(due_on))
+                 FROM milestones
+                 WHERE date(due_on) = date(org_daily_metrics.date) AND {}
+             )
+             WHERE date >= ?1",
+            repo_filter
+        ),
+        params![start_date],
+    )?;
+    Ok(())
+}
+
+/// Normalized (0..1) inputs to the community health score, each over the trailing window.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HealthComponents {
+    /// `1 / (1 + days to first response)`: same-day responses score near 1.
+    pub responsiveness: f64,
+    /// Merged share of PRs closed in the window.
+    pub merge_rate: f64,
+    /// Stars gained in the window relative to stars before it, capped at 1.
+    pub star_growth: f64,
+    /// `1 -` the top author's share of merged PRs in the window.
+    pub contributor_diversity: f64,
+}
+
+/// Weighted contribution of each component, in field order, and their sum.
+pub fn health_score(c: &HealthComponents, w: &CommunityHealthWeights) -> ([f64; 4], f64) {
+    let contributions = [
+        c.responsiveness * w.responsiveness,
+        c.merge_rate * w.merge_rate,
+        c.star_growth * w.star_growth,
+        c.contributor_diversity * w.contributor_diversity,
+    ];
+    (contributions, contributions.iter().sum())
+}
+
+/// Heuristic composite of responsiveness, merge rate, star growth and contributor diversity.
+/// Writes each component's weighted contribution to `community_health` so the score is
+/// explainable, and the total to `daily_metrics.community_health_score`. Components with no
+/// data in the window count as 0. Reads `temp_response_times`, so it must run inside `compute_metrics`.
+fn compute_community_health(
+    conn: &Connection,
+    weights: &CommunityHealthWeights,
+    start_date: &str,
+) -> Result<()> {
+    let window = format!("-{} days", COMMUNITY_HEALTH_WINDOW_DAYS);
+
+    let mut stmt = conn.prepare("SELECT date, repo FROM daily_metrics WHERE date >= ?1")?;
+    let rows = stmt
+        .query_map(params![start_date], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (date, repo) in rows {
+        let response_hours: Option<f64> = conn.query_row(
+            "SELECT AVG(hours_to_response) FROM temp_response_times
+             WHERE repo = ?1 AND created_date > date(?2, ?3) AND created_date <= ?2",
+            params![repo, date, window],
+            |row| row.get(0),
+        )?;
+
+        let (merged, closed): (i64, i64) = conn.query_row(
+            "SELECT COALESCE(SUM(merged_at IS NOT NULL), 0), count(*) FROM pull_requests
+             WHERE repo = ?1
+               AND COALESCE(merged_at, closed_at) IS NOT NULL
+               AND date(COALESCE(merged_at, closed_at)) > date(?2, ?3)
+               AND date(COALESCE(merged_at, closed_at)) <= ?2",
+            params![repo, date, window],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let (stars_before, stars_gained): (i64, i64) = conn.query_row(
+            "SELECT
+                COALESCE(SUM(date(starred_at) <= date(?2, ?3)), 0),
+                COALESCE(SUM(date(starred_at) > date(?2, ?3) AND date(starred_at) <= ?2), 0)
+             FROM stargazers WHERE repo = ?1",
+            params![repo, date, window],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let (top_author, merged_total): (i64, i64) = conn.query_row(
+            "SELECT COALESCE(MAX(n), 0), COALESCE(SUM(n), 0) FROM (
+                SELECT count(*) as n FROM pull_requests
+                WHERE repo = ?1 AND merged_at IS NOT NULL
+                  AND date(merged_at) > date(?2, ?3) AND date(merged_at) <= ?2
+                GROUP BY author
+             )",
+            params![repo, date, window],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let components = HealthComponents {
+            responsiveness: response_hours.map_or(0.0, |h| 1.0 / (1.0 + h.max(0.0) / 24.0)),
+            merge_rate: if closed > 0 {
+                merged as f64 / closed as f64
+            } else {
+                0.0
+            },
+            star_growth: if stars_gained == 0 {
+                0.0
+            } else {
+                (stars_gained as f64 / stars_before.max(1) as f64).min(1.0)
+            },
+            contributor_diversity: if merged_total > 0 {
+                1.0 - top_author as f64 / merged_total as f64
+            } else {
+                0.0
+            },
+        };
+        let (contributions, score) = health_score(&components, weights);
+
+        conn.execute(
+            "INSERT OR REPLACE INTO community_health
+                (date, repo, responsiveness, merge_rate, star_growth, contributor_diversity, score)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                date,
+                repo,
+                contributions[0],
+                contributions[1],
+                contributions[2],
+                contributions[3],
+                score
+            ],
+        )?;
+        conn.execute(
+            "UPDATE daily_metrics SET community_health_score = ?3 WHERE date = ?1 AND repo = ?2",
+            params![date, repo, score],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Linear-interpolated percentile of an ascending slice, `None` when it is empty. `p` is in
+/// [0, 1].
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = p * last as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use serde_json::json;
+
+    fn db() -> Connection {
+        init_db(":memory:").unwrap()
+    }
+
+    /// RFC3339 timestamp `days` before now.
+    fn days_ago(days: i64) -> String {
+        (Utc::now() - Duration::days(days)).to_rfc3339()
+    }
+
+    fn insert_pr(
+        conn: &Connection,
+        number: i64,
+        author: &str,
+        created_at: &str,
+        merged_at: Option<&str>,
+        data: serde_json::Value,
+    ) {
+        conn.execute(
+            "INSERT INTO pull_requests (id, repo, number, state, author, created_at, updated_at, merged_at, closed_at, data)
+             VALUES (?1, 'r', ?1, ?2, ?3, ?4, ?4, ?5, ?5, ?6)",
+            params![
+                number,
+                if merged_at.is_some() { "closed" } else { "open" },
+                author,
+                created_at,
+                merged_at,
+                data.to_string()
+            ],
+        )
+        .unwrap();
+    }
+
+    /// Runs `compute_metrics` over a database whose metrics already reach yesterday, so only
+    /// the last few days are recomputed instead of everything since 2010.
+    fn compute_recent(conn: &Connection, config: &MetricsConfig) {
+        let yesterday = (Utc::now() - Duration::days(1))
+            .format("%Y-%m-%d")
+            .to_string();
+        conn.execute(
+            "INSERT OR IGNORE INTO daily_metrics (date, repo) VALUES (?1, 'r')",
+            params![yesterday],
+        )
+        .unwrap();
+        compute_metrics(conn, config).unwrap();
+    }
+
+    /// Today's `daily_metrics` value of `column` for repo `r`.
+    fn today<T: rusqlite::types::FromSql>(conn: &Connection, column: &str) -> T {
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM daily_metrics WHERE repo = 'r' AND date = date('now')",
+                column
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn label_dwell_runs_from_labeled_to_unlabeled() {
+        let conn = db();
+        conn.execute_batch(
+            "INSERT INTO issue_events (id, repo, issue_number, event, actor, label, created_at, data) VALUES
+                (1, 'r', 1, 'labeled', 'alice', 'blocked', '2026-01-01T00:00:00Z', '{}'),
+                (2, 'r', 1, 'unlabeled', 'alice', 'blocked', '2026-01-01T06:00:00Z', '{}'),
+                (3, 'r', 2, 'labeled', 'alice', 'blocked', '2026-01-02T00:00:00Z', '{}'),
+                (4, 'r', 2, 'unlabeled', 'alice', 'blocked', '2026-01-02T10:00:00Z', '{}');",
+        )
+        .unwrap();
+
+        compute_label_dwell_time(&conn).unwrap();
+
+        let (avg_hours, count): (f64, i64) = conn
+            .query_row(
+                "SELECT avg_hours, count FROM label_dwell_time WHERE repo = 'r' AND label = 'blocked'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!((avg_hours - 8.0).abs() < 1e-6);
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn near_duplicate_open_titles_are_flagged() {
+        let conn = db();
+        conn.execute_batch(
+            "INSERT INTO issues (id, repo, number, state, author, title, created_at, updated_at, data) VALUES
+                (1, 'r', 1, 'open', 'alice', 'Crash when loading config file', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z', '{}'),
+                (2, 'r', 2, 'open', 'bob', 'crash when loading the config file!', '2026-01-02T00:00:00Z', '2026-01-02T00:00:00Z', '{}'),
+                (3, 'r', 3, 'open', 'carol', 'Add docs for agents', '2026-01-03T00:00:00Z', '2026-01-03T00:00:00Z', '{}'),
+                (4, 'r', 4, 'closed', 'dave', 'Crash when loading config file', '2026-01-04T00:00:00Z', '2026-01-04T00:00:00Z', '{}');",
+        )
+        .unwrap();
+
+        compute_potential_duplicates(&conn).unwrap();
+
+        let pairs: Vec<(i64, i64, f64)> = conn
+            .prepare("SELECT issue_number, similar_to, score FROM potential_duplicates")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        // 5 shared words out of 6; the closed exact duplicate is ignored.
+        assert_eq!(pairs, [(2, 1, 5.0 / 6.0)]);
+    }
+
+    #[test]
+    fn bus_factor_counts_authors_covering_half_the_commits() {
+        let conn = db();
+        let recent = (Utc::now() - Duration::days(1)).to_rfc3339();
+        let old = (Utc::now() - Duration::days(BUS_FACTOR_WINDOW_DAYS + 10)).to_rfc3339();
+        let commits = [
+            ("alice", 2, &recent),
+            ("Alice", 1, &recent),
+            ("bob", 2, &recent),
+            ("carol", 2, &recent),
+            ("dependabot[bot]", 5, &recent),
+            ("dave", 10, &old),
+        ];
+        let mut sha = 0;
+        for (author, n, date) in commits {
+            for _ in 0..n {
+                sha += 1;
+                conn.execute(
+                    "INSERT INTO commits (sha, repo, author, date) VALUES (?1, 'r', ?2, ?3)",
+                    params![sha.to_string(), author, date],
+                )
+                .unwrap();
+            }
+        }
+
+        compute_bus_factor(&conn).unwrap();
+
+        // alice (3) alone is under half of 7; alice and one of bob/carol (5) cover it.
+        let (bus_factor, total): (i64, i64) = conn
+            .query_row(
+                "SELECT bus_factor, total_commits FROM repo_risk WHERE repo = 'r'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((bus_factor, total), (2, 7));
+        assert_eq!(super::bus_factor(&[5, 1]), 1);
+    }
+
+    #[test]
+    fn open_items_over_thresholds_are_cumulative() {
+        let conn = db();
+        let issue = |id: i64, created: &str, closed: Option<&str>| {
+            conn.execute(
+                "INSERT INTO issues (id, repo, number, state, author, created_at, updated_at, closed_at, data)
+                 VALUES (?1, 'r', ?1, 'open', 'alice', ?2, ?2, ?3, '{}')",
+                params![id, created, closed],
+            )
+            .unwrap();
+        };
+        issue(1, &days_ago(5), None);
+        issue(2, &days_ago(10), None);
+        issue(3, &days_ago(100), None);
+        issue(4, &days_ago(100), Some(&days_ago(2)));
+        conn.execute(
+            "INSERT INTO pull_requests (id, repo, number, state, author, created_at, updated_at, data)
+             VALUES (5, 'r', 5, 'open', 'bob', ?1, ?1, '{}')",
+            params![days_ago(40)],
+        )
+        .unwrap();
+
+        compute_recent(&conn, &MetricsConfig::default());
+
+        assert_eq!(today::<i64>(&conn, "open_items_count"), 4);
+        assert_eq!(today::<i64>(&conn, "open_items_over_7d"), 3);
+        assert_eq!(today::<i64>(&conn, "open_items_over_30d"), 2);
+        assert_eq!(today::<i64>(&conn, "open_items_over_90d"), 1);
+    }
+
+    #[test]
+    fn bodyless_pr_counts_as_incomplete_description() {
+        let conn = db();
+        let now = days_ago(0);
+        let long = "x".repeat(60);
+        insert_pr(
+            &conn,
+            1,
+            "alice",
+            &days_ago(1),
+            Some(&now),
+            json!({ "body": long }),
+        );
+        insert_pr(
+            &conn,
+            2,
+            "alice",
+            &days_ago(1),
+            Some(&now),
+            json!({ "body": null }),
+        );
+        insert_pr(
+            &conn,
+            3,
+            "alice",
+            &days_ago(1),
+            Some(&now),
+            json!({ "body_length": 80 }),
+        );
+        insert_pr(
+            &conn,
+            4,
+            "alice",
+            &days_ago(1),
+            Some(&now),
+            json!({ "body": "  short  " }),
+        );
+
+        compute_recent(&conn, &MetricsConfig::default());
+        assert_eq!(today::<f64>(&conn, "pr_description_quality"), 0.5);
+
+        conn.execute(
+            "INSERT INTO pr_closes_issue (repo, pr_number, issue_number) VALUES ('r', 1, 9)",
+            [],
+        )
+        .unwrap();
+        let mut config = MetricsConfig::default();
+        config.pr_description.require_linked_issue = true;
+        compute_recent(&conn, &config);
+        assert_eq!(today::<f64>(&conn, "pr_description_quality"), 0.25);
+    }
+
+    #[test]
+    fn health_score_weighs_each_component() {
+        let components = HealthComponents {
+            responsiveness: 1.0,
+            merge_rate: 0.5,
+            star_growth: 0.0,
+            contributor_diversity: 0.25,
+        };
+        let (contributions, score) = health_score(&components, &CommunityHealthWeights::default());
+        assert_eq!(contributions, [0.3, 0.15, 0.0, 0.05]);
+        assert!((score - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn community_health_components_come_from_the_window() {
+        let conn = db();
+        let now = days_ago(0);
+        insert_pr(&conn, 1, "alice", &days_ago(3), Some(&now), json!({}));
+        insert_pr(&conn, 2, "alice", &days_ago(3), Some(&now), json!({}));
+        insert_pr(&conn, 3, "bob", &days_ago(3), Some(&now), json!({}));
+        insert_pr(&conn, 4, "carol", &days_ago(3), None, json!({}));
+        conn.execute_batch(&format!(
+            "UPDATE pull_requests SET state = 'closed', closed_at = '{now}' WHERE number = 4;
+             INSERT INTO stargazers (repo, user, starred_at) VALUES
+                ('r', 'old', '{}'), ('r', 'new', '{}');",
+            days_ago(100),
+            days_ago(5)
+        ))
+        .unwrap();
+
+        compute_recent(&conn, &MetricsConfig::default());
+
+        let row: [f64; 5] = conn
+            .query_row(
+                "SELECT responsiveness, merge_rate, star_growth, contributor_diversity, score
+                 FROM community_health WHERE repo = 'r' AND date = date('now')",
+                [],
+                |row| {
+                    Ok([
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ])
+                },
+            )
+            .unwrap();
+        // No responses, 3 of 4 closed PRs merged, stars doubled, alice has 2 of 3 merges.
+        let expected = [0.0, 0.3 * 0.75, 0.2, 0.2 / 3.0];
+        for (got, want) in row.iter().zip(expected) {
+            assert!((got - want).abs() < 1e-9, "{:?}", row);
+        }
+        assert!((row[4] - expected.iter().sum::<f64>()).abs() < 1e-9);
+        assert!((today::<f64>(&conn, "community_health_score") - row[4]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ci_queue_time_runs_from_creation_to_start() {
+        let conn = db();
+        let day = Utc::now().format("%Y-%m-%d");
+        let at = |time: &str| format!("{}T{}Z", day, time);
+        for (id, conclusion, started, updated) in [
+            (1, "success", Some("00:02:00"), "00:12:00"),
+            (2, "in_progress", Some("00:04:00"), "00:05:00"),
+            (3, "failure", None, "00:30:00"),
+        ] {
+            conn.execute(
+                "INSERT INTO workflow_runs (id, repo, conclusion, created_at, updated_at, run_started_at)
+                 VALUES (?1, 'r', ?2, ?3, ?4, ?5)",
+                params![id, conclusion, at("00:00:00"), at(updated), started.map(at)],
+            )
+            .unwrap();
+        }
+        // daily_metrics rows are only created for repos with PRs, issues, stars or commits.
+        conn.execute(
+            "INSERT INTO commits (sha, repo, author, date) VALUES ('abc', 'r', 'alice', ?1)",
+            params![at("00:00:00")],
+        )
+        .unwrap();
+
+        compute_recent(&conn, &MetricsConfig::default());
+
+        // Run 3 never started; run 2 is still going, so it has a queue time but no duration.
+        assert!((today::<f64>(&conn, "avg_ci_queue_time") - 3.0).abs() < 1e-6);
+        assert!((today::<f64>(&conn, "avg_ci_duration") - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn unanswered_and_bot_answered_issues_lower_response_rate() {
+        let conn = db();
+        conn.execute_batch(
+            "INSERT INTO daily_metrics (date, repo) VALUES ('2026-01-01', 'r');
+             INSERT INTO issues (id, repo, number, state, author, created_at, updated_at, data) VALUES
+                (1, 'r', 1, 'open', 'alice', '2026-01-01T09:00:00Z', '2026-01-01T09:00:00Z', '{}'),
+                (2, 'r', 2, 'open', 'alice', '2026-01-01T10:00:00Z', '2026-01-01T10:00:00Z', '{}'),
+                (3, 'r', 3, 'open', 'alice', '2026-01-01T11:00:00Z', '2026-01-01T11:00:00Z', '{}'),
+                (4, 'r', 4, 'open', 'alice', '2026-01-01T12:00:00Z', '2026-01-01T12:00:00Z', '{}');
+             INSERT INTO issue_comments (id, repo, issue_number, author, created_at, updated_at, data) VALUES
+                (1, 'r', 1, 'bob', '2026-01-05T00:00:00Z', '2026-01-05T00:00:00Z', '{}'),
+                (2, 'r', 2, 'alice', '2026-01-01T13:00:00Z', '2026-01-01T13:00:00Z', '{}'),
+                (3, 'r', 3, 'github-actions[bot]', '2026-01-01T13:00:00Z', '2026-01-01T13:00:00Z', '{}');",
+        )
+        .unwrap();
+
+        compute_issue_response_rate(&conn).unwrap();
+
+        // Only issue 1 got a human reply; the author's own comment and the bot's don't count.
+        let rate: f64 = conn
+            .query_row(
+                "SELECT issue_response_rate FROM daily_metrics WHERE date = '2026-01-01'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(rate, 0.25);
+    }
+
+    #[test]
+    fn meta_repos_are_left_out_of_the_org_rollup() {
+        let conn = db();
+        conn.execute_batch(
+            "INSERT INTO daily_metrics (date, repo, prs_opened) VALUES
+                ('2026-01-01', 'r', 2), ('2026-01-01', '.github', 5), ('2026-01-01', 'infra', 7);",
+        )
+        .unwrap();
+        let org_prs = |config: &MetricsConfig| -> i64 {
+            compute_org_rollup(&conn, config, "2026-01-01").unwrap();
+            conn.query_row(
+                "SELECT prs_opened FROM org_daily_metrics WHERE date = '2026-01-01'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(org_prs(&MetricsConfig::default()), 9);
+
+        let mut config = MetricsConfig::default();
+        config.meta_repos.use_defaults = false;
+        config.meta_repos.exclude = vec!["infra".to_string()];
+        assert_eq!(config.meta_repos.names(), ["infra"]);
+        assert_eq!(org_prs(&config), 7);
+    }
+
+    #[test]
+    fn merge_times_are_counted_by_author_association() {
+        let conn = db();
+        let now = days_ago(0);
+        let member = json!({ "author_association": "MEMBER" });
+        let outsider = json!({ "author_association": "CONTRIBUTOR" });
+        insert_pr(&conn, 1, "alice", &days_ago(1), Some(&now), member);
+        insert_pr(&conn, 2, "bob", &days_ago(2), Some(&now), outsider.clone());
+        insert_pr(&conn, 3, "carol", &days_ago(4), Some(&now), outsider);
+
+        compute_recent(&conn, &MetricsConfig::default());
+
+        assert_eq!(today::<i64>(&conn, "prs_merged_internal"), 1);
+        assert_eq!(today::<i64>(&conn, "prs_merged_external"), 2);
+        assert!((today::<f64>(&conn, "time_to_merge_external") - 72.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn org_merge_times_are_weighted_by_their_own_counts() {
+        let conn = db();
+        conn.execute_batch(
+            "INSERT INTO daily_metrics
+                (date, repo, prs_merged, prs_merged_internal, prs_merged_external, time_to_merge_internal, time_to_merge_external)
+             VALUES
+                ('2026-01-01', 'a', 4, 1, 3, 10, 100),
+                ('2026-01-01', 'b', 4, 3, 1, 20, 200);",
+        )
+        .unwrap();
+
+        compute_org_rollup(&conn, &MetricsConfig::default(), "2026-01-01").unwrap();
+
+        let (internal, external): (f64, f64) = conn
+            .query_row(
+                "SELECT time_to_merge_internal, time_to_merge_external FROM org_daily_metrics",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        // Weighting by prs_merged would give 15 and 150.
+        assert_eq!((internal, external), (17.5, 125.0));
+    }
+
+    #[test]
+    fn review_pairs_skip_self_reviews_and_bots() {
+        let conn = db();
+        for (number, author) in [
+            (1, "alice"),
+            (2, "alice"),
+            (3, "bob"),
+            (4, "dependabot[bot]"),
+        ] {
+            insert_pr(
+                &conn,
+                number,
+                author,
+                "2026-01-01T00:00:00Z",
+                None,
+                json!({}),
+            );
+        }
+        for (id, (pr, reviewer)) in [
+            (1, "bob"),
+            (2, "bob"),
+            (1, "carol"),
+            (1, "alice"),
+            (4, "bob"),
+            (3, "renovate[bot]"),
+            (3, "alice"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            conn.execute(
+                "INSERT INTO pr_reviews (id, repo, pr_number, state, author, submitted_at, data)
+                 VALUES (?1, 'r', ?2, 'APPROVED', ?3, '2026-01-02T00:00:00Z', '{}')",
+                params![id as i64, pr, reviewer],
+            )
+            .unwrap();
+        }
+
+        compute_review_pairing(&conn).unwrap();
+
+        let pairs: Vec<(String, String, i64)> = conn
+            .prepare("SELECT author, reviewer, count FROM review_pairing ORDER BY author, reviewer")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let pair = |a: &str, r: &str, n| (a.to_string(), r.to_string(), n);
+        assert_eq!(
+            pairs,
+            [
+                pair("alice", "bob", 2),
+                pair("alice", "carol", 1),
+                pair("bob", "alice", 1)
+            ]
+        );
+
+        let concentration: (i64, i64, f64) = conn
+            .query_row(
+                "SELECT total_reviews, distinct_pairs, top_pair_share FROM review_concentration",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(concentration, (4, 3, 0.5));
+    }
+
+    #[test]
+    fn old_untouched_open_prs_are_abandoned() {
+        let conn = db();
+        insert_pr(&conn, 1, "alice", &days_ago(20), None, json!({}));
+        insert_pr(&conn, 2, "alice", &days_ago(20), None, json!({}));
+        insert_pr(&conn, 3, "alice", &days_ago(5), None, json!({}));
+        insert_pr(
+            &conn,
+            4,
+            "alice",
+            &days_ago(20),
+            Some(&days_ago(1)),
+            json!({}),
+        );
+        conn.execute(
+            "INSERT INTO issue_comments (id, repo, issue_number, author, created_at, updated_at, data)
+             VALUES (1, 'r', 2, 'bob', ?1, ?1, '{}')",
+            params![days_ago(10)],
+        )
+        .unwrap();
+
+        compute_recent(&conn, &MetricsConfig::default());
+        assert_eq!(today::<i64>(&conn, "abandoned_prs"), 1);
+
+        let mut config = MetricsConfig::default();
+        config.abandoned_prs.min_age_days = 3;
+        compute_recent(&conn, &config);
+        assert_eq!(today::<i64>(&conn, "abandoned_prs"), 2);
+    }
+
+    #[test]
+    fn churn_per_file_skips_prs_without_files() {
+        let conn = db();
+        let now = days_ago(0);
+        for (number, size) in [
+            (1, Some((90, 10, 4))),
+            (2, Some((30, 0, 2))),
+            (3, Some((10, 0, 0))),
+            (4, None),
+        ] {
+            insert_pr(&conn, number, "alice", &days_ago(1), Some(&now), json!({}));
+            if let Some((additions, deletions, files)) = size {
+                conn.execute(
+                    "UPDATE pull_requests SET additions = ?2, deletions = ?3, changed_files = ?4
+                     WHERE number = ?1",
+                    params![number, additions, deletions, files],
+                )
+                .unwrap();
+            }
+        }
+
+        compute_recent(&conn, &MetricsConfig::default());
+
+        // 100 / 4 and 30 / 2; zero files changed would divide by zero, so that PR is left out.
+        assert_eq!(today::<f64>(&conn, "avg_pr_churn_per_file"), 20.0);
+    }
+
+    #[test]
+    fn business_hours_skip_the_weekend() {
+        let at = |s: &str| parse_gh_ts(s).unwrap();
+        // Friday 20:00 UTC to Monday 04:00 UTC.
+        let (start, end) = (at("2026-01-02T20:00:00Z"), at("2026-01-05T04:00:00Z"));
+        let none = HashSet::new();
+
+        assert_eq!(business_hours_between(start, end, Tz::UTC, &none), 8.0);
+        // In Los Angeles that's Friday noon to Sunday 20:00, so only Friday afternoon counts.
+        assert_eq!(
+            business_hours_between(start, end, Tz::America__Los_Angeles, &none),
+            12.0
+        );
+        let monday_off = HashSet::from([NaiveDate::from_ymd_opt(2026, 1, 5).unwrap()]);
+        assert_eq!(
+            business_hours_between(start, end, Tz::UTC, &monday_off),
+            4.0
+        );
+        assert_eq!(business_hours_between(end, start, Tz::UTC, &none), 0.0);
+    }
+
+    #[test]
+    fn open_vulnerabilities_are_counted_by_severity() {
+        let conn = db();
+        conn.execute(
+            "INSERT INTO commits (sha, repo, author, date) VALUES ('abc', 'r', 'alice', ?1)",
+            params![days_ago(1)],
+        )
+        .unwrap();
+        for (id, severity, created, fixed, dismissed) in [
+            (1, "critical", days_ago(10), None, None),
+            (2, "critical", days_ago(10), Some(days_ago(1)), None),
+            (3, "high", days_ago(10), None, None),
+            (4, "high", days_ago(10), None, Some(days_ago(1))),
+            (5, "high", days_ago(0), None, None),
+            (6, "medium", days_ago(10), None, None),
+        ] {
+            conn.execute(
+                "INSERT INTO dependabot_alerts (id, repo, state, severity, created_at, fixed_at, dismissed_at)
+                 VALUES (?1, 'r', 'open', ?2, ?3, ?4, ?5)",
+                params![id, severity, created, fixed, dismissed],
+            )
+            .unwrap();
+        }
+
+        compute_recent(&conn, &MetricsConfig::default());
+
+        assert_eq!(today::<i64>(&conn, "open_critical_vulnerabilities"), 1);
+        assert_eq!(today::<i64>(&conn, "open_high_vulnerabilities"), 2);
+    }
+
+    #[test]
+    fn review_wait_is_bucketed_by_size_and_skips_self_and_bot_reviews() {
+        let conn = db();
+        for (number, author, lines) in [(1, "alice", 5), (2, "alice", 4), (3, "bob", 300)] {
+            insert_pr(
+                &conn,
+                number,
+                author,
+                "2026-01-01T00:00:00Z",
+                None,
+                json!({}),
+            );
+            conn.execute(
+                "UPDATE pull_requests SET additions = ?2, deletions = 0 WHERE number = ?1",
+                params![number, lines],
+            )
+            .unwrap();
+        }
+        for (id, pr, reviewer, at) in [
+            (1, 1, "alice", "2026-01-01T01:00:00Z"),
+            (2, 1, "dependabot[bot]", "2026-01-01T01:00:00Z"),
+            (3, 1, "bob", "2026-01-01T02:00:00Z"),
+            (4, 1, "carol", "2026-01-01T09:00:00Z"),
+            (5, 2, "bob", "2026-01-01T04:00:00Z"),
+            (6, 3, "alice", "2026-01-02T00:00:00Z"),
+        ] {
+            conn.execute(
+                "INSERT INTO pr_reviews (id, repo, pr_number, state, author, submitted_at, data)
+                 VALUES (?1, 'r', ?2, 'APPROVED', ?3, ?4, '{}')",
+                params![id, pr, reviewer, at],
+            )
+            .unwrap();
+        }
+
+        compute_review_wait_by_size(&conn, &MetricsConfig::default(), "2026-01-01").unwrap();
+
+        let rows: Vec<(String, f64, i64)> = conn
+            .prepare(
+                "SELECT size_bucket, round(avg_first_review_hours, 6), count
+                 FROM review_wait_by_size ORDER BY size_bucket",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [("L".to_string(), 24.0, 1), ("XS".to_string(), 3.0, 2)]
+        );
+    }
+
+    #[test]
+    fn resolution_time_counts_each_label_of_closed_issues() {
+        let conn = db();
+        conn.execute_batch(
+            "INSERT INTO issues (id, repo, number, state, author, created_at, updated_at, closed_at, data) VALUES
+                (1, 'r', 1, 'closed', 'a', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z', '2026-01-01T10:00:00Z', '{}'),
+                (2, 'r', 2, 'closed', 'a', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z', '2026-01-02T06:00:00Z', '{}'),
+                (3, 'r', 3, 'open', 'a', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z', NULL, '{}');
+             INSERT INTO item_labels (repo, item_type, item_number, label) VALUES
+                ('r', 'issue', 1, 'bug'),
+                ('r', 'issue', 2, 'bug'),
+                ('r', 'issue', 2, 'docs'),
+                ('r', 'issue', 3, 'docs'),
+                ('r', 'pr', 1, 'docs');",
+        )
+        .unwrap();
+
+        compute_resolution_time_by_label(&conn).unwrap();
+
+        let rows: Vec<(String, f64, i64)> = conn
+            .prepare(
+                "SELECT label, round(avg_resolution_hours, 6), count
+                 FROM resolution_time_by_label ORDER BY label",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [("bug".to_string(), 20.0, 2), ("docs".to_string(), 30.0, 1)]
+        );
+    }
+
+    #[test]
+    fn template_compliance_compares_body_to_template_length() {
+        let conn = db();
+        let now = Utc::now().to_rfc3339();
+        for (number, data) in [
+            (1, json!({ "body": "x".repeat(30) })),
+            (2, json!({ "body": "  xxxxx  " })),
+            (3, json!({ "body_length": 11 })),
+        ] {
+            insert_pr(&conn, number, "alice", &days_ago(1), Some(&now), data);
+        }
+
+        compute_recent(&conn, &MetricsConfig::default());
+        assert_eq!(
+            today::<Option<f64>>(&conn, "pr_template_compliance_rate"),
+            None
+        );
+
+        conn.execute(
+            "INSERT INTO pr_templates (repo, path, body, fetched_at)
+             VALUES ('r', '.github/pull_request_template.md', ?1, ?2)",
+            params![format!("  {}  ", "t".repeat(20)), now],
+        )
+        .unwrap();
+        compute_recent(&conn, &MetricsConfig::default());
+        let rate: f64 = today(&conn, "pr_template_compliance_rate");
+        assert!((rate - 2.0 / 3.0).abs() < 1e-9, "{}", rate);
+
+        let mut config = MetricsConfig::default();
+        config.pr_template.min_body_ratio = 2.0;
+        compute_recent(&conn, &config);
+        assert_eq!(today::<f64>(&conn, "pr_template_compliance_rate"), 0.0);
+    }
+
+    #[test]
+    fn newcomer_pr_size_splits_first_prs_from_repeat_ones() {
+        let conn = db();
+        for (number, author, created_at, lines) in [
+            (1, "alice", "2026-01-01T00:00:00Z", Some(5)),
+            (2, "alice", "2026-01-02T00:00:00Z", Some(300)),
+            (3, "bob", "2026-01-02T00:00:00Z", Some(8)),
+            (4, "carol", "2026-01-01T00:00:00Z", None),
+            (5, "carol", "2026-01-02T00:00:00Z", Some(3)),
+            (6, "renovate[bot]", "2026-01-02T00:00:00Z", Some(1)),
+        ] {
+            insert_pr(&conn, number, author, created_at, None, json!({}));
+            conn.execute(
+                "UPDATE pull_requests SET additions = ?2, deletions = 0 WHERE number = ?1",
+                params![number, lines],
+            )
+            .unwrap();
+        }
+
+        compute_newcomer_pr_size(&conn, &MetricsConfig::default(), "2026-01-02").unwrap();
+
+        let rows: Vec<(bool, String, i64)> = conn
+            .prepare(
+                "SELECT is_first_time, size_bucket, count FROM newcomer_pr_size
+                 WHERE date = '2026-01-02' ORDER BY is_first_time, size_bucket",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                (false, "L".to_string(), 1),
+                (false, "XS".to_string(), 1),
+                (true, "XS".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            conn.query_row("SELECT count(*) FROM newcomer_pr_size", [], |row| row
+                .get::<_, i64>(0))
+                .unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn membership_changes_count_additions_and_departures() {
+        let conn = db();
+        conn.execute_batch(
+            "INSERT INTO org_events (id, org, event_type, actor, repo, created_at, payload) VALUES
+                ('1', 'o', 'MemberEvent', 'a', 'r', '2026-01-01T01:00:00Z', '{\"action\":\"added\"}'),
+                ('2', 'o', 'OrganizationEvent', 'a', NULL, '2026-01-01T02:00:00Z', '{\"action\":\"member_added\"}'),
+                ('3', 'o', 'OrganizationEvent', 'a', NULL, '2026-01-01T03:00:00Z', '{\"action\":\"member_removed\"}'),
+                ('4', 'o', 'PushEvent', 'a', 'r', '2026-01-01T04:00:00Z', '{\"action\":\"added\"}'),
+                ('5', 'o', 'MemberEvent', 'a', 'r', '2026-01-02T00:00:00Z', '{\"action\":\"edited\"}');",
+        )
+        .unwrap();
+
+        compute_org_membership_changes(&conn, "2026-01-01").unwrap();
+
+        let rows: Vec<(String, i64, i64)> = conn
+            .prepare(
+                "SELECT date, team_additions, team_departures FROM org_daily_metrics ORDER BY date",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                ("2026-01-01".to_string(), 1, 1),
+                ("2026-01-02".to_string(), 0, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_hours_are_counted_in_utc_and_the_calendar_timezone() {
+        let conn = db();
+        for (number, merged_at) in [
+            (1, "2026-01-15T03:30:00Z"),
+            (2, "2026-01-15T03:59:00Z"),
+            (3, "2026-01-15T17:00:00Z"),
+            (4, "not a date"),
+        ] {
+            insert_pr(
+                &conn,
+                number,
+                "alice",
+                "2026-01-14T00:00:00Z",
+                Some(merged_at),
+                json!({}),
+            );
+        }
+        let hours = |conn: &Connection| -> Vec<(String, i64, i64)> {
+            conn.prepare(
+                "SELECT timezone, hour, count FROM merge_hour_distribution ORDER BY timezone, hour",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+        };
+        let row = |tz: &str, hour, count| (tz.to_string(), hour, count);
+
+        compute_merge_hour_distribution(&conn, &BusinessHours::default()).unwrap();
+        assert_eq!(hours(&conn), [row("UTC", 3, 2), row("UTC", 17, 1)]);
+
+        let tokyo = BusinessHours {
+            timezone: "Asia/Tokyo".to_string(),
+            holidays: Vec::new(),
+        };
+        compute_merge_hour_distribution(&conn, &tokyo).unwrap();
+        assert_eq!(
+            hours(&conn),
+            [
+                row("Asia/Tokyo", 2, 1),
+                row("Asia/Tokyo", 12, 2),
+                row("UTC", 3, 2),
+                row("UTC", 17, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn retention_counts_external_authors_returning_within_180_days() {
+        let conn = db();
+        let prs = [
+            ("alice", 200, "CONTRIBUTOR"),
+            ("alice", 100, "CONTRIBUTOR"),
+            ("bob", 250, "NONE"),
+            ("bob", 30, "CONTRIBUTOR"),
+            ("carol", 300, "FIRST_TIME_CONTRIBUTOR"),
+            ("dave", 200, "MEMBER"),
+            ("dave", 190, "MEMBER"),
+            ("erin", 100, "NONE"),
+            ("frank", 400, "NONE"),
+            ("frank", 399, "NONE"),
+        ];
+        for (number, (author, age, association)) in prs.into_iter().enumerate() {
+            insert_pr(
+                &conn,
+                number as i64 + 1,
+                author,
+                &days_ago(age),
+                None,
+                json!({ "author_association": association }),
+            );
+        }
+
+        compute_recent(&conn, &MetricsConfig::default());
+        let rate: f64 = today(&conn, "external_contributor_retention_rate");
+        assert!((rate - 1.0 / 3.0).abs() < 1e-9, "{}", rate);
+    }
+
+    #[test]
+    fn closed_issues_are_split_by_state_reason() {
+        let conn = db();
+        let now = Utc::now().to_rfc3339();
+        for (number, reason) in [
+            (1, Some("completed")),
+            (2, Some("completed")),
+            (3, Some("not_planned")),
+            (4, Some("duplicate")),
+            (5, None),
+        ] {
+            conn.execute(
+                "INSERT INTO issues (id, repo, number, state, author, created_at, updated_at, closed_at, state_reason, data)
+                 VALUES (?1, 'r', ?1, 'closed', 'alice', ?2, ?2, ?3, ?4, '{}')",
+                params![number, days_ago(3), now, reason],
+            )
+            .unwrap();
+        }
+
+        compute_recent(&conn, &MetricsConfig::default());
+
+        assert_eq!(today::<i64>(&conn, "issues_completed"), 2);
+        assert_eq!(today::<i64>(&conn, "issues_not_planned"), 1);
+        assert_eq!(today::<i64>(&conn, "issues_closed_unknown_reason"), 2);
+    }
+
+    #[test]
+    fn review_depth_averages_inline_comments_per_review() {
+        let conn = db();
+        let now = Utc::now().to_rfc3339();
+        insert_pr(&conn, 1, "alice", &days_ago(2), None, json!({}));
+        for (id, submitted_at) in [(10, now.clone()), (11, now.clone()), (12, days_ago(1))] {
+            conn.execute(
+                "INSERT INTO pr_reviews (id, repo, pr_number, state, author, submitted_at, data)
+                 VALUES (?1, 'r', 1, 'COMMENTED', 'bob', ?2, '{}')",
+                params![id, submitted_at],
+            )
+            .unwrap();
+        }
+        for (id, review_id) in [(1, 10), (2, 10), (3, 10), (4, 12), (5, 12)] {
+            conn.execute(
+                "INSERT INTO pr_review_comments (id, repo, pr_number, author, created_at, updated_at, data)
+                 VALUES (?1, 'r', 1, 'bob', ?2, ?2, ?3)",
+                params![
+                    id,
+                    now,
+                    json!({ "pull_request_review_id": review_id }).to_string()
+                ],
+            )
+            .unwrap();
+        }
+
+        compute_recent(&conn, &MetricsConfig::default());
+        assert_eq!(today::<f64>(&conn, "avg_review_depth"), 1.5);
+    }
+
+    #[test]
+    fn pr_age_is_split_between_merged_and_closed_unmerged() {
+        let conn = db();
+        let now = Utc::now();
+        let hours_ago = |h: i64| (now - Duration::hours(h)).to_rfc3339();
+        let now = now.to_rfc3339();
+        insert_pr(&conn, 1, "alice", &hours_ago(10), Some(&now), json!({}));
+        insert_pr(&conn, 2, "alice", &hours_ago(30), Some(&now), json!({}));
+        insert_pr(&conn, 3, "alice", &hours_ago(50), None, json!({}));
+        insert_pr(&conn, 4, "alice", &hours_ago(70), None, json!({}));
+        conn.execute(
+            "UPDATE pull_requests SET state = 'closed', closed_at = ?1 WHERE number = 3",
+            params![now],
+        )
+        .unwrap();
+
+        compute_recent(&conn, &MetricsConfig::default());
+
+        let merged: f64 = today(&conn, "round(avg_pr_age_at_merge, 6)");
+        let unmerged: f64 = today(&conn, "round(avg_pr_age_at_close_unmerged, 6)");
+        assert_eq!((merged, unmerged), (20.0, 50.0));
+    }
+
+    #[test]
+    fn leaderboard_scores_contributions_per_period() {
+        let conn = db();
+        insert_pr(&conn, 1, "alice", &days_ago(2), None, json!({}));
+        insert_pr(&conn, 2, "bob", &days_ago(40), None, json!({}));
+        insert_pr(&conn, 3, GHOST_AUTHOR, &days_ago(2), None, json!({}));
+        conn.execute(
+            "INSERT INTO commits (sha, repo, author, date) VALUES ('c1', 'r', 'alice', ?1)",
+            params![days_ago(2)],
+        )
+        .unwrap();
+        for (id, pr, reviewer) in [(1, 1, "bob"), (2, 2, "bob")] {
+            conn.execute(
+                "INSERT INTO pr_reviews (id, repo, pr_number, state, author, submitted_at, data)
+                 VALUES (?1, 'r', ?2, 'APPROVED', ?3, ?4, '{}')",
+                params![id, pr, reviewer, days_ago(1)],
+            )
+            .unwrap();
+        }
+        for (id, author) in [(1, "alice"), (2, "dependabot[bot]")] {
+            conn.execute(
+                "INSERT INTO issue_comments (id, repo, issue_number, author, created_at, updated_at, data)
+                 VALUES (?1, 'r', 1, ?2, ?3, ?3, '{}')",
+                params![id, author, days_ago(2)],
+            )
+            .unwrap();
+        }
+
+        compute_contributor_leaderboard(&conn).unwrap();
+
+        let board = |period: &str| -> Vec<(String, i64, i64, i64, i64, f64)> {
+            conn.prepare(
+                "SELECT contributor, prs, commits, reviews, comments, score
+                 FROM contributor_leaderboard WHERE period = ?1 ORDER BY contributor",
+            )
+            .unwrap()
+            .query_map(params![period], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+        };
+        let alice = ("alice".to_string(), 1, 1, 0, 1, 4.5);
+        assert_eq!(
+            board("7d"),
+            [alice.clone(), ("bob".to_string(), 0, 0, 1, 0, 2.0)]
+        );
+        assert_eq!(board("90d"), [alice, ("bob".to_string(), 1, 0, 1, 0, 5.0)]);
+        assert_eq!(board("all"), board("90d"));
+    }
+
+    #[test]
+    fn review_fanout_counts_answered_individual_and_team_requests() {
+        let conn = db();
+        insert_pr(
+            &conn,
+            1,
+            "alice",
+            "2026-01-01T00:00:00Z",
+            Some("2026-01-05T00:00:00Z"),
+            json!({}),
+        );
+        insert_pr(&conn, 2, "alice", "2026-01-01T00:00:00Z", None, json!({}));
+        conn.execute_batch(
+            "INSERT INTO review_requests (repo, pr_number, reviewer, is_team, requested_at) VALUES
+                ('r', 1, 'bob', 0, '2026-01-02T00:00:00Z'),
+                ('r', 1, 'bob', 0, '2026-01-03T12:00:00Z'),
+                ('r', 1, 'carol', 0, '2026-01-02T00:00:00Z'),
+                ('r', 1, 'core', 1, '2026-01-02T00:00:00Z'),
+                ('r', 1, 'docs', 1, '2026-01-04T12:00:00Z'),
+                ('r', 2, 'bob', 0, '2026-01-02T00:00:00Z');
+             INSERT INTO pr_reviews (id, repo, pr_number, state, author, submitted_at, data) VALUES
+                (1, 'r', 1, 'APPROVED', 'bob', '2026-01-03T00:00:00Z', '{}'),
+                (2, 'r', 1, 'COMMENTED', 'carol', '2026-01-01T12:00:00Z', '{}'),
+                (3, 'r', 1, 'APPROVED', 'dave', '2026-01-04T00:00:00Z', '{}'),
+                (4, 'r', 2, 'APPROVED', 'bob', '2026-01-03T00:00:00Z', '{}');",
+        )
+        .unwrap();
+
+        compute_review_fanout(&conn, "2026-01-01").unwrap();
+
+        let rows: Vec<(String, i64, i64, i64, i64)> = conn
+            .prepare(
+                "SELECT date, requested, responded, team_requested, team_responded FROM review_fanout",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows, [("2026-01-05".to_string(), 2, 1, 2, 1)]);
+    }
+
+    #[test]
+    fn triage_sla_counts_first_outside_touch_within_the_window() {
+        let conn = db();
+        let created = "2026-01-01T00:00:00Z";
+        for number in 1..=4 {
+            conn.execute(
+                "INSERT INTO issues (id, repo, number, state, author, created_at, updated_at, data)
+                 VALUES (?1, 'r', ?1, 'open', 'alice', ?2, ?2, '{}')",
+                params![number, created],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO issues (id, repo, number, state, author, created_at, updated_at, data)
+             VALUES (5, 'r', 5, 'open', 'alice', ?1, ?1, '{}')",
+            params![Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+        conn.execute_batch(
+            "INSERT INTO daily_metrics (date, repo) VALUES ('2026-01-01', 'r'), (date('now'), 'r');
+             INSERT INTO issue_events (id, repo, issue_number, event, actor, label, created_at, data) VALUES
+                (1, 'r', 1, 'labeled', 'bob', 'bug', '2026-01-01T10:00:00Z', '{}'),
+                (2, 'r', 2, 'labeled', 'triage[bot]', 'bug', '2026-01-01T02:00:00Z', '{}'),
+                (3, 'r', 4, 'labeled', 'bob', 'bug', '2026-01-02T23:00:00Z', '{}');
+             INSERT INTO issue_comments (id, repo, issue_number, author, created_at, updated_at, data) VALUES
+                (1, 'r', 2, 'alice', '2026-01-01T01:00:00Z', '2026-01-01T01:00:00Z', '{}'),
+                (2, 'r', 2, 'bob', '2026-01-03T12:00:00Z', '2026-01-03T12:00:00Z', '{}');",
+        )
+        .unwrap();
+
+        compute_triage_sla(&conn, &MetricsConfig::default(), "2026-01-01").unwrap();
+
+        let rate = |date: &str| -> Option<f64> {
+            conn.query_row(
+                "SELECT triage_sla_met_rate FROM daily_metrics WHERE repo = 'r' AND date = ?1",
+                params![date],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(rate("2026-01-01"), Some(0.5));
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        assert_eq!(rate(&today), None);
+    }
+
+    #[test]
+    fn commit_lead_time_runs_to_the_first_release_after_it() {
+        let conn = db();
+        let now = Utc::now();
+        let hours_ago = |h: i64| (now - Duration::hours(h)).to_rfc3339();
+        for (sha, age) in [("c1", 60), ("c2", 10), ("c3", 30)] {
+            conn.execute(
+                "INSERT INTO commits (sha, repo, author, date) VALUES (?1, 'r', 'alice', ?2)",
+                params![sha, hours_ago(age)],
+            )
+            .unwrap();
+        }
+        for (id, draft, published_at) in [
+            (1, 0, hours_ago(48)),
+            (2, 1, hours_ago(5)),
+            (3, 0, now.to_rfc3339()),
+        ] {
+            conn.execute(
+                "INSERT INTO releases (id, repo, tag_name, draft, created_at, published_at)
+                 VALUES (?1, 'r', ?2, ?3, ?4, ?4)",
+                params![id, format!("v{}", id), draft, published_at],
+            )
+            .unwrap();
+        }
+
+        compute_recent(&conn, &MetricsConfig::default());
+        let lead: f64 = today(&conn, "round(avg_commit_to_release_hours, 6)");
+        assert_eq!(lead, 20.0);
+    }
+
+    #[test]
+    fn percentile_interpolates_and_handles_small_inputs() {
+        assert_eq!(percentile(&[], 0.5), None);
+        assert_eq!(percentile(&[7.0], 0.9), Some(7.0));
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(percentile(&values, 0.0), Some(1.0));
+        assert_eq!(percentile(&values, 0.5), Some(3.0));
+        assert_eq!(percentile(&values, 0.9), Some(4.6));
+        assert_eq!(percentile(&values, 1.0), Some(5.0));
+    }
+
+    #[test]
+    fn branch_cleanup_counts_deleted_same_repo_branches_and_survives_trim() {
+        let conn = db();
+        let now = Utc::now().to_rfc3339();
+        let pr = |head: i64| json!({ "head": { "repo": { "id": head } }, "base": { "repo": { "id": 1 } } });
+        insert_pr(&conn, 1, "alice", &days_ago(1), Some(&now), pr(1));
+        insert_pr(&conn, 2, "alice", &days_ago(1), Some(&now), pr(1));
+        insert_pr(&conn, 3, "alice", &days_ago(1), Some(&now), pr(2));
+        for (id, number) in [(1, 1), (2, 3)] {
+            conn.execute(
+                "INSERT INTO issue_events (id, repo, issue_number, event, actor, created_at, data)
+                 VALUES (?1, 'r', ?2, 'head_ref_deleted', 'alice', ?3, '{}')",
+                params![id, number, now],
+            )
+            .unwrap();
+        }
+
+        compute_recent(&conn, &MetricsConfig::default());
+        assert_eq!(today::<f64>(&conn, "branch_cleanup_rate"), 0.5);
+
+        let tomorrow = (Utc::now() + Duration::days(1))
+            .format("%Y-%m-%d")
+            .to_string();
+        crate::db_utils::trim_raw(&conn, &tomorrow).unwrap();
+        compute_recent(&conn, &MetricsConfig::default());
+        assert_eq!(today::<f64>(&conn, "branch_cleanup_rate"), 0.5);
+    }
+
+    #[test]
+    fn author_wip_counts_overlapping_open_prs() {
+        let conn = db();
+        for (number, author, created, closed) in [
+            (1, "alice", "2026-01-01T09:00:00Z", "2026-01-04T09:00:00Z"),
+            (2, "alice", "2026-01-02T09:00:00Z", "2026-01-03T09:00:00Z"),
+            (3, "bob", "2026-01-02T09:00:00Z", "2026-01-02T18:00:00Z"),
+            (4, "bob", "2026-01-02T09:00:00Z", "2026-01-05T09:00:00Z"),
+            (
+                5,
+                "renovate[bot]",
+                "2026-01-01T09:00:00Z",
+                "2026-01-05T09:00:00Z",
+            ),
+        ] {
+            insert_pr(&conn, number, author, created, None, json!({}));
+            conn.execute(
+                "UPDATE pull_requests SET state = 'closed', closed_at = ?2 WHERE number = ?1",
+                params![number, closed],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO daily_metrics (date, repo) VALUES ('2026-01-02', 'r')",
+            [],
+        )
+        .unwrap();
+
+        compute_author_wip(&conn, "2026-01-01").unwrap();
+
+        let wip: Vec<(String, String, i64)> = conn
+            .prepare("SELECT date, author, open_prs FROM author_wip ORDER BY date, author")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let row = |d: &str, a: &str, n| (format!("2026-01-0{}", d), a.to_string(), n);
+        assert_eq!(
+            wip,
+            [
+                row("1", "alice", 1),
+                row("2", "alice", 2),
+                row("2", "bob", 1),
+                row("3", "alice", 1),
+                row("3", "bob", 1),
+                row("4", "bob", 1)
+            ]
+        );
+
+        let (max, avg): (i64, f64) = conn
+            .query_row(
+                "SELECT max_author_wip, avg_author_wip FROM daily_metrics WHERE date = '2026-01-02'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((max, avg), (2, 1.5));
+    }
+
+    #[test]
+    fn merge_batches_chain_merges_within_the_gap_per_base_branch() {
+        let conn = db();
+        for (number, base, merged_at, sha) in [
+            (1, "main", "2026-01-01T10:00:00Z", Some("a")),
+            (2, "main", "2026-01-01T10:03:00Z", Some("b")),
+            (3, "main", "2026-01-01T10:07:00Z", Some("c")),
+            (4, "main", "2026-01-01T10:20:00Z", Some("d")),
+            (5, "release", "2026-01-01T10:01:00Z", Some("e")),
+            (6, "main", "2026-01-01T10:21:00Z", None),
+        ] {
+            insert_pr(
+                &conn,
+                number,
+                "alice",
+                "2026-01-01T00:00:00Z",
+                Some(merged_at),
+                json!({ "base": { "ref": base }, "merge_commit_sha": sha }),
+            );
+        }
+        let batches = |conn: &Connection| -> Vec<(String, String, i64)> {
+            conn.prepare(
+                "SELECT base_ref, batch_time, batch_size FROM merge_batches ORDER BY base_ref, batch_time",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+        };
+        let batch = |base: &str, time: &str, size| {
+            (
+                base.to_string(),
+                format!("2026-01-01T{}:00+00:00", time),
+                size,
+            )
+        };
+        let expected = [
+            batch("main", "10:00", 3),
+            batch("main", "10:20", 1),
+            batch("release", "10:01", 1),
+        ];
+
+        compute_merge_batches(&conn).unwrap();
+        assert_eq!(batches(&conn), expected);
+
+        crate::db_utils::trim_raw(&conn, "2026-02-01").unwrap();
+        compute_merge_batches(&conn).unwrap();
+        assert_eq!(batches(&conn), expected);
+    }
+
+    #[test]
+    fn bypassed_reviews_count_requests_outstanding_at_merge() {
+        let conn = db();
+        let now = Utc::now();
+        let at = |h: i64| (now + Duration::hours(h)).to_rfc3339();
+        let merged = at(0);
+        for number in 1..=6 {
+            insert_pr(
+                &conn,
+                number,
+                "alice",
+                &days_ago(2),
+                Some(&merged),
+                json!({}),
+            );
+        }
+        for (pr, reviewer, is_team, requested) in [
+            (1, "bob", 0, -10),
+            (2, "carol", 0, -10),
+            (3, "dave", 0, -10),
+            (4, "erin", 0, -10),
+            (5, "frank", 0, -10),
+            (5, "frank", 0, 1),
+            (6, "core", 1, -10),
+        ] {
+            conn.execute(
+                "INSERT INTO review_requests (repo, pr_number, reviewer, is_team, requested_at)
+                 VALUES ('r', ?1, ?2, ?3, ?4)",
+                params![pr, reviewer, is_team, at(requested)],
+            )
+            .unwrap();
+        }
+        for (id, pr, reviewer, submitted) in [(1, 2, "carol", -5), (2, 4, "erin", -12)] {
+            conn.execute(
+                "INSERT INTO pr_reviews (id, repo, pr_number, state, author, submitted_at, data)
+                 VALUES (?1, 'r', ?2, 'APPROVED', ?3, ?4, '{}')",
+                params![id, pr, reviewer, at(submitted)],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO issue_events (id, repo, issue_number, event, actor, created_at, data)
+             VALUES (1, 'r', 3, 'review_request_removed', 'alice', ?1, ?2)",
+            params![
+                at(-8),
+                json!({ "requested_reviewer": { "login": "Dave" } }).to_string()
+            ],
+        )
+        .unwrap();
+
+        compute_recent(&conn, &MetricsConfig::default());
+        assert_eq!(today::<i64>(&conn, "bypassed_reviews"), 3);
+
+        let tomorrow = (now + Duration::days(2)).format("%Y-%m-%d").to_string();
+        crate::db_utils::trim_raw(&conn, &tomorrow).unwrap();
+        compute_recent(&conn, &MetricsConfig::default());
+        assert_eq!(today::<i64>(&conn, "bypassed_reviews"), 3);
+    }
+}
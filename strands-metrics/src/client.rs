@@ -1,13 +1,84 @@
-use anyhow::Result;
-use chrono::{DateTime, Datelike, Utc};
-use http::header::ACCEPT;
+use crate::config::MetricsConfig;
+use crate::cost::{self, SyncPhase, SYNC_PHASES};
+use crate::dry_run::PatchOp;
+use crate::labels::{label_names, upsert_item_labels};
+use crate::links::{closing_issue_refs, upsert_pr_closes_issue};
+use crate::preflight;
+use crate::progress;
+use crate::redact::redact_payload;
+use crate::repo_filter::{RepoFilter, RepoOrder};
+use crate::store::{BranchProtectionRow, IssueRow, MetricsStore, PullRequestRow};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use http::header::{ACCEPT, USER_AGENT};
 use http::StatusCode;
 use indicatif::ProgressBar;
 use octocrab::{models, Octocrab, OctocrabBuilder};
-use rusqlite::{params, Connection};
-use serde::Deserialize;
-use serde_json::Value;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+
+/// Stored as the author/actor when GitHub returns no user, matching the login GitHub itself
+/// uses for deleted accounts.
+pub const GHOST_AUTHOR: &str = "ghost";
+
+/// How a failed GitHub request should be treated.
+#[derive(Debug, PartialEq)]
+enum ApiErrorKind {
+    /// 404/410: the resource doesn't exist (or the feature is off for the repo).
+    Missing,
+    /// 403 because the token lacks a scope or repo role.
+    PermissionDenied,
+    /// 403/429 from the primary or secondary rate limit. Never skipped.
+    RateLimited,
+    Other,
+}
+
+/// GitHub logins are case-insensitive, so every stored author/actor is lowercased to keep
+/// `Alice` and `alice` from counting as two people.
+pub fn normalize_author(login: &str) -> String {
+    login.to_lowercase()
+}
+
+/// Where GitHub looks for a single PR template, in the order it checks them.
+const PR_TEMPLATE_PATHS: &[&str] = &[
+    ".github/pull_request_template.md",
+    "pull_request_template.md",
+    "docs/pull_request_template.md",
+];
+
+/// Parses a GitHub timestamp. Accepts RFC3339 plus the offset-less and space-separated forms
+/// some endpoints return (read as UTC). `None` means the caller should skip the item rather
+/// than guess a date.
+pub fn parse_gh_ts(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+        .map(|naive| naive.and_utc())
+}
+
+/// Where a repo's sync starts: its cursor, or `initial_window_days` back from `now` on a first
+/// sync, or the beginning when there is no window or a windowed repo is being backfilled. The
+/// flag is set when this opens a new window, whose floor the caller records.
+fn sync_since(
+    cursor: Option<DateTime<Utc>>,
+    windowed: bool,
+    initial_window_days: Option<u32>,
+    now: DateTime<Utc>,
+) -> (DateTime<Utc>, bool) {
+    match (cursor, initial_window_days) {
+        (Some(_), None) if windowed => (DateTime::UNIX_EPOCH, false),
+        (Some(cursor), _) => (cursor, false),
+        (None, None) => (DateTime::UNIX_EPOCH, false),
+        (None, Some(days)) => (now - chrono::Duration::days(days as i64), true),
+    }
+}
 
 #[derive(Deserialize, Debug)]
 struct SimpleUser {
@@ -22,13 +93,118 @@ struct StarEntry {
 
 pub struct GitHubClient<'a> {
     pub gh: Octocrab,
-    db: &'a mut Connection,
+    db: &'a mut dyn MetricsStore,
     pb: ProgressBar,
+    repos_synced: usize,
+    api_requests: Cell<u64>,
+    repo_filter: RepoFilter,
+    user_agent: String,
+    redact_bodies: bool,
+    repo_order: RepoOrder,
+    initial_window_days: Option<u32>,
 }
 
 impl<'a> GitHubClient<'a> {
-    pub fn new(gh: Octocrab, db: &'a mut Connection, pb: ProgressBar) -> Self {
-        Self { gh, db, pb }
+    pub fn new(gh: Octocrab, db: &'a mut dyn MetricsStore, pb: ProgressBar) -> Self {
+        Self {
+            gh,
+            db,
+            pb,
+            repos_synced: 0,
+            api_requests: Cell::new(0),
+            repo_filter: RepoFilter::default(),
+            user_agent: MetricsConfig::default().client.user_agent,
+            redact_bodies: false,
+            repo_order: RepoOrder::default(),
+            initial_window_days: None,
+        }
+    }
+
+    /// Restricts `sync_org` / `sweep_org` to repos the filter allows.
+    pub fn set_repo_filter(&mut self, filter: RepoFilter) {
+        self.repo_filter = filter;
+    }
+
+    /// Order `sync_org` visits repos in.
+    pub fn set_repo_order(&mut self, order: RepoOrder) {
+        self.repo_order = order;
+    }
+
+    /// How far back a repo's first sync reaches, in days; `None` syncs its whole history and
+    /// also backfills repos whose first sync was windowed.
+    pub fn set_initial_window(&mut self, days: Option<u32>) {
+        self.initial_window_days = days;
+    }
+
+    /// `User-Agent` for the extra clients built during sync; `gh` is expected to carry it already.
+    pub fn set_user_agent(&mut self, user_agent: &str) {
+        self.user_agent = user_agent.to_string();
+    }
+
+    /// Drops titles and bodies from the stored `data` of issues, PRs, reviews, comments,
+    /// discussions, issue events and org event payloads written from now on. Rows synced
+    /// earlier keep theirs.
+    pub fn set_redact_bodies(&mut self, redact: bool) {
+        self.redact_bodies = redact;
+    }
+
+    /// The `data` column for an API payload, redacted if `set_redact_bodies` is on.
+    fn payload_json<T: Serialize>(&self, item: &T) -> Result<String> {
+        if !self.redact_bodies {
+            return Ok(serde_json::to_string(item)?);
+        }
+        let mut value = serde_json::to_value(item)?;
+        redact_payload(&mut value);
+        Ok(serde_json::to_string(&value)?)
+    }
+
+    /// Changes recorded instead of written when running with `dry_run`.
+    pub fn take_patch(&self) -> Vec<PatchOp> {
+        self.db.take_patch()
+    }
+
+    /// Repos fully processed by the last `sync_org` / `sweep_org`, including a partial run.
+    pub fn repos_synced(&self) -> usize {
+        self.repos_synced
+    }
+
+    /// GitHub API requests made so far, not counting the free rate-limit checks.
+    pub fn api_requests(&self) -> u64 {
+        self.api_requests.get()
+    }
+
+    /// Adds the requests made since `before` to the phase's running total in `app_state`.
+    /// Runs one sync stage and adds the API calls it made to the phase's running count.
+    async fn run_phase(
+        &self,
+        phase: SyncPhase,
+        org: &str,
+        repo: &str,
+        stage: impl Future<Output = Result<()>>,
+    ) -> Result<()> {
+        let before = self.api_requests.get();
+        stage.await?;
+        self.record_api_calls(phase.name(), org, repo, before)
+    }
+
+    fn record_api_calls(&self, phase: &str, org: &str, repo: &str, before: u64) -> Result<()> {
+        let key = cost::calls_key(phase, org, repo);
+        let total: u64 = self
+            .db
+            .get_state(&key)?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        self.db.set_state(
+            &key,
+            &(total + self.api_requests.get() - before).to_string(),
+        )?;
+        Ok(())
+    }
+
+    /// Counts one GitHub API request toward `api_requests`. Called next to each request; the
+    /// rate-limit checks themselves are free and aren't counted.
+    fn count_request(&self) {
+        self.api_requests.set(self.api_requests.get() + 1);
     }
 
     pub async fn check_limits(&self) -> Result<()> {
@@ -39,39 +215,98 @@ impl<'a> GitHubClient<'a> {
             let reset = core.reset;
             let now = Utc::now().timestamp() as u64;
             let wait_secs = reset.saturating_sub(now) + 10;
-            self.pb
-                .set_message(format!("Rate limit low. Sleeping {}s...", wait_secs));
+            progress::set_status(
+                &self.pb,
+                format!("Rate limit low. Sleeping {}s...", wait_secs),
+            );
             tokio::time::sleep(tokio::time::Duration::from_secs(wait_secs)).await;
         }
         Ok(())
     }
 
+    /// Calls `/user` before a long run so a bad token fails in seconds, not an hour in. A 401
+    /// aborts; missing scopes for optional stages only warn, since those stages skip per repo.
+    async fn preflight(&self) -> Result<()> {
+        self.count_request();
+        let response = self
+            .gh
+            ._get("/user")
+            .await
+            .context("GitHub preflight request to /user failed")?;
+        match response.status() {
+            StatusCode::UNAUTHORIZED => anyhow::bail!(
+                "GITHUB_TOKEN was rejected by GitHub (401); check it hasn't expired or been revoked"
+            ),
+            status if !status.is_success() => {
+                anyhow::bail!("GitHub preflight request to /user returned {}", status)
+            }
+            _ => {}
+        }
+
+        let Some(header) = response
+            .headers()
+            .get(preflight::OAUTH_SCOPES_HEADER)
+            .and_then(|v| v.to_str().ok())
+        else {
+            tracing::info!(
+                "Token reports no OAuth scopes (fine-grained or app token); not checking scopes"
+            );
+            return Ok(());
+        };
+        let granted = preflight::parse_scopes(header);
+        for (stage, needed) in preflight::stages_missing_scopes(&granted) {
+            tracing::warn!(
+                "Token lacks {} scope; {} will be skipped",
+                needed.join(" or "),
+                stage
+            );
+        }
+        Ok(())
+    }
+
     pub async fn sync_org(&mut self, org: &str) -> Result<()> {
+        self.repos_synced = 0;
+        self.preflight().await?;
+        self.sync_org_events(org).await?;
         self.check_limits().await?;
-        let repos = self.fetch_repos(org).await?;
+        let mut repos = self.fetch_repos(org).await?;
+        self.order_repos(org, &mut repos)?;
         for repo in repos {
-            self.pb.set_message(format!("Syncing {}", repo.name));
+            progress::set_status(&self.pb, format!("Syncing {}", repo.name));
             self.sync_repo(org, &repo).await?;
+            self.repos_synced += 1;
         }
+        let runs: u64 = self
+            .db
+            .get_state(cost::SYNC_RUNS_KEY)?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        self.db
+            .set_state(cost::SYNC_RUNS_KEY, &(runs + 1).to_string())?;
         Ok(())
     }
 
     pub async fn sweep_org(&mut self, org: &str) -> Result<()> {
+        self.repos_synced = 0;
+        self.preflight().await?;
         self.check_limits().await?;
         let repos = self.fetch_repos(org).await?;
         for repo in repos {
-            self.pb.set_message(format!("Sweeping {}", repo.name));
+            progress::set_status(&self.pb, format!("Sweeping {}", repo.name));
             self.sweep_repo(org, &repo).await?;
+            self.repos_synced += 1;
         }
         Ok(())
     }
 
     async fn fetch_repos(&self, org: &str) -> Result<Vec<models::Repository>> {
         let mut repos = Vec::new();
+        self.count_request();
         let mut page = self.gh.orgs(org).list_repos().per_page(100).send().await?;
         repos.extend(page.items);
         while let Some(next) = page.next {
             self.check_limits().await?;
+            self.count_request();
             page = self.gh.get_page(&Some(next)).await?.unwrap();
             repos.extend(page.items);
         }
@@ -80,19 +315,119 @@ impl<'a> GitHubClient<'a> {
             !r.archived.unwrap_or(false)
                 && !r.private.unwrap_or(false)
                 && !r.name.starts_with("private_")
+                && self.repo_filter.allows(&r.name)
         });
 
         Ok(repos)
     }
 
+    fn order_repos(&self, org: &str, repos: &mut [models::Repository]) -> Result<()> {
+        match self.repo_order {
+            RepoOrder::Name => repos.sort_by(|a, b| a.name.cmp(&b.name)),
+            RepoOrder::Activity => {
+                repos.sort_by(|a, b| b.pushed_at.cmp(&a.pushed_at).then(a.name.cmp(&b.name)))
+            }
+            RepoOrder::LastSynced => {
+                let mut last_sync = HashMap::new();
+                for repo in repos.iter() {
+                    let synced_at = self
+                        .db
+                        .get_state(&format!("last_sync_{}_{}", org, repo.name))?
+                        .and_then(|v| DateTime::parse_from_rfc3339(&v).ok())
+                        .map(|d| d.with_timezone(&Utc));
+                    last_sync.insert(repo.name.clone(), synced_at);
+                }
+                // `None` sorts before `Some`, so never-synced repos go first.
+                repos.sort_by(|a, b| {
+                    last_sync[&a.name]
+                        .cmp(&last_sync[&b.name])
+                        .then(a.name.cmp(&b.name))
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Recent public org events (membership, repo creation/deletion, ...). GitHub only serves
+    /// the last 90 days / 300 events, so each sync keeps whatever is new since the last one.
+    async fn sync_org_events(&self, org: &str) -> Result<()> {
+        self.check_limits().await?;
+        let route = format!("/orgs/{}/events", org);
+        self.count_request();
+        let result: Result<octocrab::Page<Value>, _> =
+            self.gh.get(&route, Some(&json!({ "per_page": 100 }))).await;
+        // Org events only feed membership metrics, so losing them mustn't stop the repo sync.
+        let mut page = match result {
+            Ok(page) => page,
+            // 404 when the owner is a user account rather than an org
+            Err(e) if Self::is_missing_resource(&e) => return Ok(()),
+            Err(e) if Self::classify_error(&e) == ApiErrorKind::PermissionDenied => {
+                self.record_skip(org, "org_events", "token can't read org events", &e)?;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        loop {
+            let next_page = page.next.clone();
+            for event in page.items {
+                let Some(id) = event.get("id").and_then(|v| v.as_str()) else {
+                    continue;
+                };
+                let event_type = event.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                let actor = event
+                    .get("actor")
+                    .and_then(|u| u.get("login"))
+                    .and_then(|l| l.as_str())
+                    .map(normalize_author)
+                    .unwrap_or_else(|| GHOST_AUTHOR.to_string());
+                // "org/repo" -> "repo", matching how every other table names repos
+                let repo = event
+                    .pointer("/repo/name")
+                    .and_then(|v| v.as_str())
+                    .map(|name| name.rsplit('/').next().unwrap_or(name));
+                let created_at = event
+                    .get("created_at")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let payload = event
+                    .get("payload")
+                    .map(|p| self.payload_json(p))
+                    .transpose()?;
+
+                self.db.upsert_row(
+                    "org_events",
+                    &[
+                        ("id", json!(id)),
+                        ("org", json!(org)),
+                        ("event_type", json!(event_type)),
+                        ("actor", json!(actor)),
+                        ("repo", json!(repo)),
+                        ("created_at", json!(created_at)),
+                        ("payload", json!(payload)),
+                    ],
+                )?;
+            }
+            if let Some(next) = next_page {
+                self.check_limits().await?;
+                self.count_request();
+                page = self.gh.get_page(&Some(next)).await?.unwrap();
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     async fn sweep_repo(&self, org: &str, repo: &models::Repository) -> Result<()> {
         let mut remote_open_numbers = HashSet::new();
         let route = format!("/repos/{}/{}/issues", org, repo.name);
+        self.count_request();
         let mut page: octocrab::Page<Value> = self
             .gh
             .get(
                 &route,
-                Some(&serde_json::json!({
+                Some(&json!({
                     "state": "open", "per_page": 100
                 })),
             )
@@ -107,26 +442,23 @@ impl<'a> GitHubClient<'a> {
             }
             if let Some(next) = next_page {
                 self.check_limits().await?;
+                self.count_request();
                 page = self.gh.get_page(&Some(next)).await?.unwrap();
             } else {
                 break;
             }
         }
 
-        let mut stmt = self.db.prepare(
-            "SELECT number FROM issues WHERE repo = ?1 AND state = 'open' AND closed_at IS NULL AND deleted_at IS NULL"
-        )?;
-        let local_open_nums: Vec<i64> = stmt
-            .query_map(params![repo.name], |row| row.get(0))?
-            .collect::<Result<Vec<_>, _>>()?;
+        let local_open_nums = self.db.open_issue_numbers(&repo.name)?;
 
         let now = Utc::now().to_rfc3339();
 
         for local_num in local_open_nums {
             if !remote_open_numbers.contains(&local_num) {
-                self.check_limits().await?;
                 let issue_route = format!("/repos/{}/{}/issues/{}", org, repo.name, local_num);
 
+                self.check_limits().await?;
+                self.count_request();
                 let result: Result<Value, _> = self.gh.get(&issue_route, None::<&()>).await;
 
                 match result {
@@ -136,17 +468,19 @@ impl<'a> GitHubClient<'a> {
                             .and_then(|s| s.as_str())
                             .unwrap_or("closed");
                         let closed_at = json.get("closed_at").and_then(|s| s.as_str());
-                        self.db.execute(
-                            "UPDATE issues SET state = ?1, closed_at = ?2 WHERE repo = ?3 AND number = ?4",
-                            params![state, closed_at, repo.name, local_num]
+                        self.db.update_rows(
+                            "issues",
+                            &[("state", json!(state)), ("closed_at", json!(closed_at))],
+                            &[("repo", json!(repo.name)), ("number", json!(local_num))],
                         )?;
                     }
                     Err(e) => {
                         if Self::is_missing_resource(&e) {
                             // Explicit 404/410 means deleted/missing
-                            self.db.execute(
-                                "UPDATE issues SET state = 'deleted', deleted_at = ?1 WHERE repo = ?2 AND number = ?3",
-                                params![now, repo.name, local_num]
+                            self.db.update_rows(
+                                "issues",
+                                &[("state", json!("deleted")), ("deleted_at", json!(now))],
+                                &[("repo", json!(repo.name)), ("number", json!(local_num))],
                             )?;
                         } else {
                             // Any other error (500, 502, timeout) is a crash.
@@ -162,38 +496,52 @@ impl<'a> GitHubClient<'a> {
     async fn sync_repo(&mut self, org: &str, repo: &models::Repository) -> Result<()> {
         let repo_name = &repo.name;
         let last_sync_key = format!("last_sync_{}_{}", org, repo_name);
+        // Set while a repo's history only reaches back to its windowed first sync.
+        let window_key = format!("initial_window_{}_{}", org, repo_name);
 
-        let since: DateTime<Utc> = self
+        let cursor = self
             .db
-            .query_row(
-                "SELECT value FROM app_state WHERE key = ?1",
-                params![last_sync_key],
-                |row| {
-                    let s: String = row.get(0)?;
-                    Ok(DateTime::parse_from_rfc3339(&s)
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .unwrap_or(Utc::now()))
-                },
-            )
-            .unwrap_or_else(|_| {
-                DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z")
-                    .unwrap()
-                    .with_timezone(&Utc)
-            });
-
-        self.sync_pull_requests(org, repo_name, since).await?;
-        self.sync_issues(org, repo_name, since).await?;
-        self.sync_issue_comments(org, repo_name, since).await?;
-        self.sync_pr_comments(org, repo_name, since).await?;
-        self.sync_stars(org, repo).await?;
-        self.sync_commits(org, repo_name, since).await?;
-        self.sync_workflows(org, repo_name, since).await?;
-
-        let now_str = Utc::now().to_rfc3339();
-        self.db.execute(
-            "INSERT OR REPLACE INTO app_state (key, value) VALUES (?1, ?2)",
-            params![last_sync_key, now_str],
-        )?;
+            .get_state(&last_sync_key)?
+            // An unreadable cursor means a full resync, not skipping everything up to now.
+            .and_then(|s| parse_gh_ts(&s));
+        let windowed = self.db.get_state(&window_key)?.is_some();
+        let (since, opens_window) =
+            sync_since(cursor, windowed, self.initial_window_days, Utc::now());
+        if opens_window {
+            self.db.set_state(&window_key, &since.to_rfc3339())?;
+        }
+
+        // Phases run in `SYNC_PHASES` order; the match makes every phase name its stage.
+        for &phase in SYNC_PHASES {
+            let stage: Pin<Box<dyn Future<Output = Result<()>> + '_>> = match phase {
+                SyncPhase::PullRequests => Box::pin(self.sync_pull_requests(org, repo_name, since)),
+                SyncPhase::Issues => Box::pin(self.sync_issues(org, repo_name, since)),
+                SyncPhase::IssueComments => {
+                    Box::pin(self.sync_issue_comments(org, repo_name, since))
+                }
+                SyncPhase::IssueEvents => Box::pin(self.sync_issue_events(org, repo_name, since)),
+                SyncPhase::Discussions => Box::pin(self.sync_discussions(org, repo_name, since)),
+                SyncPhase::PrComments => Box::pin(self.sync_pr_comments(org, repo_name, since)),
+                SyncPhase::Stars => Box::pin(self.sync_stars(org, repo)),
+                SyncPhase::Commits => Box::pin(self.sync_commits(org, repo_name, since)),
+                SyncPhase::Workflows => Box::pin(self.sync_workflows(org, repo_name, since)),
+                SyncPhase::CheckRuns => Box::pin(self.sync_check_runs(org, repo_name, since)),
+                SyncPhase::Releases => Box::pin(self.sync_releases(org, repo_name)),
+                SyncPhase::Milestones => Box::pin(self.sync_milestones(org, repo_name)),
+                SyncPhase::DependabotAlerts => {
+                    Box::pin(self.sync_dependabot_alerts(org, repo_name))
+                }
+                SyncPhase::BranchProtection => Box::pin(self.sync_branch_protection(org, repo)),
+                SyncPhase::PrTemplate => Box::pin(self.sync_pr_template(org, repo_name)),
+            };
+            self.run_phase(phase, org, repo_name, stage).await?;
+        }
+
+        self.db
+            .set_state(&last_sync_key, &Utc::now().to_rfc3339())?;
+        if windowed && self.initial_window_days.is_none() {
+            self.db.delete_state(&window_key)?;
+        }
 
         Ok(())
     }
@@ -202,15 +550,22 @@ impl<'a> GitHubClient<'a> {
         self.check_limits().await?;
 
         let route = format!("/repos/{}/{}/commits", org, repo);
-        let mut page: octocrab::Page<Value> = self
+        self.count_request();
+        let result: Result<octocrab::Page<Value>, _> = self
             .gh
             .get(
                 &route,
-                Some(&serde_json::json!({
+                Some(&json!({
                     "since": since.to_rfc3339(), "per_page": 100
                 })),
             )
-            .await?;
+            .await;
+        let mut page = match result {
+            Ok(page) => page,
+            // Brand-new repos have no commits yet; that's zero commits, not a failure.
+            Err(e) if Self::is_empty_repo(&e) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
 
         loop {
             let next_page = page.next.clone();
@@ -225,12 +580,7 @@ impl<'a> GitHubClient<'a> {
 
             for sha in shas {
                 // Check if exists
-                let exists: bool = self
-                    .db
-                    .query_row("SELECT 1 FROM commits WHERE sha = ?1", params![sha], |_| {
-                        Ok(true)
-                    })
-                    .unwrap_or(false);
+                let exists = self.db.has_commit(&sha).unwrap_or(false);
 
                 if !exists {
                     // We must fetch details to get stats (additions/deletions)
@@ -238,6 +588,7 @@ impl<'a> GitHubClient<'a> {
                     self.check_limits().await?;
 
                     let detail_route = format!("/repos/{}/{}/commits/{}", org, repo, sha);
+                    self.count_request();
                     let detail: Value = self.gh.get(&detail_route, None::<&()>).await?;
 
                     let author = detail
@@ -245,7 +596,8 @@ impl<'a> GitHubClient<'a> {
                         .and_then(|c| c.get("author"))
                         .and_then(|a| a.get("name"))
                         .and_then(|n| n.as_str())
-                        .unwrap_or("unknown");
+                        .map(normalize_author)
+                        .unwrap_or_else(|| "unknown".to_string());
 
                     let date_str = detail
                         .get("commit")
@@ -269,16 +621,44 @@ impl<'a> GitHubClient<'a> {
                         .and_then(|m| m.as_str())
                         .unwrap_or("");
 
-                    self.db.execute(
-                        "INSERT OR REPLACE INTO commits (sha, repo, author, date, additions, deletions, message) 
-                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                        params![sha, repo, author, date_str, adds, dels, msg]
+                    self.db.upsert_row(
+                        "commits",
+                        &[
+                            ("sha", json!(sha)),
+                            ("repo", json!(repo)),
+                            ("author", json!(author)),
+                            ("date", json!(date_str)),
+                            ("additions", json!(adds)),
+                            ("deletions", json!(dels)),
+                            ("message", json!(msg)),
+                        ],
                     )?;
+
+                    // GitHub lists at most 300 files per commit; larger commits are partial.
+                    let files = detail.get("files").and_then(|f| f.as_array());
+                    for file in files.into_iter().flatten() {
+                        let Some(filename) = file.get("filename").and_then(|f| f.as_str()) else {
+                            continue;
+                        };
+                        let file_adds = file.get("additions").and_then(|v| v.as_i64()).unwrap_or(0);
+                        let file_dels = file.get("deletions").and_then(|v| v.as_i64()).unwrap_or(0);
+                        self.db.upsert_row(
+                            "commit_files",
+                            &[
+                                ("sha", json!(sha)),
+                                ("repo", json!(repo)),
+                                ("filename", json!(filename)),
+                                ("additions", json!(file_adds)),
+                                ("deletions", json!(file_dels)),
+                            ],
+                        )?;
+                    }
                 }
             }
 
             if let Some(next) = next_page {
                 self.check_limits().await?;
+                self.count_request();
                 page = self.gh.get_page(&Some(next)).await?.unwrap();
             } else {
                 break;
@@ -292,20 +672,30 @@ impl<'a> GitHubClient<'a> {
         let route = format!("/repos/{}/{}/actions/runs", org, repo);
         let created_filter = format!(">{}", since.format("%Y-%m-%d"));
 
+        self.count_request();
         let mut page: octocrab::Page<Value> = self
             .gh
             .get(
                 &route,
-                Some(&serde_json::json!({
+                Some(&json!({
                     "created": created_filter, "per_page": 100
                 })),
             )
             .await?;
 
+        // Runs come back newest first, so paging can stop at the newest run we already have.
+        // Runs still in progress are refetched so their conclusion gets filled in.
+        let stop_at = self.db.workflow_run_stop_id(repo, &since.to_rfc3339())?;
+
+        let mut keep_fetching = true;
         loop {
             let next_page = page.next.clone();
             for run in page.items {
                 let id = run.get("id").and_then(|v| v.as_i64()).unwrap_or(0);
+                if stop_at.is_some_and(|known| id <= known) {
+                    keep_fetching = false;
+                    break;
+                }
                 let name = run.get("name").and_then(|v| v.as_str()).unwrap_or("");
                 let head = run
                     .get("head_branch")
@@ -317,27 +707,35 @@ impl<'a> GitHubClient<'a> {
                     .unwrap_or("in_progress");
                 let created_at = run.get("created_at").and_then(|v| v.as_str()).unwrap_or("");
                 let updated_at = run.get("updated_at").and_then(|v| v.as_str()).unwrap_or("");
+                let run_started_at = run.get("run_started_at").and_then(|v| v.as_str());
 
-                let duration = if let (Some(start), Some(end)) = (
-                    run.get("created_at").and_then(|v| v.as_str()),
-                    run.get("updated_at").and_then(|v| v.as_str()),
-                ) {
-                    let s = DateTime::parse_from_rfc3339(start).unwrap_or(Utc::now().into());
-                    let e = DateTime::parse_from_rfc3339(end).unwrap_or(Utc::now().into());
-                    (e - s).num_milliseconds()
-                } else {
-                    0
+                let duration = match (parse_gh_ts(created_at), parse_gh_ts(updated_at)) {
+                    (Some(s), Some(e)) => (e - s).num_milliseconds(),
+                    _ => 0,
                 };
 
-                self.db.execute(
-                    "INSERT OR REPLACE INTO workflow_runs (id, repo, name, head_branch, conclusion, created_at, updated_at, duration_ms)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                    params![id, repo, name, head, conclusion, created_at, updated_at, duration]
+                self.db.upsert_row(
+                    "workflow_runs",
+                    &[
+                        ("id", json!(id)),
+                        ("repo", json!(repo)),
+                        ("name", json!(name)),
+                        ("head_branch", json!(head)),
+                        ("conclusion", json!(conclusion)),
+                        ("created_at", json!(created_at)),
+                        ("updated_at", json!(updated_at)),
+                        ("duration_ms", json!(duration)),
+                        ("run_started_at", json!(run_started_at)),
+                    ],
                 )?;
             }
 
+            if !keep_fetching {
+                break;
+            }
             if let Some(next) = next_page {
                 self.check_limits().await?;
+                self.count_request();
                 page = self.gh.get_page(&Some(next)).await?.unwrap();
             } else {
                 break;
@@ -346,65 +744,446 @@ impl<'a> GitHubClient<'a> {
         Ok(())
     }
 
-    async fn sync_stars(&mut self, org: &str, repo: &models::Repository) -> Result<()> {
+    /// Check runs for each commit synced since `since`. These include status checks posted by
+    /// external CI (CircleCI, Travis, ...), which `workflow_runs` doesn't see.
+    async fn sync_check_runs(&self, org: &str, repo: &str, since: DateTime<Utc>) -> Result<()> {
+        let shas = self.db.commit_shas_since(repo, &since.to_rfc3339())?;
+
+        for sha in shas {
+            let route = format!("/repos/{}/{}/commits/{}/check-runs", org, repo, sha);
+            // The response is an object with `total_count`, not a list, so pages are walked
+            // by number rather than through `Link` headers.
+            let mut page_number = 1;
+            loop {
+                self.check_limits().await?;
+                self.count_request();
+                let body: Value = match self
+                    .gh
+                    .get(
+                        &route,
+                        Some(&json!({ "per_page": 100, "page": page_number })),
+                    )
+                    .await
+                {
+                    Ok(body) => body,
+                    Err(e) if Self::is_missing_resource(&e) => break,
+                    Err(e) => return Err(e.into()),
+                };
+
+                let total = body
+                    .get("total_count")
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(0);
+                let runs = body
+                    .get("check_runs")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                let page_len = runs.len();
+                for run in runs {
+                    let id = run.get("id").and_then(|v| v.as_i64()).unwrap_or(0);
+                    let name = run.get("name").and_then(|v| v.as_str());
+                    let app = run
+                        .get("app")
+                        .and_then(|a| a.get("slug"))
+                        .and_then(|v| v.as_str());
+                    let status = run.get("status").and_then(|v| v.as_str());
+                    let conclusion = run.get("conclusion").and_then(|v| v.as_str());
+                    let started_at = run.get("started_at").and_then(|v| v.as_str());
+                    let completed_at = run.get("completed_at").and_then(|v| v.as_str());
+
+                    self.db.upsert_row(
+                        "check_runs",
+                        &[
+                            ("id", json!(id)),
+                            ("repo", json!(repo)),
+                            ("sha", json!(sha)),
+                            ("name", json!(name)),
+                            ("app", json!(app)),
+                            ("status", json!(status)),
+                            ("conclusion", json!(conclusion)),
+                            ("started_at", json!(started_at)),
+                            ("completed_at", json!(completed_at)),
+                        ],
+                    )?;
+                }
+
+                if page_len < 100 || page_number * 100 >= total {
+                    break;
+                }
+                page_number += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// All releases for the repo. There are few enough that each sync refetches them, which
+    /// also picks up edits and drafts that were published since.
+    async fn sync_releases(&self, org: &str, repo: &str) -> Result<()> {
+        self.check_limits().await?;
+        let route = format!("/repos/{}/{}/releases", org, repo);
+        self.count_request();
+        let mut page: octocrab::Page<Value> = self
+            .gh
+            .get(&route, Some(&json!({ "per_page": 100 })))
+            .await?;
+
+        loop {
+            let next_page = page.next.clone();
+            for release in page.items {
+                let id = release.get("id").and_then(|v| v.as_i64()).unwrap_or(0);
+                let tag = release
+                    .get("tag_name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let name = release.get("name").and_then(|v| v.as_str());
+                let draft = release
+                    .get("draft")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let prerelease = release
+                    .get("prerelease")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let created_at = release
+                    .get("created_at")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let published_at = release.get("published_at").and_then(|v| v.as_str());
+
+                self.db.upsert_row(
+                    "releases",
+                    &[
+                        ("id", json!(id)),
+                        ("repo", json!(repo)),
+                        ("tag_name", json!(tag)),
+                        ("name", json!(name)),
+                        ("draft", json!(draft)),
+                        ("prerelease", json!(prerelease)),
+                        ("created_at", json!(created_at)),
+                        ("published_at", json!(published_at)),
+                    ],
+                )?;
+            }
+            if let Some(next) = next_page {
+                self.check_limits().await?;
+                self.count_request();
+                page = self.gh.get_page(&Some(next)).await?.unwrap();
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// All milestones, open and closed. Like releases, there are few enough to refetch each sync.
+    async fn sync_milestones(&self, org: &str, repo: &str) -> Result<()> {
+        self.check_limits().await?;
+        let route = format!("/repos/{}/{}/milestones", org, repo);
+        self.count_request();
+        let mut page: octocrab::Page<Value> = self
+            .gh
+            .get(&route, Some(&json!({ "state": "all", "per_page": 100 })))
+            .await?;
+
+        loop {
+            let next_page = page.next.clone();
+            for milestone in page.items {
+                let id = milestone.get("id").and_then(|v| v.as_i64()).unwrap_or(0);
+                let number = milestone
+                    .get("number")
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(0);
+                let title = milestone
+                    .get("title")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let state = milestone
+                    .get("state")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let due_on = milestone.get("due_on").and_then(|v| v.as_str());
+                let created_at = milestone
+                    .get("created_at")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let closed_at = milestone.get("closed_at").and_then(|v| v.as_str());
+
+                self.db.upsert_row(
+                    "milestones",
+                    &[
+                        ("id", json!(id)),
+                        ("repo", json!(repo)),
+                        ("number", json!(number)),
+                        ("title", json!(title)),
+                        ("state", json!(state)),
+                        ("due_on", json!(due_on)),
+                        ("created_at", json!(created_at)),
+                        ("closed_at", json!(closed_at)),
+                    ],
+                )?;
+            }
+            if let Some(next) = next_page {
+                self.check_limits().await?;
+                self.count_request();
+                page = self.gh.get_page(&Some(next)).await?.unwrap();
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Dependabot security alerts in every state, so fixed and dismissed alerts keep the dates
+    /// needed to count what was open on past days.
+    async fn sync_dependabot_alerts(&self, org: &str, repo: &str) -> Result<()> {
+        self.check_limits().await?;
+        let route = format!("/repos/{}/{}/dependabot/alerts", org, repo);
+        self.count_request();
+        let result: Result<octocrab::Page<Value>, _> =
+            self.gh.get(&route, Some(&json!({ "per_page": 100 }))).await;
+        let mut page = match result {
+            Ok(page) => page,
+            // 404 when Dependabot alerts are disabled for the repo
+            Err(e) if Self::is_missing_resource(&e) => return Ok(()),
+            Err(e) if Self::classify_error(&e) == ApiErrorKind::PermissionDenied => {
+                self.record_skip(
+                    repo,
+                    "dependabot_alerts",
+                    "token lacks security_events access",
+                    &e,
+                )?;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        loop {
+            let next_page = page.next.clone();
+            for alert in page.items {
+                let id = alert.get("number").and_then(|v| v.as_i64()).unwrap_or(0);
+                let state = alert.get("state").and_then(|v| v.as_str()).unwrap_or("");
+                let severity = alert
+                    .pointer("/security_advisory/severity")
+                    .and_then(|v| v.as_str());
+                let package_name = alert
+                    .pointer("/dependency/package/name")
+                    .and_then(|v| v.as_str());
+                let cve_id = alert
+                    .pointer("/security_advisory/cve_id")
+                    .and_then(|v| v.as_str());
+                let created_at = alert
+                    .get("created_at")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let fixed_at = alert.get("fixed_at").and_then(|v| v.as_str());
+                let dismissed_at = alert.get("dismissed_at").and_then(|v| v.as_str());
+
+                self.db.upsert_row(
+                    "dependabot_alerts",
+                    &[
+                        ("id", json!(id)),
+                        ("repo", json!(repo)),
+                        ("state", json!(state)),
+                        ("severity", json!(severity)),
+                        ("package_name", json!(package_name)),
+                        ("cve_id", json!(cve_id)),
+                        ("created_at", json!(created_at)),
+                        ("fixed_at", json!(fixed_at)),
+                        ("dismissed_at", json!(dismissed_at)),
+                    ],
+                )?;
+            }
+            if let Some(next) = next_page {
+                self.check_limits().await?;
+                self.count_request();
+                page = self.gh.get_page(&Some(next)).await?.unwrap();
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores the repo's PR template, checking GitHub at most once a day. Repos without one
+    /// have their stored template removed.
+    async fn sync_pr_template(&self, org: &str, repo: &str) -> Result<()> {
+        if self.db.pr_template_fetched_today(repo)? {
+            return Ok(());
+        }
+
+        for path in PR_TEMPLATE_PATHS {
+            self.check_limits().await?;
+            self.count_request();
+            let result = self
+                .gh
+                .repos(org, repo)
+                .get_content()
+                .path(*path)
+                .send()
+                .await;
+            let mut items = match result {
+                Ok(items) => items,
+                Err(e) if Self::is_missing_resource(&e) || Self::is_empty_repo(&e) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let Some(body) = items
+                .take_items()
+                .into_iter()
+                .next()
+                .and_then(|c| c.decoded_content())
+            else {
+                continue;
+            };
+            self.db.upsert_row(
+                "pr_templates",
+                &[
+                    ("repo", json!(repo)),
+                    ("path", json!(path)),
+                    ("body", json!(body)),
+                    ("fetched_at", json!(Utc::now().to_rfc3339())),
+                ],
+            )?;
+            return Ok(());
+        }
+
+        self.db
+            .delete_rows("pr_templates", &[("repo", json!(repo))])?;
+        Ok(())
+    }
+
+    /// Snapshots the default branch's protection rules, writing a row only when they change.
+    async fn sync_branch_protection(&self, org: &str, repo: &models::Repository) -> Result<()> {
+        let Some(branch) = repo.default_branch.as_deref() else {
+            return Ok(());
+        };
+        self.check_limits().await?;
+        let route = format!(
+            "/repos/{}/{}/branches/{}/protection",
+            org, repo.name, branch
+        );
+
+        self.count_request();
+        let result: Result<Value, _> = self.gh.get(&route, None::<&()>).await;
+        let protection = match result {
+            Ok(json) => json,
+            // 404 means the branch has no protection rules
+            Err(e) if Self::is_missing_resource(&e) => Value::Null,
+            Err(e) if Self::classify_error(&e) == ApiErrorKind::PermissionDenied => {
+                self.record_skip(
+                    &repo.name,
+                    "branch_protection",
+                    "token lacks admin access",
+                    &e,
+                )?;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let required_reviewers = protection
+            .pointer("/required_pull_request_reviews/required_approving_review_count")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0);
+        let require_status_checks = protection
+            .get("required_status_checks")
+            .map(|v| !v.is_null())
+            .unwrap_or(false);
+        let require_linear_history = protection
+            .pointer("/required_linear_history/enabled")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let allow_force_push = protection
+            .pointer("/allow_force_pushes/enabled")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let latest = self
+            .db
+            .latest_branch_protection(&repo.name, branch)
+            .ok()
+            .flatten();
+
+        let current = BranchProtectionRow {
+            required_reviewers,
+            require_status_checks,
+            require_linear_history,
+            allow_force_push,
+        };
+        if latest != Some(current) {
+            self.db.upsert_row(
+                "branch_protection_snapshots",
+                &[
+                    ("repo", json!(repo.name)),
+                    ("branch", json!(branch)),
+                    ("required_reviewers", json!(required_reviewers)),
+                    ("require_status_checks", json!(require_status_checks)),
+                    ("require_linear_history", json!(require_linear_history)),
+                    ("allow_force_push", json!(allow_force_push)),
+                    ("synced_at", json!(Utc::now().to_rfc3339())),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    async fn sync_stars(&self, org: &str, repo: &models::Repository) -> Result<()> {
         self.check_limits().await?;
         let token = std::env::var("GITHUB_TOKEN").unwrap_or_default();
         let star_gh = OctocrabBuilder::new()
             .personal_token(token)
             .add_header(ACCEPT, "application/vnd.github.star+json".to_string())
+            .add_header(USER_AGENT, self.user_agent.clone())
             .build()?;
 
         let mut remote_users = HashSet::new();
 
+        self.count_request();
         let route = format!("/repos/{}/{}/stargazers", org, repo.name);
         let mut page: octocrab::Page<StarEntry> = star_gh
-            .get(&route, Some(&serde_json::json!({ "per_page": 100 })))
+            .get(&route, Some(&json!({ "per_page": 100 })))
             .await?;
 
         loop {
             let next_page = page.next.clone();
             for entry in page.items {
                 if let (Some(starred_at), Some(user)) = (entry.starred_at, entry.user) {
-                    remote_users.insert(user.login.clone());
-                    self.db.execute(
-                        "INSERT OR REPLACE INTO stargazers (repo, user, starred_at) VALUES (?1, ?2, ?3)",
-                        params![repo.name, user.login, starred_at.to_rfc3339()],
+                    let login = normalize_author(&user.login);
+                    self.db.upsert_row(
+                        "stargazers",
+                        &[
+                            ("repo", json!(repo.name)),
+                            ("user", json!(login)),
+                            ("starred_at", json!(starred_at.to_rfc3339())),
+                        ],
                     )?;
+                    remote_users.insert(login);
                 }
             }
             if let Some(next) = next_page {
                 self.check_limits().await?;
+                self.count_request();
                 page = star_gh.get_page(&Some(next)).await?.unwrap();
             } else {
                 break;
             }
         }
 
-        let mut stmt = self
-            .db
-            .prepare("SELECT user FROM stargazers WHERE repo = ?1")?;
-        let rows = stmt.query_map(params![repo.name], |row| row.get::<_, String>(0))?;
-
-        let mut to_delete = Vec::new();
-        for local_user in rows {
-            let u = local_user?;
+        for u in self.db.stargazers(&repo.name)? {
             if !remote_users.contains(&u) {
-                to_delete.push(u);
+                self.db.delete_rows(
+                    "stargazers",
+                    &[("repo", json!(repo.name)), ("user", json!(u))],
+                )?;
             }
         }
 
-        for u in to_delete {
-            self.db.execute(
-                "DELETE FROM stargazers WHERE repo = ?1 AND user = ?2",
-                params![repo.name, u],
-            )?;
-        }
-
         Ok(())
     }
 
     async fn sync_pull_requests(&self, org: &str, repo: &str, since: DateTime<Utc>) -> Result<()> {
         self.check_limits().await?;
+        self.count_request();
         let mut page = self
             .gh
             .pulls(org, repo)
@@ -427,7 +1206,7 @@ impl<'a> GitHubClient<'a> {
                     }
                 }
 
-                let json = serde_json::to_string(&pr)?;
+                let json = self.payload_json(&pr)?;
                 let pr_id = pr.id.0 as i64;
                 let pr_number = pr.number as i64;
                 let state_str = match pr.state {
@@ -436,23 +1215,43 @@ impl<'a> GitHubClient<'a> {
                     _ => "unknown",
                 };
 
-                self.db.execute(
-                    "INSERT OR REPLACE INTO pull_requests 
-                    (id, repo, number, state, author, title, created_at, updated_at, merged_at, closed_at, data) 
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-                    params![
-                        pr_id, repo, pr_number, state_str,
-                        pr.user.as_ref().map(|u| u.login.clone()).unwrap_or_default(),
-                        pr.title.unwrap_or_default(),
-                        pr.created_at.map(|d| d.to_rfc3339()).unwrap_or_default(),
-                        pr.updated_at.map(|d| d.to_rfc3339()).unwrap_or_default(),
-                        pr.merged_at.map(|t| t.to_rfc3339()),
-                        pr.closed_at.map(|t| t.to_rfc3339()),
-                        json
-                    ],
-                )?;
+                let created_at = pr.created_at.map(|d| d.to_rfc3339()).unwrap_or_default();
+                let updated_at = pr.updated_at.map(|d| d.to_rfc3339()).unwrap_or_default();
+                let merged_at = pr.merged_at.map(|t| t.to_rfc3339());
+                let closed_at = pr.closed_at.map(|t| t.to_rfc3339());
+                let author = pr
+                    .user
+                    .as_ref()
+                    .map(|u| normalize_author(&u.login))
+                    .unwrap_or_else(|| GHOST_AUTHOR.to_string());
+                let closes = closing_issue_refs(pr.body.as_deref().unwrap_or(""));
+                let linked_issues = serde_json::to_string(&closes)?;
+                self.db.upsert_pull_request(&PullRequestRow {
+                    id: pr_id,
+                    repo,
+                    number: pr_number,
+                    state: state_str,
+                    author: &author,
+                    title: pr.title.as_deref().unwrap_or_default(),
+                    created_at: &created_at,
+                    updated_at: &updated_at,
+                    merged_at: merged_at.as_deref(),
+                    closed_at: closed_at.as_deref(),
+                    linked_issues: &linked_issues,
+                    data: &json,
+                })?;
+
+                let labels: Vec<String> = pr
+                    .labels
+                    .as_ref()
+                    .map(|ls| ls.iter().map(|l| l.name.clone()).collect())
+                    .unwrap_or_default();
+                upsert_item_labels(self.db, repo, "pr", pr_number, &labels)?;
+
+                upsert_pr_closes_issue(self.db, repo, pr_number, &closes)?;
 
                 if pr.updated_at.map(|t| t >= since).unwrap_or(false) {
+                    self.sync_pr_size(org, repo, pr.number).await?;
                     self.sync_reviews(org, repo, pr.number).await?;
                 }
             }
@@ -462,6 +1261,7 @@ impl<'a> GitHubClient<'a> {
             }
             if let Some(next) = next_page {
                 self.check_limits().await?;
+                self.count_request();
                 page = self.gh.get_page(&Some(next)).await?.unwrap();
             } else {
                 break;
@@ -470,7 +1270,26 @@ impl<'a> GitHubClient<'a> {
         Ok(())
     }
 
+    /// The list endpoint omits diff stats, so they come from the single-PR endpoint.
+    async fn sync_pr_size(&self, org: &str, repo: &str, pr_number: u64) -> Result<()> {
+        self.check_limits().await?;
+        self.count_request();
+        let detail = self.gh.pulls(org, repo).get(pr_number).await?;
+
+        self.db.update_rows(
+            "pull_requests",
+            &[
+                ("additions", json!(detail.additions)),
+                ("deletions", json!(detail.deletions)),
+                ("changed_files", json!(detail.changed_files)),
+            ],
+            &[("repo", json!(repo)), ("number", json!(pr_number))],
+        )?;
+        Ok(())
+    }
+
     async fn sync_reviews(&self, org: &str, repo: &str, pr_number: u64) -> Result<()> {
+        self.count_request();
         let mut page = self
             .gh
             .pulls(org, repo)
@@ -481,7 +1300,7 @@ impl<'a> GitHubClient<'a> {
         loop {
             let next_page = page.next;
             for review in page.items {
-                let json = serde_json::to_string(&review)?;
+                let json = self.payload_json(&review)?;
                 let review_id = review.id.0 as i64;
                 let pr_num = pr_number as i64;
                 let state_str = review
@@ -489,19 +1308,32 @@ impl<'a> GitHubClient<'a> {
                     .map(|s| format!("{:?}", s).to_uppercase())
                     .unwrap_or_else(|| "UNKNOWN".to_string());
 
-                self.db.execute(
-                    "INSERT OR REPLACE INTO pr_reviews (id, repo, pr_number, state, author, submitted_at, data)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                    params![
-                        review_id, repo, pr_num, state_str,
-                        review.user.as_ref().map(|u| u.login.clone()).unwrap_or_default(),
-                        review.submitted_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
-                        json
+                let author = review
+                    .user
+                    .as_ref()
+                    .map(|u| normalize_author(&u.login))
+                    .unwrap_or_else(|| GHOST_AUTHOR.to_string());
+                let submitted_at = review
+                    .submitted_at
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_default();
+
+                self.db.upsert_row(
+                    "pr_reviews",
+                    &[
+                        ("id", json!(review_id)),
+                        ("repo", json!(repo)),
+                        ("pr_number", json!(pr_num)),
+                        ("state", json!(state_str)),
+                        ("author", json!(author)),
+                        ("submitted_at", json!(submitted_at)),
+                        ("data", json!(json)),
                     ],
                 )?;
             }
             if let Some(next) = next_page {
                 self.check_limits().await?;
+                self.count_request();
                 page = self.gh.get_page(&Some(next)).await?.unwrap();
             } else {
                 break;
@@ -518,15 +1350,46 @@ impl<'a> GitHubClient<'a> {
         // This appears to work for our use case.
         let use_since_filter = since.year() >= 2010;
 
-        let mut page: octocrab::Page<Value> = if use_since_filter {
-            self.gh.get(&route, Some(&serde_json::json!({
+        // An interrupted run leaves the next page's URL behind; pick up from there.
+        let cursor_key = format!("last_cursor_issues_{}_{}", org, repo);
+        let saved_cursor = self
+            .db
+            .get_state(&cursor_key)?
+            .and_then(|c| c.parse::<http::Uri>().ok());
+        let resumed: Option<octocrab::Page<Value>> = match saved_cursor {
+            Some(uri) => {
+                self.count_request();
+                match self.gh.get_page(&Some(uri)).await {
+                    Ok(page) => page,
+                    Err(e) => {
+                        // A stale or rejected cursor would otherwise fail every run; start over.
+                        tracing::warn!("Discarding saved issues cursor for {}: {}", repo, e);
+                        self.db.delete_state(&cursor_key)?;
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let mut page: octocrab::Page<Value> = if let Some(page) = resumed {
+            page
+        } else if use_since_filter {
+            self.count_request();
+            self.gh.get(&route, Some(&json!({
                 "state": "all", "sort": "updated", "direction": "desc", "since": since.to_rfc3339(), "per_page": 100
             }))).await?
         } else {
             // First sync: don't pass since parameter to avoid GitHub API bug
-            self.gh.get(&route, Some(&serde_json::json!({
-                "state": "all", "sort": "updated", "direction": "desc", "per_page": 100
-            }))).await?
+            self.count_request();
+            self.gh
+                .get(
+                    &route,
+                    Some(&json!({
+                        "state": "all", "sort": "updated", "direction": "desc", "per_page": 100
+                    })),
+                )
+                .await?
         };
 
         let mut keep_fetching = true;
@@ -537,9 +1400,14 @@ impl<'a> GitHubClient<'a> {
                     .get("updated_at")
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
-                let updated_at = DateTime::parse_from_rfc3339(updated_at_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
+                let Some(updated_at) = parse_gh_ts(updated_at_str) else {
+                    tracing::warn!(
+                        "Skipping issue in {} with unparseable updated_at '{}'",
+                        repo,
+                        updated_at_str
+                    );
+                    continue;
+                };
 
                 if updated_at < since {
                     keep_fetching = false;
@@ -549,7 +1417,7 @@ impl<'a> GitHubClient<'a> {
                     continue;
                 }
 
-                let json = serde_json::to_string(&issue)?;
+                let json = self.payload_json(&issue)?;
                 let id = issue.get("id").and_then(|v| v.as_i64()).unwrap_or(0);
                 let number = issue.get("number").and_then(|v| v.as_i64()).unwrap_or(0);
                 let state = issue
@@ -560,38 +1428,54 @@ impl<'a> GitHubClient<'a> {
                     .get("user")
                     .and_then(|u| u.get("login"))
                     .and_then(|l| l.as_str())
-                    .unwrap_or("unknown");
+                    .map(normalize_author)
+                    .unwrap_or_else(|| GHOST_AUTHOR.to_string());
                 let title = issue.get("title").and_then(|v| v.as_str()).unwrap_or("");
                 let created = issue
                     .get("created_at")
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
                 let closed = issue.get("closed_at").and_then(|v| v.as_str());
-
-                self.db.execute(
-                    "INSERT OR REPLACE INTO issues 
-                    (id, repo, number, state, author, title, created_at, updated_at, closed_at, data) 
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-                    params![id, repo, number, state, author, title, created, updated_at_str, closed, json],
-                )?;
+                let state_reason = issue.get("state_reason").and_then(|v| v.as_str());
+
+                self.db.upsert_issue(&IssueRow {
+                    id,
+                    repo,
+                    number,
+                    state,
+                    author: &author,
+                    title,
+                    created_at: created,
+                    updated_at: updated_at_str,
+                    closed_at: closed,
+                    state_reason,
+                    data: &json,
+                })?;
+
+                upsert_item_labels(self.db, repo, "issue", number, &label_names(&issue))?;
             }
             if !keep_fetching {
                 break;
             }
             if let Some(next) = next_page {
+                self.db.set_state(&cursor_key, &next.to_string())?;
                 self.check_limits().await?;
+                self.count_request();
                 page = self.gh.get_page(&Some(next)).await?.unwrap();
             } else {
                 break;
             }
         }
+
+        self.db.delete_state(&cursor_key)?;
         Ok(())
     }
 
     async fn sync_issue_comments(&self, org: &str, repo: &str, since: DateTime<Utc>) -> Result<()> {
         self.check_limits().await?;
         let route = format!("/repos/{}/{}/issues/comments", org, repo);
-        let mut page: octocrab::Page<Value> = self.gh.get(&route, Some(&serde_json::json!({
+        self.count_request();
+        let mut page: octocrab::Page<Value> = self.gh.get(&route, Some(&json!({
                 "sort": "updated", "direction": "desc", "since": since.to_rfc3339(), "per_page": 100
             }))).await?;
 
@@ -603,9 +1487,14 @@ impl<'a> GitHubClient<'a> {
                     .get("updated_at")
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
-                let updated_at = DateTime::parse_from_rfc3339(updated_at_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
+                let Some(updated_at) = parse_gh_ts(updated_at_str) else {
+                    tracing::warn!(
+                        "Skipping comment in {} with unparseable updated_at '{}'",
+                        repo,
+                        updated_at_str
+                    );
+                    continue;
+                };
 
                 if updated_at < since {
                     keep_fetching = false;
@@ -626,24 +1515,143 @@ impl<'a> GitHubClient<'a> {
                     .get("user")
                     .and_then(|u| u.get("login"))
                     .and_then(|l| l.as_str())
-                    .unwrap_or("unknown");
+                    .map(normalize_author)
+                    .unwrap_or_else(|| GHOST_AUTHOR.to_string());
                 let created = comment
                     .get("created_at")
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
-                let json = serde_json::to_string(&comment)?;
+                let json = self.payload_json(&comment)?;
+
+                self.db.upsert_row(
+                    "issue_comments",
+                    &[
+                        ("id", json!(id)),
+                        ("repo", json!(repo)),
+                        ("issue_number", json!(issue_number)),
+                        ("author", json!(author)),
+                        ("created_at", json!(created)),
+                        ("updated_at", json!(updated_at_str)),
+                        ("data", json!(json)),
+                    ],
+                )?;
+            }
+            if !keep_fetching {
+                break;
+            }
+            if let Some(next) = next_page {
+                self.check_limits().await?;
+                self.count_request();
+                page = self.gh.get_page(&Some(next)).await?.unwrap();
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn sync_issue_events(&self, org: &str, repo: &str, since: DateTime<Utc>) -> Result<()> {
+        self.check_limits().await?;
+        // The events endpoint has no `since` filter, but returns newest first.
+        let route = format!("/repos/{}/{}/issues/events", org, repo);
+        self.count_request();
+        let mut page: octocrab::Page<Value> = self
+            .gh
+            .get(&route, Some(&json!({ "per_page": 100 })))
+            .await?;
+
+        let mut keep_fetching = true;
+        loop {
+            let next_page = page.next.clone();
+            for event in page.items {
+                let created_at_str = event
+                    .get("created_at")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let Some(created_at) = parse_gh_ts(created_at_str) else {
+                    tracing::warn!(
+                        "Skipping event in {} with unparseable created_at '{}'",
+                        repo,
+                        created_at_str
+                    );
+                    continue;
+                };
 
-                self.db.execute(
-                    "INSERT OR REPLACE INTO issue_comments (id, repo, issue_number, author, created_at, updated_at, data)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                    params![id, repo, issue_number, author, created, updated_at_str, json],
+                if created_at < since {
+                    keep_fetching = false;
+                    break;
+                }
+                let id = event.get("id").and_then(|v| v.as_i64()).unwrap_or(0);
+                let issue_number = event
+                    .get("issue")
+                    .and_then(|i| i.get("number"))
+                    .and_then(|n| n.as_i64())
+                    .unwrap_or(0);
+                let kind = event.get("event").and_then(|v| v.as_str()).unwrap_or("");
+                let actor = event
+                    .get("actor")
+                    .and_then(|u| u.get("login"))
+                    .and_then(|l| l.as_str())
+                    .map(normalize_author)
+                    .unwrap_or_else(|| GHOST_AUTHOR.to_string());
+                let label = event
+                    .get("label")
+                    .and_then(|l| l.get("name"))
+                    .and_then(|n| n.as_str());
+                // Carries the whole `issue`, body included, so it's redacted like one.
+                let json = self.payload_json(&event)?;
+
+                self.db.upsert_row(
+                    "issue_events",
+                    &[
+                        ("id", json!(id)),
+                        ("repo", json!(repo)),
+                        ("issue_number", json!(issue_number)),
+                        ("event", json!(kind)),
+                        ("actor", json!(actor)),
+                        ("label", json!(label)),
+                        ("created_at", json!(created_at_str)),
+                        ("data", json!(json)),
+                    ],
                 )?;
+
+                if kind == "review_requested" {
+                    // Either a user or a team is requested, never both.
+                    let (reviewer, is_team) = match event
+                        .get("requested_reviewer")
+                        .and_then(|u| u.get("login"))
+                        .and_then(|l| l.as_str())
+                    {
+                        Some(login) => (Some(normalize_author(login)), false),
+                        None => (
+                            event
+                                .get("requested_team")
+                                .and_then(|t| t.get("slug"))
+                                .and_then(|s| s.as_str())
+                                .map(String::from),
+                            true,
+                        ),
+                    };
+                    if let Some(reviewer) = reviewer {
+                        self.db.upsert_row(
+                            "review_requests",
+                            &[
+                                ("repo", json!(repo)),
+                                ("pr_number", json!(issue_number)),
+                                ("reviewer", json!(reviewer)),
+                                ("is_team", json!(is_team)),
+                                ("requested_at", json!(created_at_str)),
+                            ],
+                        )?;
+                    }
+                }
             }
             if !keep_fetching {
                 break;
             }
             if let Some(next) = next_page {
                 self.check_limits().await?;
+                self.count_request();
                 page = self.gh.get_page(&Some(next)).await?.unwrap();
             } else {
                 break;
@@ -652,10 +1660,138 @@ impl<'a> GitHubClient<'a> {
         Ok(())
     }
 
+    async fn sync_discussions(&self, org: &str, repo: &str, since: DateTime<Utc>) -> Result<()> {
+        // Discussions are only exposed over GraphQL.
+        const QUERY: &str = "query($owner: String!, $name: String!, $cursor: String) {
+            repository(owner: $owner, name: $name) {
+                discussions(first: 100, after: $cursor, orderBy: {field: UPDATED_AT, direction: DESC}) {
+                    pageInfo { hasNextPage endCursor }
+                    nodes {
+                        id number title createdAt updatedAt answerChosenAt
+                        author { login }
+                        category { name isAnswerable }
+                    }
+                }
+            }
+        }";
+
+        let mut cursor: Option<String> = None;
+        loop {
+            self.check_limits().await?;
+            self.count_request();
+            let response: Value = self
+                .gh
+                .graphql(&json!({
+                    "query": QUERY,
+                    "variables": { "owner": org, "name": repo, "cursor": cursor }
+                }))
+                .await?;
+            // GraphQL reports failures in an `errors` array alongside a null `data`. Treating
+            // that as "no discussions" would advance `last_sync` past what was never fetched.
+            if let Some(errors) = response
+                .get("errors")
+                .and_then(|e| e.as_array())
+                .filter(|e| !e.is_empty())
+            {
+                let messages: Vec<&str> = errors
+                    .iter()
+                    .filter_map(|e| e.get("message").and_then(|m| m.as_str()))
+                    .collect();
+                anyhow::bail!(
+                    "GraphQL error fetching discussions for {}/{}: {}",
+                    org,
+                    repo,
+                    messages.join("; ")
+                );
+            }
+
+            let discussions = response
+                .pointer("/data/repository/discussions")
+                .cloned()
+                .unwrap_or(Value::Null);
+            let nodes = discussions
+                .get("nodes")
+                .and_then(|n| n.as_array())
+                .cloned()
+                .unwrap_or_default();
+
+            let mut keep_fetching = true;
+            for node in nodes {
+                let updated_at_str = node.get("updatedAt").and_then(|v| v.as_str()).unwrap_or("");
+                let Some(updated_at) = parse_gh_ts(updated_at_str) else {
+                    tracing::warn!(
+                        "Skipping discussion in {} with unparseable updated_at '{}'",
+                        repo,
+                        updated_at_str
+                    );
+                    continue;
+                };
+
+                if updated_at < since {
+                    keep_fetching = false;
+                    break;
+                }
+                let id = node.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                let number = node.get("number").and_then(|v| v.as_i64()).unwrap_or(0);
+                let title = node.get("title").and_then(|v| v.as_str()).unwrap_or("");
+                let author = node
+                    .get("author")
+                    .and_then(|u| u.get("login"))
+                    .and_then(|l| l.as_str())
+                    .map(normalize_author)
+                    .unwrap_or_else(|| GHOST_AUTHOR.to_string());
+                let category = node
+                    .get("category")
+                    .and_then(|c| c.get("name"))
+                    .and_then(|n| n.as_str());
+                let is_answerable = node
+                    .get("category")
+                    .and_then(|c| c.get("isAnswerable"))
+                    .and_then(|a| a.as_bool())
+                    .unwrap_or(false);
+                let created = node.get("createdAt").and_then(|v| v.as_str()).unwrap_or("");
+                let answered = node.get("answerChosenAt").and_then(|v| v.as_str());
+                let json = self.payload_json(&node)?;
+
+                self.db.upsert_row(
+                    "discussions",
+                    &[
+                        ("id", json!(id)),
+                        ("repo", json!(repo)),
+                        ("number", json!(number)),
+                        ("author", json!(author)),
+                        ("title", json!(title)),
+                        ("category_name", json!(category)),
+                        ("is_answerable", json!(is_answerable)),
+                        ("created_at", json!(created)),
+                        ("updated_at", json!(updated_at_str)),
+                        ("answer_chosen_at", json!(answered)),
+                        ("data", json!(json)),
+                    ],
+                )?;
+            }
+
+            let page_info = discussions.get("pageInfo");
+            let has_next = page_info
+                .and_then(|p| p.get("hasNextPage"))
+                .and_then(|h| h.as_bool())
+                .unwrap_or(false);
+            if !keep_fetching || !has_next {
+                break;
+            }
+            cursor = page_info
+                .and_then(|p| p.get("endCursor"))
+                .and_then(|c| c.as_str())
+                .map(String::from);
+        }
+        Ok(())
+    }
+
     async fn sync_pr_comments(&self, org: &str, repo: &str, since: DateTime<Utc>) -> Result<()> {
         self.check_limits().await?;
         let route = format!("/repos/{}/{}/pulls/comments", org, repo);
-        let mut page: octocrab::Page<Value> = self.gh.get(&route, Some(&serde_json::json!({
+        self.count_request();
+        let mut page: octocrab::Page<Value> = self.gh.get(&route, Some(&json!({
                 "sort": "updated", "direction": "desc", "since": since.to_rfc3339(), "per_page": 100
             }))).await?;
 
@@ -667,9 +1803,14 @@ impl<'a> GitHubClient<'a> {
                     .get("updated_at")
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
-                let updated_at = DateTime::parse_from_rfc3339(updated_at_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
+                let Some(updated_at) = parse_gh_ts(updated_at_str) else {
+                    tracing::warn!(
+                        "Skipping review comment in {} with unparseable updated_at '{}'",
+                        repo,
+                        updated_at_str
+                    );
+                    continue;
+                };
 
                 if updated_at < since {
                     keep_fetching = false;
@@ -690,17 +1831,25 @@ impl<'a> GitHubClient<'a> {
                     .get("user")
                     .and_then(|u| u.get("login"))
                     .and_then(|l| l.as_str())
-                    .unwrap_or("unknown");
+                    .map(normalize_author)
+                    .unwrap_or_else(|| GHOST_AUTHOR.to_string());
                 let created = comment
                     .get("created_at")
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
-                let json = serde_json::to_string(&comment)?;
-
-                self.db.execute(
-                    "INSERT OR REPLACE INTO pr_review_comments (id, repo, pr_number, author, created_at, updated_at, data)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                    params![id, repo, pr_number, author, created, updated_at_str, json],
+                let json = self.payload_json(&comment)?;
+
+                self.db.upsert_row(
+                    "pr_review_comments",
+                    &[
+                        ("id", json!(id)),
+                        ("repo", json!(repo)),
+                        ("pr_number", json!(pr_number)),
+                        ("author", json!(author)),
+                        ("created_at", json!(created)),
+                        ("updated_at", json!(updated_at_str)),
+                        ("data", json!(json)),
+                    ],
                 )?;
             }
             if !keep_fetching {
@@ -708,6 +1857,7 @@ impl<'a> GitHubClient<'a> {
             }
             if let Some(next) = next_page {
                 self.check_limits().await?;
+                self.count_request();
                 page = self.gh.get_page(&Some(next)).await?.unwrap();
             } else {
                 break;
@@ -716,15 +1866,580 @@ impl<'a> GitHubClient<'a> {
         Ok(())
     }
 
-    fn is_missing_resource(err: &octocrab::Error) -> bool {
+    /// GitHub answers 409 "Git Repository is empty." for git-backed endpoints on empty repos.
+    fn is_empty_repo(err: &octocrab::Error) -> bool {
         match err {
             octocrab::Error::GitHub { source, .. } => {
-                source.status_code == StatusCode::NOT_FOUND
-                    || source.status_code == StatusCode::GONE
-                    || source.message.eq_ignore_ascii_case("Not Found")
-                    || source.message.eq_ignore_ascii_case("Not Found.")
+                source.status_code == StatusCode::CONFLICT
+                    && source
+                        .message
+                        .to_lowercase()
+                        .contains("git repository is empty")
             }
             _ => false,
         }
     }
+
+    fn is_missing_resource(err: &octocrab::Error) -> bool {
+        Self::classify_error(err) == ApiErrorKind::Missing
+    }
+
+    /// Sorts a failed request into missing, permission-denied, rate-limited or other. octocrab
+    /// drops the response headers, so a rate-limit 403 is told apart from a permission 403 by
+    /// GitHub's message and documentation link instead of `x-ratelimit-remaining`.
+    fn classify_error(err: &octocrab::Error) -> ApiErrorKind {
+        let octocrab::Error::GitHub { source, .. } = err else {
+            return ApiErrorKind::Other;
+        };
+        if source.status_code == StatusCode::NOT_FOUND
+            || source.status_code == StatusCode::GONE
+            || source.message.eq_ignore_ascii_case("Not Found")
+            || source.message.eq_ignore_ascii_case("Not Found.")
+        {
+            return ApiErrorKind::Missing;
+        }
+        let rate_limited = source.message.to_lowercase().contains("rate limit")
+            || source
+                .documentation_url
+                .as_deref()
+                .is_some_and(|url| url.contains("rate-limit"));
+        match source.status_code {
+            StatusCode::TOO_MANY_REQUESTS => ApiErrorKind::RateLimited,
+            StatusCode::FORBIDDEN if rate_limited => ApiErrorKind::RateLimited,
+            StatusCode::FORBIDDEN => ApiErrorKind::PermissionDenied,
+            _ => ApiErrorKind::Other,
+        }
+    }
+
+    /// Warns about and records an optional stage skipped for lack of permission, so `sync_errors`
+    /// shows which repos are missing that data.
+    fn record_skip(
+        &self,
+        repo: &str,
+        stage: &str,
+        reason: &str,
+        err: &octocrab::Error,
+    ) -> Result<()> {
+        tracing::warn!("Skipping {} for {}: {}", stage, repo, reason);
+        self.db.upsert_row(
+            "sync_errors",
+            &[
+                ("repo", json!(repo)),
+                ("stage", json!(stage)),
+                ("kind", json!("permission_denied")),
+                ("message", json!(err.to_string())),
+                ("occurred_at", json!(Utc::now().to_rfc3339())),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+    use crate::dry_run::DryRunConnection;
+    use crate::mock_github::{MockGitHub, Route};
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn epoch() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn issue(number: i64) -> Value {
+        json!({
+            "id": number,
+            "number": number,
+            "state": "open",
+            "title": format!("Issue {}", number),
+            "user": { "login": "alice" },
+            "created_at": "2026-01-01T00:00:00Z",
+            "updated_at": "2026-01-02T00:00:00Z",
+            "labels": [],
+        })
+    }
+
+    fn count(conn: &rusqlite::Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn stale_issues_cursor_falls_back_to_first_page() {
+        let server = MockGitHub::start().await;
+        server.route(Route::new("/stale", 410, json!({ "message": "Gone" })));
+        server.route(Route::ok("/repos/o/r/issues", json!([issue(1)])));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let key = "last_cursor_issues_o_r";
+        store
+            .set_state(key, &format!("{}/stale", server.uri))
+            .unwrap();
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_issues("o", "r", epoch()).await.unwrap();
+
+        assert_eq!(server.paths(), ["/stale", "/repos/o/r/issues"]);
+        assert_eq!(store.get_state(key).unwrap(), None);
+        assert_eq!(count(&store, "SELECT count(*) FROM issues"), 1);
+    }
+
+    #[tokio::test]
+    async fn saved_issues_cursor_resumes_there() {
+        let server = MockGitHub::start().await;
+        server.route(
+            Route::ok("/repos/o/r/issues?page=2", json!([issue(2)]))
+                .next_page(&server, "/repos/o/r/issues?page=3"),
+        );
+        server.route(Route::ok("/repos/o/r/issues?page=3", json!([issue(3)])));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let key = "last_cursor_issues_o_r";
+        store
+            .set_state(key, &format!("{}/repos/o/r/issues?page=2", server.uri))
+            .unwrap();
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_issues("o", "r", epoch()).await.unwrap();
+
+        assert_eq!(
+            server.requests(),
+            ["/repos/o/r/issues?page=2", "/repos/o/r/issues?page=3"]
+        );
+        assert_eq!(store.get_state(key).unwrap(), None);
+        assert_eq!(count(&store, "SELECT sum(number) FROM issues"), 5);
+    }
+
+    #[tokio::test]
+    async fn empty_repo_409_means_no_commits() {
+        let server = MockGitHub::start().await;
+        server.route(Route::new(
+            "/repos/o/r/commits",
+            409,
+            json!({ "message": "Git Repository is empty." }),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_commits("o", "r", epoch()).await.unwrap();
+        assert_eq!(count(&store, "SELECT count(*) FROM commits"), 0);
+    }
+
+    #[tokio::test]
+    async fn other_409s_are_still_errors() {
+        let server = MockGitHub::start().await;
+        server.route(Route::new(
+            "/repos/o/r/commits",
+            409,
+            json!({ "message": "Merge conflict" }),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        assert!(client.sync_commits("o", "r", epoch()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn check_runs_are_read_past_the_first_page() {
+        let server = MockGitHub::start().await;
+        let run = |id: i64| json!({ "id": id, "name": "build", "status": "completed" });
+        let route = "/repos/o/r/commits/abc/check-runs";
+        server.route(Route::ok(
+            &format!("{}?page=1&per_page=100", route),
+            json!({ "total_count": 101, "check_runs": (1..=100).map(run).collect::<Vec<_>>() }),
+        ));
+        server.route(Route::ok(
+            &format!("{}?page=2&per_page=100", route),
+            json!({ "total_count": 101, "check_runs": [run(101)] }),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        conn.execute(
+            "INSERT INTO commits (sha, repo, author, date) VALUES ('abc', 'r', 'alice', '2026-01-01T00:00:00+00:00')",
+            [],
+        )
+        .unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_check_runs("o", "r", epoch()).await.unwrap();
+
+        assert_eq!(server.paths(), [route, route]);
+        assert_eq!(count(&store, "SELECT count(*) FROM check_runs"), 101);
+    }
+
+    fn workflow_run(id: i64, conclusion: Option<&str>) -> Value {
+        json!({
+            "id": id,
+            "name": "ci",
+            "head_branch": "main",
+            "conclusion": conclusion,
+            "created_at": "2026-01-01T00:00:00Z",
+            "updated_at": "2026-01-01T00:10:00Z",
+        })
+    }
+
+    #[tokio::test]
+    async fn workflow_sync_stops_at_a_known_run() {
+        let server = MockGitHub::start().await;
+        let runs = "/repos/o/r/actions/runs";
+        server.route(
+            Route::ok(
+                &format!("{}?page=2", runs),
+                json!({ "workflow_runs": [workflow_run(100, Some("success")), workflow_run(99, Some("success"))] }),
+            )
+            .next_page(&server, &format!("{}?page=3", runs)),
+        );
+        server.route(
+            Route::ok(
+                runs,
+                json!({ "workflow_runs": [workflow_run(102, Some("success")), workflow_run(101, Some("failure"))] }),
+            )
+            .next_page(&server, &format!("{}?page=2", runs)),
+        );
+
+        let conn = init_db(":memory:").unwrap();
+        conn.execute(
+            "INSERT INTO workflow_runs (id, repo, conclusion, created_at, updated_at)
+             VALUES (100, 'r', 'success', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z')",
+            [],
+        )
+        .unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_workflows("o", "r", epoch()).await.unwrap();
+
+        assert_eq!(server.paths(), [runs, runs]);
+        assert_eq!(count(&store, "SELECT count(*) FROM workflow_runs"), 3);
+        assert_eq!(count(&store, "SELECT min(id) FROM workflow_runs"), 100);
+    }
+
+    #[tokio::test]
+    async fn workflow_sync_refetches_runs_in_progress() {
+        let server = MockGitHub::start().await;
+        server.route(Route::ok(
+            "/repos/o/r/actions/runs",
+            json!({ "workflow_runs": [
+                workflow_run(102, Some("success")),
+                workflow_run(101, Some("failure")),
+                workflow_run(100, Some("success")),
+            ] }),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        conn.execute_batch(
+            "INSERT INTO workflow_runs (id, repo, conclusion, created_at, updated_at) VALUES
+                (100, 'r', 'success', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z'),
+                (101, 'r', 'in_progress', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z');",
+        )
+        .unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_workflows("o", "r", epoch()).await.unwrap();
+
+        assert_eq!(
+            count(
+                &store,
+                "SELECT count(*) FROM workflow_runs WHERE conclusion = 'in_progress'"
+            ),
+            0
+        );
+        assert_eq!(count(&store, "SELECT count(*) FROM workflow_runs"), 3);
+    }
+
+    #[tokio::test]
+    async fn deleted_users_are_stored_as_ghost() {
+        let server = MockGitHub::start().await;
+        let mut orphan = issue(1);
+        orphan["user"] = Value::Null;
+        server.route(Route::ok("/repos/o/r/issues", json!([orphan])));
+        server.route(Route::ok(
+            "/repos/o/r/issues/comments",
+            json!([{
+                "id": 7,
+                "issue_url": "https://api.github.com/repos/o/r/issues/1",
+                "user": null,
+                "created_at": "2026-01-02T00:00:00Z",
+                "updated_at": "2026-01-02T00:00:00Z",
+            }]),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_issues("o", "r", epoch()).await.unwrap();
+        client.sync_issue_comments("o", "r", epoch()).await.unwrap();
+
+        let conn = &*store;
+        let ghost = |sql: &str| {
+            conn.query_row(sql, [], |row| row.get::<_, String>(0))
+                .unwrap()
+        };
+        assert_eq!(ghost("SELECT author FROM issues"), GHOST_AUTHOR);
+        assert_eq!(ghost("SELECT author FROM issue_comments"), GHOST_AUTHOR);
+    }
+
+    #[test]
+    fn parse_gh_ts_accepts_github_variants() {
+        let expected = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap();
+        for s in [
+            "2026-01-02T03:04:05Z",
+            "2026-01-02T05:04:05+02:00",
+            "2026-01-02T03:04:05",
+            "2026-01-02 03:04:05",
+        ] {
+            assert_eq!(parse_gh_ts(s), Some(expected), "{}", s);
+        }
+        assert_eq!(
+            parse_gh_ts("2026-01-02T03:04:05.250Z"),
+            Some(expected + chrono::Duration::milliseconds(250))
+        );
+        assert_eq!(parse_gh_ts(""), None);
+        assert_eq!(parse_gh_ts("2026-01-02"), None);
+    }
+
+    #[tokio::test]
+    async fn author_logins_are_stored_lowercase() {
+        let server = MockGitHub::start().await;
+        let mut first = issue(1);
+        first["user"]["login"] = json!("Alice");
+        let mut second = issue(2);
+        second["user"]["login"] = json!("ALICE");
+        server.route(Route::ok("/repos/o/r/issues", json!([first, second])));
+        server.route(Route::ok(
+            "/repos/o/r/issues/comments",
+            json!([{
+                "id": 7,
+                "issue_url": "https://api.github.com/repos/o/r/issues/1",
+                "user": { "login": "aLiCe" },
+                "created_at": "2026-01-02T00:00:00Z",
+                "updated_at": "2026-01-02T00:00:00Z",
+            }]),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_issues("o", "r", epoch()).await.unwrap();
+        client.sync_issue_comments("o", "r", epoch()).await.unwrap();
+
+        assert_eq!(
+            count(
+                &store,
+                "SELECT count(*) FROM (
+                    SELECT author FROM issues UNION ALL SELECT author FROM issue_comments
+                 ) WHERE author = 'alice'"
+            ),
+            3
+        );
+    }
+
+    #[test]
+    fn normalize_author_ignores_case() {
+        assert_eq!(normalize_author("Alice"), "alice");
+        assert_eq!(normalize_author("dependabot[bot]"), "dependabot[bot]");
+        assert_eq!(normalize_author("ALICE"), normalize_author("alice"));
+    }
+
+    #[tokio::test]
+    async fn org_events_are_stored_across_pages() {
+        let server = MockGitHub::start().await;
+        let event = |id: &str, action: &str| {
+            json!({
+                "id": id,
+                "type": "MemberEvent",
+                "actor": { "login": "Alice" },
+                "repo": { "name": "o/r" },
+                "created_at": "2026-01-01T00:00:00Z",
+                "payload": { "action": action },
+            })
+        };
+        server.route(Route::ok(
+            "/orgs/o/events?page=2",
+            json!([event("2", "removed")]),
+        ));
+        server.route(
+            Route::ok(
+                "/orgs/o/events",
+                json!([event("1", "added"), { "type": "MemberEvent" }]),
+            )
+            .next_page(&server, "/orgs/o/events?page=2"),
+        );
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_org_events("o").await.unwrap();
+
+        let rows: Vec<(String, String, String, String)> = store
+            .prepare(
+                "SELECT id, actor, repo, json_extract(payload, '$.action') FROM org_events ORDER BY id",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let row = |id: &str, action: &str| {
+            (
+                id.to_string(),
+                "alice".to_string(),
+                "r".to_string(),
+                action.to_string(),
+            )
+        };
+        assert_eq!(rows, [row("1", "added"), row("2", "removed")]);
+    }
+
+    #[tokio::test]
+    async fn forbidden_org_events_are_skipped_and_recorded() {
+        let server = MockGitHub::start().await;
+        server.route(Route::new(
+            "/orgs/o/events",
+            403,
+            json!({ "message": "Resource not accessible by integration" }),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_org_events("o").await.unwrap();
+
+        assert_eq!(
+            count(
+                &store,
+                "SELECT count(*) FROM sync_errors WHERE repo = 'o' AND stage = 'org_events'"
+            ),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn repos_are_ordered_by_name_activity_or_last_sync() {
+        let repo = |id: u64, name: &str, pushed_at: &str| -> models::Repository {
+            serde_json::from_value(json!({
+                "id": id,
+                "name": name,
+                "url": format!("https://api.github.com/repos/o/{}", name),
+                "pushed_at": pushed_at,
+            }))
+            .unwrap()
+        };
+        let server = MockGitHub::start().await;
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        store
+            .set_state("last_sync_o_a", "2026-01-03T00:00:00Z")
+            .unwrap();
+        store
+            .set_state("last_sync_o_b", "2026-01-01T00:00:00Z")
+            .unwrap();
+        let mut client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+
+        let mut order = |order: RepoOrder| {
+            let mut repos = vec![
+                repo(1, "b", "2026-01-01T00:00:00Z"),
+                repo(2, "d", "2026-01-05T00:00:00Z"),
+                repo(3, "a", "2026-01-02T00:00:00Z"),
+                repo(4, "c", "2026-01-04T00:00:00Z"),
+            ];
+            client.set_repo_order(order);
+            client.order_repos("o", &mut repos).unwrap();
+            repos.into_iter().map(|r| r.name).collect::<Vec<_>>()
+        };
+        assert_eq!(order(RepoOrder::Name), ["a", "b", "c", "d"]);
+        assert_eq!(order(RepoOrder::Activity), ["d", "c", "a", "b"]);
+        assert_eq!(order(RepoOrder::LastSynced), ["c", "d", "b", "a"]);
+    }
+
+    #[tokio::test]
+    async fn permission_403_skips_the_stage_and_records_it() {
+        let server = MockGitHub::start().await;
+        server.route(Route::new(
+            "/repos/o/r/dependabot/alerts",
+            403,
+            json!({ "message": "Resource not accessible by personal access token" }),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        client.sync_dependabot_alerts("o", "r").await.unwrap();
+
+        let (stage, kind): (String, String) = store
+            .query_row(
+                "SELECT stage, kind FROM sync_errors WHERE repo = 'r'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (stage.as_str(), kind.as_str()),
+            ("dependabot_alerts", "permission_denied")
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limit_403_is_still_an_error() {
+        let server = MockGitHub::start().await;
+        server.route(Route::new(
+            "/repos/o/r/dependabot/alerts",
+            403,
+            json!({
+                "message": "API rate limit exceeded for user ID 1.",
+                "documentation_url": "https://docs.github.com/rest/overview/rate-limits-for-the-rest-api",
+            }),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        assert!(client.sync_dependabot_alerts("o", "r").await.is_err());
+        assert_eq!(count(&store, "SELECT count(*) FROM sync_errors"), 0);
+    }
+
+    #[test]
+    fn first_syncs_are_windowed_unless_full() {
+        let now = Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap();
+        let cursor = Utc.with_ymd_and_hms(2026, 5, 1, 0, 0, 0).unwrap();
+        let floor = Utc.with_ymd_and_hms(2026, 5, 22, 0, 0, 0).unwrap();
+
+        assert_eq!(sync_since(None, false, Some(10), now), (floor, true));
+        assert_eq!(sync_since(None, false, None, now), (epoch(), false));
+        // Later syncs resume from the cursor, whether or not the first was windowed.
+        assert_eq!(
+            sync_since(Some(cursor), true, Some(10), now),
+            (cursor, false)
+        );
+        assert_eq!(sync_since(Some(cursor), false, None, now), (cursor, false));
+        // --full backfills a repo whose first sync was windowed.
+        assert_eq!(sync_since(Some(cursor), true, None, now), (epoch(), false));
+    }
+
+    #[tokio::test]
+    async fn discussions_graphql_errors_fail_the_stage() {
+        let server = MockGitHub::start().await;
+        server.route(Route::ok(
+            "/graphql",
+            json!({
+                "data": { "repository": null },
+                "errors": [{ "type": "NOT_FOUND", "message": "Could not resolve to a Repository" }]
+            }),
+        ));
+
+        let conn = init_db(":memory:").unwrap();
+        let mut store = DryRunConnection::new(&conn, false);
+        let client = GitHubClient::new(server.client(), &mut store, ProgressBar::hidden());
+        let err = client
+            .sync_discussions("o", "r", epoch())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Could not resolve"), "{}", err);
+    }
 }
@@ -0,0 +1,422 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Tunables for syncing and `compute_metrics`, read from `.strands.toml`. Every section is
+/// optional.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub pr_size_buckets: PrSizeBuckets,
+    pub pr_description: PrDescription,
+    pub community_health: CommunityHealthWeights,
+    pub meta_repos: MetaRepos,
+    pub abandoned_prs: AbandonedPrs,
+    pub business_hours: BusinessHours,
+    pub pr_template: PrTemplate,
+    pub triage: Triage,
+    pub change_composition: ChangeComposition,
+    pub client: ClientConfig,
+}
+
+/// HTTP settings shared by the GitHub and package registry clients.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ClientConfig {
+    /// Sent as `User-Agent` on every request. crates.io and GitHub both ask for one that
+    /// identifies the tool.
+    pub user_agent: String,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: concat!("strands-metrics/", env!("CARGO_PKG_VERSION")).to_string(),
+        }
+    }
+}
+
+/// Upper bounds (inclusive) on lines changed for each PR size bucket. Anything above `l` is XL.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct PrSizeBuckets {
+    pub xs: i64,
+    pub s: i64,
+    pub m: i64,
+    pub l: i64,
+}
+
+impl Default for PrSizeBuckets {
+    fn default() -> Self {
+        Self {
+            xs: 10,
+            s: 50,
+            m: 200,
+            l: 500,
+        }
+    }
+}
+
+/// What counts as a "complete" PR description for `pr_description_quality`.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct PrDescription {
+    /// Minimum body length in characters (after trimming).
+    pub min_length: i64,
+    /// Also require a closing reference to an issue ("Fixes #12").
+    pub require_linked_issue: bool,
+}
+
+impl Default for PrDescription {
+    fn default() -> Self {
+        Self {
+            min_length: 50,
+            require_linked_issue: false,
+        }
+    }
+}
+
+/// SLA for `triage_sla_met_rate`: an issue is triaged by its first label or first comment from
+/// someone other than its author.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct Triage {
+    /// Hours after opening within which an issue must be triaged.
+    pub sla_hours: f64,
+}
+
+impl Default for Triage {
+    fn default() -> Self {
+        Self { sla_hours: 48.0 }
+    }
+}
+
+/// Path patterns sorting changed files into docs, tests and source for `change_composition`.
+/// A pattern starting with `*` matches a path suffix (`*.md`), one ending in `/` matches a
+/// directory anywhere in the path (`docs/`), and a bare word matches a path component split on
+/// `/ . _ -`, singular or plural (`test` matches `tests/`, `foo_test.go`, `__tests__`).
+/// Matching ignores case; test patterns are checked before doc patterns.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ChangeComposition {
+    pub docs: Vec<String>,
+    pub tests: Vec<String>,
+}
+
+impl Default for ChangeComposition {
+    fn default() -> Self {
+        Self {
+            docs: vec!["*.md".to_string(), "docs/".to_string()],
+            tests: vec!["test".to_string(), "spec".to_string()],
+        }
+    }
+}
+
+/// Which `change_composition` bucket a changed file falls in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangeKind {
+    Docs,
+    Tests,
+    Src,
+}
+
+impl ChangeComposition {
+    pub fn classify(&self, path: &str) -> ChangeKind {
+        let path = path.to_lowercase();
+        let matches = |patterns: &[String]| patterns.iter().any(|p| path_matches(&path, p));
+        if matches(&self.tests) {
+            ChangeKind::Tests
+        } else if matches(&self.docs) {
+            ChangeKind::Docs
+        } else {
+            ChangeKind::Src
+        }
+    }
+}
+
+/// `path` must already be lowercased.
+fn path_matches(path: &str, pattern: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    if let Some(suffix) = pattern.strip_prefix('*') {
+        return path.ends_with(suffix);
+    }
+    if let Some(dir) = pattern.strip_suffix('/') {
+        let dirs = path.rsplit_once('/').map(|(d, _)| d).unwrap_or("");
+        return dirs.split('/').any(|d| d == dir);
+    }
+    let plural = format!("{}s", pattern);
+    path.split(['/', '.', '_', '-'])
+        .any(|part| part == pattern || part == plural)
+}
+
+/// When a merged PR counts as having filled out its repo's PR template, for
+/// `pr_template_compliance_rate`.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct PrTemplate {
+    /// Body length must exceed the template's length times this ratio.
+    pub min_body_ratio: f64,
+}
+
+impl Default for PrTemplate {
+    fn default() -> Self {
+        Self {
+            min_body_ratio: 0.5,
+        }
+    }
+}
+
+/// Weights for the `community_health_score` composite. Must sum to 1.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct CommunityHealthWeights {
+    pub responsiveness: f64,
+    pub merge_rate: f64,
+    pub star_growth: f64,
+    pub contributor_diversity: f64,
+}
+
+impl Default for CommunityHealthWeights {
+    fn default() -> Self {
+        Self {
+            responsiveness: 0.3,
+            merge_rate: 0.3,
+            star_growth: 0.2,
+            contributor_diversity: 0.2,
+        }
+    }
+}
+
+/// When an untouched open PR counts toward `abandoned_prs`.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct AbandonedPrs {
+    /// Days since the PR was opened.
+    pub min_age_days: i64,
+}
+
+impl Default for AbandonedPrs {
+    fn default() -> Self {
+        Self { min_age_days: 14 }
+    }
+}
+
+/// Calendar for `time_to_first_response_business`: weekends and `holidays` don't count.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct BusinessHours {
+    /// IANA name, e.g. "America/Los_Angeles". Decides where each day starts and ends.
+    pub timezone: String,
+    /// Dates (YYYY-MM-DD) treated like weekend days.
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl Default for BusinessHours {
+    fn default() -> Self {
+        Self {
+            timezone: "UTC".to_string(),
+            holidays: Vec::new(),
+        }
+    }
+}
+
+impl BusinessHours {
+    pub fn tz(&self) -> Result<Tz> {
+        self.timezone.parse().map_err(|_| {
+            anyhow!(
+                "business_hours.timezone: unknown timezone '{}'",
+                self.timezone
+            )
+        })
+    }
+}
+
+/// Org automation repos left out of org-wide aggregates. They are still synced and keep their
+/// own per-repo rows.
+pub const DEFAULT_META_REPOS: &[&str] = &[".github", ".github-private", "homebrew-tap"];
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct MetaRepos {
+    /// Start from `DEFAULT_META_REPOS`. Set to false to use only `exclude`.
+    pub use_defaults: bool,
+    /// Additional repos to exclude.
+    pub exclude: Vec<String>,
+}
+
+impl Default for MetaRepos {
+    fn default() -> Self {
+        Self {
+            use_defaults: true,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl MetaRepos {
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = if self.use_defaults {
+            DEFAULT_META_REPOS.iter().map(|r| r.to_string()).collect()
+        } else {
+            Vec::new()
+        };
+        for repo in &self.exclude {
+            if !names.contains(repo) {
+                names.push(repo.clone());
+            }
+        }
+        names
+    }
+
+    /// SQL predicate keeping only non-meta repos, e.g. `repo NOT IN ('.github')`.
+    pub fn filter_sql(&self, column: &str) -> String {
+        let names = self.names();
+        if names.is_empty() {
+            return "1 = 1".to_string();
+        }
+        let list = names
+            .iter()
+            .map(|r| format!("'{}'", r.replace('\'', "''")))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} NOT IN ({})", column, list)
+    }
+}
+
+impl MetricsConfig {
+    /// Loads the config file, falling back to defaults if it doesn't exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(raw) => {
+                let config: Self = toml::from_str(&raw)?;
+                let w = &config.community_health;
+                let sum = w.responsiveness + w.merge_rate + w.star_growth + w.contributor_diversity;
+                if (sum - 1.0).abs() > 1e-6 {
+                    bail!("community_health weights must sum to 1, got {}", sum);
+                }
+                config.business_hours.tz()?;
+                if config.triage.sla_hours <= 0.0 {
+                    bail!(
+                        "triage.sla_hours must be positive, got {}",
+                        config.triage.sla_hours
+                    );
+                }
+                if http::HeaderValue::from_str(&config.client.user_agent).is_err()
+                    || config.client.user_agent.trim().is_empty()
+                {
+                    bail!(
+                        "client.user_agent: '{}' is not a valid header value",
+                        config.client.user_agent
+                    );
+                }
+                Ok(config)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl PrSizeBuckets {
+    /// SQL CASE expression mapping a lines-changed expression to 'XS'..'XL'.
+    pub fn case_sql(&self, lines_expr: &str) -> String {
+        format!(
+            "CASE WHEN {e} <= {xs} THEN 'XS' WHEN {e} <= {s} THEN 'S' WHEN {e} <= {m} THEN 'M' WHEN {e} <= {l} THEN 'L' ELSE 'XL' END",
+            e = lines_expr,
+            xs = self.xs,
+            s = self.s,
+            m = self.m,
+            l = self.l
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(buckets: &PrSizeBuckets, lines: i64) -> String {
+        rusqlite::Connection::open_in_memory()
+            .unwrap()
+            .query_row(
+                &format!("SELECT {}", buckets.case_sql("?1")),
+                [lines],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn custom_size_thresholds_move_bucket_edges() {
+        let defaults = PrSizeBuckets::default();
+        let custom: PrSizeBuckets = toml::from_str("xs = 1\ns = 2\nm = 3\nl = 4").unwrap();
+
+        let sizes = [0, 3, 5, 50, 600];
+        let by = |b: &PrSizeBuckets| sizes.map(|n| bucket(b, n));
+        assert_eq!(by(&defaults), ["XS", "XS", "XS", "S", "XL"]);
+        assert_eq!(by(&custom), ["XS", "M", "XL", "XL", "XL"]);
+    }
+
+    fn load(toml: &str) -> Result<MetricsConfig> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".strands.toml");
+        std::fs::write(&path, toml).unwrap();
+        MetricsConfig::load(&path)
+    }
+
+    #[test]
+    fn user_agent_defaults_to_the_crate_version() {
+        let config = load("").unwrap();
+        assert_eq!(
+            config.client.user_agent,
+            format!("strands-metrics/{}", env!("CARGO_PKG_VERSION"))
+        );
+        let config = load("[client]\nuser_agent = \"acme-metrics/1.0\"").unwrap();
+        assert_eq!(config.client.user_agent, "acme-metrics/1.0");
+    }
+
+    #[test]
+    fn invalid_user_agents_are_rejected() {
+        for agent in ["\"\"", "\"   \"", "\"bad\\nagent\""] {
+            let err = load(&format!("[client]\nuser_agent = {}", agent)).unwrap_err();
+            assert!(
+                err.to_string().starts_with("client.user_agent:"),
+                "{}: {}",
+                agent,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn changed_files_are_classified_by_default_patterns() {
+        let patterns = ChangeComposition::default();
+        for (path, kind) in [
+            ("README.MD", ChangeKind::Docs),
+            ("site/docs/guide.html", ChangeKind::Docs),
+            ("tests/agent.py", ChangeKind::Tests),
+            ("src/agent_test.go", ChangeKind::Tests),
+            ("src/__tests__/agent.ts", ChangeKind::Tests),
+            ("src/agent.spec.ts", ChangeKind::Tests),
+            ("docs/testing.md", ChangeKind::Docs),
+            ("docs/test/index.md", ChangeKind::Tests),
+            ("src/contest.rs", ChangeKind::Src),
+            ("src/docs.rs", ChangeKind::Src),
+            ("Cargo.toml", ChangeKind::Src),
+        ] {
+            assert_eq!(patterns.classify(path), kind, "{}", path);
+        }
+    }
+
+    #[test]
+    fn custom_change_patterns_replace_the_defaults() {
+        let patterns: ChangeComposition =
+            toml::from_str("docs = [\"*.rst\"]\ntests = [\"Fixture\"]").unwrap();
+        assert_eq!(patterns.classify("guide.rst"), ChangeKind::Docs);
+        assert_eq!(patterns.classify("README.md"), ChangeKind::Src);
+        assert_eq!(patterns.classify("fixtures/parse.json"), ChangeKind::Tests);
+        assert_eq!(patterns.classify("tests/parse.rs"), ChangeKind::Src);
+    }
+}
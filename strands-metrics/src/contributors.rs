@@ -0,0 +1,130 @@
+use anyhow::{bail, Result};
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const ROLES: &[&str] = &["maintainer", "core", "community", "bot"];
+
+/// Role for logins missing from `contributors.yaml`, unless the login is a `[bot]` account.
+pub const DEFAULT_ROLE: &str = "community";
+
+#[derive(Deserialize, Debug)]
+pub struct ContributorsConfig {
+    /// GitHub login to one of `ROLES`.
+    #[serde(default)]
+    pub contributors: BTreeMap<String, String>,
+}
+
+impl ContributorsConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let config = Self::parse(path)?;
+        let problems = config.problems();
+        if !problems.is_empty() {
+            bail!("Invalid contributors:\n  {}", problems.join("\n  "));
+        }
+        Ok(config)
+    }
+
+    pub fn parse<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&raw)?)
+    }
+
+    /// Every validation problem across all contributors, one message each.
+    pub fn problems(&self) -> Vec<String> {
+        self.contributors
+            .iter()
+            .filter(|(_, role)| !ROLES.contains(&role.as_str()))
+            .map(|(login, role)| {
+                format!(
+                    "Contributor '{}': role must be one of {:?}, got '{}'",
+                    login, ROLES, role
+                )
+            })
+            .collect()
+    }
+}
+
+/// Replaces the stored roles with the contents of the config file. Logins are stored lowercased.
+pub fn load_contributors(conn: &Connection, config: &ContributorsConfig) -> Result<usize> {
+    conn.execute("DELETE FROM contributor_roles", [])?;
+    for (login, role) in &config.contributors {
+        conn.execute(
+            "INSERT OR REPLACE INTO contributor_roles (login, role) VALUES (?1, ?2)",
+            params![login.to_lowercase(), role],
+        )?;
+    }
+    Ok(config.contributors.len())
+}
+
+/// SQL expression for the role of the login in `column`: the mapped role, else `bot` for
+/// `[bot]` accounts, else `DEFAULT_ROLE`.
+pub fn role_sql(column: &str) -> String {
+    format!(
+        "COALESCE(
+            (SELECT role FROM contributor_roles WHERE login = lower({0})),
+            CASE WHEN {0} LIKE '%[bot]' THEN 'bot' ELSE '{1}' END
+        )",
+        column, DEFAULT_ROLE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregates::compute_merge_time_by_role;
+    use crate::db::init_db;
+
+    #[test]
+    fn merge_time_is_bucketed_by_role() {
+        let conn = init_db(":memory:").unwrap();
+        let config: ContributorsConfig =
+            serde_yaml::from_str("contributors:\n  Alice: maintainer\n  carol: core\n").unwrap();
+        assert!(config.problems().is_empty());
+        assert_eq!(load_contributors(&conn, &config).unwrap(), 2);
+
+        for (id, author, hours) in [
+            (1, "alice", 10),
+            (2, "alice", 20),
+            (3, "carol", 8),
+            (4, "dave", 4),
+            (5, "dependabot[bot]", 1),
+        ] {
+            conn.execute(
+                "INSERT INTO pull_requests (id, repo, number, state, author, created_at, updated_at, merged_at, data)
+                 VALUES (?1, 'r', ?1, 'closed', ?2, '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z',
+                         strftime('%Y-%m-%dT%H:%M:%SZ', '2026-01-01', ?3 || ' hours'), '{}')",
+                params![id, author, hours],
+            )
+            .unwrap();
+        }
+
+        compute_merge_time_by_role(&conn, "2026-01-01").unwrap();
+
+        let rows: Vec<(String, i64, f64)> = conn
+            .prepare("SELECT role, prs_merged, round(avg_hours, 6) FROM merge_time_by_role ORDER BY role")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let row = |role: &str, n, hours| (role.to_string(), n, hours);
+        assert_eq!(
+            rows,
+            [
+                row("bot", 1, 1.0),
+                row("community", 1, 4.0),
+                row("core", 1, 8.0),
+                row("maintainer", 2, 15.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_roles_are_problems() {
+        let config: ContributorsConfig =
+            serde_yaml::from_str("contributors:\n  alice: owner\n").unwrap();
+        assert_eq!(config.problems().len(), 1);
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+macro_rules! sync_phases {
+    ($($variant:ident => $name:literal;)*) => {
+        /// A stage of `sync_repo` whose API calls are counted.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum SyncPhase {
+            $($variant),*
+        }
+
+        /// Every sync phase, in the order `sync_repo` runs them.
+        pub const SYNC_PHASES: &[SyncPhase] = &[$(SyncPhase::$variant),*];
+
+        impl SyncPhase {
+            /// The phase's name in `api_calls_*` keys and the `cost` report.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(SyncPhase::$variant => $name),*
+                }
+            }
+        }
+    };
+}
+
+sync_phases! {
+    PullRequests => "pull_requests";
+    Issues => "issues";
+    IssueComments => "issue_comments";
+    IssueEvents => "issue_events";
+    Discussions => "discussions";
+    PrComments => "pr_comments";
+    Stars => "stars";
+    Commits => "commits";
+    Workflows => "workflows";
+    CheckRuns => "check_runs";
+    Releases => "releases";
+    Milestones => "milestones";
+    DependabotAlerts => "dependabot_alerts";
+    BranchProtection => "branch_protection";
+    PrTemplate => "pr_template";
+}
+
+/// `app_state` key counting completed `sync_org` runs.
+pub const SYNC_RUNS_KEY: &str = "api_sync_runs";
+
+/// `app_state` key holding the running API call count for one phase of one repo.
+pub fn calls_key(phase: &str, org: &str, repo: &str) -> String {
+    format!("api_calls_{}_{}_{}", phase, org, repo)
+}
+
+/// API calls spent on one sync phase, summed across repos.
+pub struct PhaseCost {
+    pub phase: &'static str,
+    pub calls: i64,
+    pub pct_of_total: f64,
+    /// `calls` divided by the number of recorded sync runs.
+    pub avg_per_sync: f64,
+}
+
+/// Per-phase totals from the `api_calls_*` keys, most expensive first. Phases with no
+/// recorded calls are included with zeros so the table always lists every phase.
+pub fn phase_costs(conn: &Connection) -> Result<Vec<PhaseCost>> {
+    let runs: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(CAST(value AS INTEGER)), 0) FROM app_state WHERE key = ?1",
+        params![SYNC_RUNS_KEY],
+        |row| row.get(0),
+    )?;
+
+    let mut costs = Vec::new();
+    for &phase in SYNC_PHASES {
+        let prefix = format!("api_calls_{}_", phase.name());
+        let calls: i64 = conn.query_row(
+            "SELECT COALESCE(SUM(CAST(value AS INTEGER)), 0) FROM app_state
+             WHERE substr(key, 1, length(?1)) = ?1",
+            params![prefix],
+            |row| row.get(0),
+        )?;
+        costs.push(PhaseCost {
+            phase: phase.name(),
+            calls,
+            pct_of_total: 0.0,
+            avg_per_sync: if runs > 0 {
+                calls as f64 / runs as f64
+            } else {
+                0.0
+            },
+        });
+    }
+
+    let total: i64 = costs.iter().map(|c| c.calls).sum();
+    if total > 0 {
+        for cost in &mut costs {
+            cost.pct_of_total = cost.calls as f64 * 100.0 / total as f64;
+        }
+    }
+    costs.sort_by_key(|c| std::cmp::Reverse(c.calls));
+    Ok(costs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phase_costs_sum_repos_and_list_every_phase() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        for (key, value) in [
+            (calls_key("issues", "o", "a"), "30"),
+            (calls_key("issues", "o", "b"), "10"),
+            (calls_key("issue_comments", "o", "a"), "60"),
+            (SYNC_RUNS_KEY.to_string(), "4"),
+        ] {
+            conn.execute(
+                "INSERT INTO app_state (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )
+            .unwrap();
+        }
+
+        let costs = phase_costs(&conn).unwrap();
+
+        assert_eq!(costs.len(), SYNC_PHASES.len());
+        assert_eq!(costs[0].phase, "issue_comments");
+        assert_eq!(costs[0].calls, 60);
+        assert_eq!(costs[0].pct_of_total, 60.0);
+        assert_eq!(costs[0].avg_per_sync, 15.0);
+        assert_eq!(costs[1].phase, "issues");
+        assert_eq!(costs[1].calls, 40);
+        assert_eq!(costs[1].avg_per_sync, 10.0);
+        assert_eq!(costs[2].phase, "pull_requests");
+        assert!(costs[2..]
+            .iter()
+            .all(|c| c.calls == 0 && c.pct_of_total == 0.0));
+    }
+
+    #[test]
+    fn phase_costs_without_runs_average_to_zero() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        conn.execute(
+            "INSERT INTO app_state (key, value) VALUES (?1, '5')",
+            params![calls_key("stars", "o", "a")],
+        )
+        .unwrap();
+
+        let costs = phase_costs(&conn).unwrap();
+        assert_eq!(costs[0].phase, "stars");
+        assert_eq!(costs[0].pct_of_total, 100.0);
+        assert_eq!(costs[0].avg_per_sync, 0.0);
+    }
+}
@@ -1,7 +1,79 @@
+use crate::aggregates::{ORG_SUM_COLUMNS, ORG_WEIGHTED_COLUMNS};
+use crate::client::GHOST_AUTHOR;
 use anyhow::Result;
-use rusqlite::Connection;
+use rusqlite::{params, Connection};
 use std::path::Path;
 
+/// Columns added after the initial schema, grouped by table as (column, definition).
+/// Existing databases pick these up via ALTER TABLE on startup.
+const ADDED_COLUMNS: &[(&str, &[(&str, &str)])] = &[
+    ("goals", &[("unit", "TEXT")]),
+    ("issues", &[("state_reason", "TEXT")]),
+    (
+        "pull_requests",
+        &[
+            ("additions", "INTEGER"),
+            ("deletions", "INTEGER"),
+            ("changed_files", "INTEGER"),
+            ("linked_issues", "TEXT"),
+        ],
+    ),
+    (
+        "daily_metrics",
+        &[
+            ("issues_stale_closed", "INTEGER DEFAULT 0"),
+            ("issues_resolved_closed", "INTEGER DEFAULT 0"),
+            ("qa_discussions_opened", "INTEGER DEFAULT 0"),
+            ("qa_discussions_answered", "INTEGER DEFAULT 0"),
+            ("discussions_answered_rate", "REAL"),
+            ("prs_merged_xs", "INTEGER DEFAULT 0"),
+            ("prs_merged_s", "INTEGER DEFAULT 0"),
+            ("prs_merged_m", "INTEGER DEFAULT 0"),
+            ("prs_merged_l", "INTEGER DEFAULT 0"),
+            ("prs_merged_xl", "INTEGER DEFAULT 0"),
+            ("open_items_over_7d", "INTEGER DEFAULT 0"),
+            ("open_items_over_30d", "INTEGER DEFAULT 0"),
+            ("open_items_over_90d", "INTEGER DEFAULT 0"),
+            ("pr_description_quality", "REAL"),
+            ("community_health_score", "REAL"),
+            ("avg_time_to_assign_hours", "REAL"),
+            ("review_request_fulfillment_rate", "REAL"),
+            ("avg_issue_comment_lag_hours", "REAL"),
+            ("avg_ci_queue_time", "REAL"),
+            ("avg_ci_duration", "REAL"),
+            ("avg_commit_message_length", "REAL"),
+            ("conventional_commit_rate", "REAL"),
+            ("issue_response_rate", "REAL"),
+            ("avg_time_to_merge_after_approve_hours", "REAL"),
+            ("external_ci_failure_rate", "REAL"),
+            ("abandoned_prs", "INTEGER DEFAULT 0"),
+            ("avg_pr_churn_per_file", "REAL"),
+            ("time_to_first_response_business", "REAL"),
+            ("open_critical_vulnerabilities", "INTEGER DEFAULT 0"),
+            ("open_high_vulnerabilities", "INTEGER DEFAULT 0"),
+            ("prs_with_linked_issues", "INTEGER DEFAULT 0"),
+            ("linked_issue_close_rate", "REAL"),
+            ("pr_template_compliance_rate", "REAL"),
+            ("external_contributor_retention_rate", "REAL"),
+            ("issues_completed", "INTEGER DEFAULT 0"),
+            ("issues_not_planned", "INTEGER DEFAULT 0"),
+            ("issues_closed_unknown_reason", "INTEGER DEFAULT 0"),
+            ("avg_review_depth", "REAL"),
+            ("avg_pr_age_at_merge", "REAL"),
+            ("avg_pr_age_at_close_unmerged", "REAL"),
+            ("triage_sla_met_rate", "REAL"),
+            ("avg_commit_to_release_hours", "REAL"),
+            ("branch_cleanup_rate", "REAL"),
+            ("max_author_wip", "INTEGER DEFAULT 0"),
+            ("avg_author_wip", "REAL"),
+            ("bypassed_reviews", "INTEGER DEFAULT 0"),
+            ("prs_merged_internal", "INTEGER DEFAULT 0"),
+            ("prs_merged_external", "INTEGER DEFAULT 0"),
+        ],
+    ),
+    ("workflow_runs", &[("run_started_at", "TEXT")]),
+];
+
 pub fn init_db<P: AsRef<Path>>(path: P) -> Result<Connection> {
     let conn = Connection::open(path)?;
 
@@ -110,6 +182,32 @@ pub fn init_db<P: AsRef<Path>>(path: P) -> Result<Connection> {
         [],
     )?;
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS commit_files (
+            sha TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            filename TEXT NOT NULL,
+            additions INTEGER DEFAULT 0,
+            deletions INTEGER DEFAULT 0,
+            PRIMARY KEY (sha, filename)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS change_composition (
+            repo TEXT NOT NULL,
+            period TEXT NOT NULL,
+            docs_pct REAL NOT NULL,
+            tests_pct REAL NOT NULL,
+            src_pct REAL NOT NULL,
+            lines_changed INTEGER NOT NULL,
+            computed_at TEXT NOT NULL,
+            PRIMARY KEY (repo, period)
+        )",
+        [],
+    )?;
+
     conn.execute(
         "CREATE TABLE IF NOT EXISTS workflow_runs (
             id INTEGER PRIMARY KEY,
@@ -124,6 +222,486 @@ pub fn init_db<P: AsRef<Path>>(path: P) -> Result<Connection> {
         [],
     )?;
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS check_runs (
+            id INTEGER PRIMARY KEY,
+            repo TEXT NOT NULL,
+            sha TEXT NOT NULL,
+            name TEXT,
+            app TEXT,
+            status TEXT,
+            conclusion TEXT,
+            started_at TEXT,
+            completed_at TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS releases (
+            id INTEGER PRIMARY KEY,
+            repo TEXT NOT NULL,
+            tag_name TEXT NOT NULL,
+            name TEXT,
+            draft INTEGER NOT NULL DEFAULT 0,
+            prerelease INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            published_at TEXT
+        )",
+        [],
+    )?;
+
+    // `id` is the alert number, which is only unique within a repo.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS dependabot_alerts (
+            id INTEGER NOT NULL,
+            repo TEXT NOT NULL,
+            state TEXT NOT NULL,
+            severity TEXT,
+            package_name TEXT,
+            cve_id TEXT,
+            created_at TEXT NOT NULL,
+            fixed_at TEXT,
+            dismissed_at TEXT,
+            PRIMARY KEY (repo, id)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS milestones (
+            id INTEGER PRIMARY KEY,
+            repo TEXT NOT NULL,
+            number INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            state TEXT NOT NULL,
+            due_on TEXT,
+            created_at TEXT NOT NULL,
+            closed_at TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS org_events (
+            id TEXT PRIMARY KEY,
+            org TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            actor TEXT NOT NULL,
+            repo TEXT,
+            created_at TEXT NOT NULL,
+            payload TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS discussions (
+            id TEXT PRIMARY KEY,
+            repo TEXT NOT NULL,
+            number INTEGER NOT NULL,
+            author TEXT NOT NULL,
+            title TEXT,
+            category_name TEXT,
+            is_answerable BOOLEAN DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            answer_chosen_at TEXT,
+            data TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS branch_protection_snapshots (
+            repo TEXT NOT NULL,
+            branch TEXT NOT NULL,
+            required_reviewers INTEGER DEFAULT 0,
+            require_status_checks BOOLEAN DEFAULT 0,
+            require_linear_history BOOLEAN DEFAULT 0,
+            allow_force_push BOOLEAN DEFAULT 0,
+            synced_at TEXT NOT NULL,
+            PRIMARY KEY (repo, branch, synced_at)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pr_templates (
+            repo TEXT PRIMARY KEY,
+            path TEXT NOT NULL,
+            body TEXT NOT NULL,
+            fetched_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_labels (
+            repo TEXT NOT NULL,
+            item_type TEXT NOT NULL,
+            item_number INTEGER NOT NULL,
+            label TEXT NOT NULL,
+            PRIMARY KEY (repo, item_type, item_number, label)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pr_closes_issue (
+            repo TEXT NOT NULL,
+            pr_number INTEGER NOT NULL,
+            issue_number INTEGER NOT NULL,
+            PRIMARY KEY (repo, pr_number, issue_number)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS issue_events (
+            id INTEGER PRIMARY KEY,
+            repo TEXT NOT NULL,
+            issue_number INTEGER NOT NULL,
+            event TEXT NOT NULL,
+            actor TEXT NOT NULL,
+            label TEXT,
+            created_at TEXT NOT NULL,
+            data TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS review_requests (
+            repo TEXT NOT NULL,
+            pr_number INTEGER NOT NULL,
+            reviewer TEXT NOT NULL,
+            is_team BOOLEAN NOT NULL,
+            requested_at TEXT NOT NULL,
+            PRIMARY KEY (repo, pr_number, reviewer, requested_at)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS label_dwell_time (
+            repo TEXT NOT NULL,
+            label TEXT NOT NULL,
+            avg_hours REAL DEFAULT 0,
+            count INTEGER DEFAULT 0,
+            PRIMARY KEY (repo, label)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS resolution_time_by_label (
+            repo TEXT NOT NULL,
+            label TEXT NOT NULL,
+            avg_resolution_hours REAL DEFAULT 0,
+            count INTEGER DEFAULT 0,
+            PRIMARY KEY (repo, label)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS potential_duplicates (
+            repo TEXT NOT NULL,
+            issue_number INTEGER NOT NULL,
+            similar_to INTEGER NOT NULL,
+            score REAL NOT NULL,
+            PRIMARY KEY (repo, issue_number, similar_to)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS repo_risk (
+            repo TEXT PRIMARY KEY,
+            window_days INTEGER NOT NULL,
+            bus_factor INTEGER NOT NULL,
+            total_commits INTEGER NOT NULL,
+            computed_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS review_pairing (
+            repo TEXT NOT NULL,
+            author TEXT NOT NULL,
+            reviewer TEXT NOT NULL,
+            count INTEGER NOT NULL,
+            PRIMARY KEY (repo, author, reviewer)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS contributor_leaderboard (
+            period TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            contributor TEXT NOT NULL,
+            prs INTEGER DEFAULT 0,
+            commits INTEGER DEFAULT 0,
+            reviews INTEGER DEFAULT 0,
+            comments INTEGER DEFAULT 0,
+            score REAL NOT NULL,
+            computed_at TEXT NOT NULL,
+            PRIMARY KEY (period, repo, contributor)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS review_wait_by_size (
+            date TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            size_bucket TEXT NOT NULL,
+            avg_first_review_hours REAL NOT NULL,
+            count INTEGER NOT NULL,
+            PRIMARY KEY (date, repo, size_bucket)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS review_fanout (
+            date TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            requested INTEGER NOT NULL,
+            responded INTEGER NOT NULL,
+            team_requested INTEGER NOT NULL,
+            team_responded INTEGER NOT NULL,
+            PRIMARY KEY (date, repo)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS author_wip (
+            date TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            author TEXT NOT NULL,
+            open_prs INTEGER NOT NULL,
+            PRIMARY KEY (date, repo, author)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS merge_batches (
+            repo TEXT NOT NULL,
+            base_ref TEXT NOT NULL,
+            batch_time TEXT NOT NULL,
+            batch_size INTEGER NOT NULL,
+            PRIMARY KEY (repo, base_ref, batch_time)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS newcomer_pr_size (
+            date TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            is_first_time INTEGER NOT NULL,
+            size_bucket TEXT NOT NULL,
+            count INTEGER DEFAULT 0,
+            PRIMARY KEY (date, repo, is_first_time, size_bucket)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS merge_hour_distribution (
+            repo TEXT NOT NULL,
+            timezone TEXT NOT NULL,
+            hour INTEGER NOT NULL,
+            count INTEGER DEFAULT 0,
+            PRIMARY KEY (repo, timezone, hour)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reviewer_latency_pct (
+            reviewer TEXT PRIMARY KEY,
+            p50_hours REAL NOT NULL,
+            p90_hours REAL NOT NULL,
+            count INTEGER NOT NULL,
+            computed_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS review_concentration (
+            repo TEXT PRIMARY KEY,
+            total_reviews INTEGER NOT NULL,
+            distinct_pairs INTEGER NOT NULL,
+            top_pair_share REAL NOT NULL,
+            computed_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS community_health (
+            date TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            responsiveness REAL NOT NULL,
+            merge_rate REAL NOT NULL,
+            star_growth REAL NOT NULL,
+            contributor_diversity REAL NOT NULL,
+            score REAL NOT NULL,
+            PRIMARY KEY (date, repo)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS org_percentile_metrics (
+            date TEXT NOT NULL,
+            metric TEXT NOT NULL,
+            p50 REAL,
+            p90 REAL,
+            p99 REAL,
+            PRIMARY KEY (date, metric)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS package_downloads (
+            package TEXT NOT NULL,
+            registry TEXT NOT NULL,
+            date TEXT NOT NULL,
+            downloads INTEGER DEFAULT 0,
+            PRIMARY KEY (package, registry, date)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pypi_version_downloads (
+            package TEXT NOT NULL,
+            version TEXT NOT NULL,
+            date TEXT NOT NULL,
+            downloads INTEGER DEFAULT 0,
+            PRIMARY KEY (package, version, date)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS npm_version_downloads (
+            package TEXT NOT NULL,
+            version TEXT NOT NULL,
+            week_start TEXT NOT NULL,
+            downloads INTEGER DEFAULT 0,
+            PRIMARY KEY (package, version, week_start)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS package_version_adoption (
+            package TEXT NOT NULL,
+            date TEXT NOT NULL,
+            latest_version TEXT NOT NULL,
+            latest_version_adoption_rate REAL,
+            PRIMARY KEY (package, date)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS repo_mappings (
+            package TEXT NOT NULL,
+            registry TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            PRIMARY KEY (package, registry)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS download_star_ratio (
+            package_name TEXT NOT NULL,
+            week_start TEXT NOT NULL,
+            downloads INTEGER DEFAULT 0,
+            stars INTEGER DEFAULT 0,
+            ratio REAL,
+            PRIMARY KEY (package_name, week_start)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS goals (
+            metric TEXT PRIMARY KEY,
+            goal REAL NOT NULL,
+            direction TEXT NOT NULL,
+            warning_ratio REAL NOT NULL,
+            critical_ratio REAL NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS contributor_roles (
+            login TEXT PRIMARY KEY,
+            role TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS merge_time_by_role (
+            date TEXT NOT NULL,
+            repo TEXT NOT NULL,
+            role TEXT NOT NULL,
+            prs_merged INTEGER NOT NULL,
+            avg_hours REAL,
+            PRIMARY KEY (date, repo, role)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS team_members (
+            login TEXT NOT NULL,
+            team TEXT NOT NULL,
+            PRIMARY KEY (login, team)
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_runs (
+            run_id TEXT PRIMARY KEY,
+            started_at TEXT NOT NULL,
+            finished_at TEXT,
+            command TEXT NOT NULL,
+            repos_synced INTEGER,
+            status TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sync_errors (
+            repo TEXT NOT NULL,
+            stage TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            message TEXT NOT NULL,
+            occurred_at TEXT NOT NULL,
+            PRIMARY KEY (repo, stage, occurred_at)
+        )",
+        [],
+    )?;
+
     conn.execute(
         "CREATE TABLE IF NOT EXISTS daily_metrics (
             date TEXT NOT NULL,
@@ -158,6 +736,32 @@ pub fn init_db<P: AsRef<Path>>(path: P) -> Result<Connection> {
         [],
     )?;
 
+    for (table, columns) in ADDED_COLUMNS {
+        for (column, definition) in *columns {
+            add_column(&conn, table, column, definition)?;
+        }
+    }
+
+    // Columns follow the rollup definition in aggregates, so they're added the same way.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS org_daily_metrics (date TEXT PRIMARY KEY)",
+        [],
+    )?;
+    for column in ORG_SUM_COLUMNS {
+        add_column(&conn, "org_daily_metrics", column, "INTEGER DEFAULT 0")?;
+    }
+    for (column, _) in ORG_WEIGHTED_COLUMNS {
+        add_column(&conn, "org_daily_metrics", column, "REAL")?;
+    }
+    // Org-only metrics, filled after the rollup instead of derived from daily_metrics.
+    for (column, definition) in [
+        ("team_additions", "INTEGER DEFAULT 0"),
+        ("team_departures", "INTEGER DEFAULT 0"),
+        ("milestone_delivery_rate", "REAL"),
+    ] {
+        add_column(&conn, "org_daily_metrics", column, definition)?;
+    }
+
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_pr_repo_updated ON pull_requests(repo, updated_at)",
         [],
@@ -187,6 +791,286 @@ pub fn init_db<P: AsRef<Path>>(path: P) -> Result<Connection> {
         "CREATE INDEX IF NOT EXISTS idx_workflows_repo_date ON workflow_runs(repo, created_at)",
         [],
     )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_issue_events_repo_issue ON issue_events(repo, issue_number, event)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_discussions_repo_created ON discussions(repo, created_at)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_item_labels_label ON item_labels(repo, label)",
+        [],
+    )?;
+
+    // Views are recreated on every start so definition changes take effect.
+    conn.execute("DROP VIEW IF EXISTS goal_thresholds", [])?;
+    conn.execute(
+        "CREATE VIEW goal_thresholds AS
+         SELECT
+            metric,
+            goal,
+            direction,
+            unit,
+            CASE WHEN direction = 'higher_is_better' THEN goal / warning_ratio ELSE goal * warning_ratio END as warning,
+            CASE WHEN direction = 'higher_is_better' THEN goal / critical_ratio ELSE goal * critical_ratio END as critical
+         FROM goals",
+        [],
+    )?;
+
+    // Packages linked to the same repo are one group, so differently named PyPI and npm
+    // packages line up; unlinked packages are grouped by name.
+    conn.execute("DROP VIEW IF EXISTS download_registry_comparison", [])?;
+    conn.execute(
+        "CREATE VIEW download_registry_comparison AS
+         SELECT
+            COALESCE(m.repo, d.package) as package_group,
+            d.date,
+            SUM(CASE WHEN d.registry = 'pypi' THEN d.downloads ELSE 0 END) as pypi_downloads,
+            SUM(CASE WHEN d.registry = 'npm' THEN d.downloads ELSE 0 END) as npm_downloads
+         FROM package_downloads d
+         LEFT JOIN repo_mappings m ON m.package = d.package AND m.registry = d.registry
+         GROUP BY package_group, d.date",
+        [],
+    )?;
+
+    migrate_missing_authors(&conn)?;
+    migrate_lowercase_authors(&conn)?;
+    migrate_state_reason(&conn)?;
 
     Ok(conn)
 }
+
+/// Author/actor columns that used to get "unknown" or "" when GitHub returned no user.
+const AUTHOR_COLUMNS: &[(&str, &str)] = &[
+    ("issues", "author"),
+    ("pull_requests", "author"),
+    ("issue_comments", "author"),
+    ("pr_reviews", "author"),
+    ("pr_review_comments", "author"),
+    ("discussions", "author"),
+    ("issue_events", "actor"),
+];
+
+/// One-off rewrite of the old missing-author placeholders to `GHOST_AUTHOR`.
+fn migrate_missing_authors(conn: &Connection) -> Result<()> {
+    let done = conn
+        .query_row(
+            "SELECT 1 FROM app_state WHERE key = 'migrated_ghost_authors'",
+            [],
+            |_| Ok(()),
+        )
+        .is_ok();
+    if done {
+        return Ok(());
+    }
+
+    for (table, column) in AUTHOR_COLUMNS {
+        conn.execute(
+            &format!("UPDATE {table} SET {column} = ?1 WHERE {column} IN ('unknown', '')"),
+            params![GHOST_AUTHOR],
+        )?;
+    }
+    conn.execute(
+        "INSERT INTO app_state (key, value) VALUES ('migrated_ghost_authors', ?1)",
+        params![chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// One-off lowercasing of logins stored before `normalize_author`. `OR REPLACE` merges rows
+/// whose key only differed by case, e.g. the same stargazer stored as `Alice` and `alice`.
+fn migrate_lowercase_authors(conn: &Connection) -> Result<()> {
+    let done = conn
+        .query_row(
+            "SELECT 1 FROM app_state WHERE key = 'migrated_lowercase_authors'",
+            [],
+            |_| Ok(()),
+        )
+        .is_ok();
+    if done {
+        return Ok(());
+    }
+
+    let extra = [
+        ("commits", "author"),
+        ("stargazers", "user"),
+        ("review_requests", "reviewer"),
+    ];
+    for (table, column) in AUTHOR_COLUMNS.iter().chain(extra.iter()) {
+        conn.execute(
+            &format!("UPDATE OR REPLACE {table} SET {column} = lower({column}) WHERE {column} != lower({column})"),
+            [],
+        )?;
+    }
+    conn.execute(
+        "INSERT INTO app_state (key, value) VALUES ('migrated_lowercase_authors', ?1)",
+        params![chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Differences between the schema at `conn` and the one `init_db` creates: missing tables,
+/// missing columns and columns whose declared type differs. Extra tables and columns are
+/// ignored. Empty means the database matches.
+pub fn schema_drift(conn: &Connection) -> Result<Vec<String>> {
+    let expected = init_db(":memory:")?;
+    let actual_tables = table_names(conn)?;
+
+    let mut problems = Vec::new();
+    for table in table_names(&expected)? {
+        if !actual_tables.contains(&table) {
+            problems.push(format!("missing table {}", table));
+            continue;
+        }
+        let actual = table_columns(conn, &table)?;
+        for (column, ty) in table_columns(&expected, &table)? {
+            match actual.iter().find(|(c, _)| *c == column) {
+                None => problems.push(format!("missing column {}.{} ({})", table, column, ty)),
+                Some((_, found)) if !found.eq_ignore_ascii_case(&ty) => problems.push(format!(
+                    "column {}.{} is {}, expected {}",
+                    table, column, found, ty
+                )),
+                Some(_) => {}
+            }
+        }
+    }
+    Ok(problems)
+}
+
+fn table_names(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(names)
+}
+
+/// `(name, declared type)` for each column of `table`, in table order.
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(columns)
+}
+
+/// One-off backfill of `issues.state_reason` from the stored payloads of issues synced before
+/// the column existed.
+fn migrate_state_reason(conn: &Connection) -> Result<()> {
+    let done = conn
+        .query_row(
+            "SELECT 1 FROM app_state WHERE key = 'migrated_state_reason'",
+            [],
+            |_| Ok(()),
+        )
+        .is_ok();
+    if done {
+        return Ok(());
+    }
+
+    conn.execute(
+        "UPDATE issues SET state_reason = json_extract(data, '$.state_reason')
+         WHERE state_reason IS NULL AND json_valid(data)",
+        [],
+    )?;
+    conn.execute(
+        "INSERT INTO app_state (key, value) VALUES ('migrated_state_reason', ?1)",
+        params![chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|c| c == column);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresh_database_has_no_drift() {
+        let conn = init_db(":memory:").unwrap();
+        assert!(schema_drift(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn drift_reports_missing_tables_columns_and_type_changes() {
+        let conn = init_db(":memory:").unwrap();
+        conn.execute_batch(
+            "DROP TABLE milestones;
+             ALTER TABLE daily_metrics DROP COLUMN prs_merged_internal;
+             DROP TABLE app_state;
+             CREATE TABLE app_state (key TEXT PRIMARY KEY, value INTEGER NOT NULL);
+             CREATE TABLE scratch (x TEXT);",
+        )
+        .unwrap();
+
+        let drift = schema_drift(&conn).unwrap();
+        assert_eq!(
+            drift,
+            [
+                "column app_state.value is INTEGER, expected TEXT",
+                "missing column daily_metrics.prs_merged_internal (INTEGER)",
+                "missing table milestones",
+            ]
+        );
+    }
+
+    #[test]
+    fn registry_comparison_groups_mapped_packages_by_repo() {
+        let conn = init_db(":memory:").unwrap();
+        conn.execute_batch(
+            "INSERT INTO repo_mappings (package, registry, repo) VALUES
+                ('strands-agents', 'pypi', 'sdk'),
+                ('@strands-agents/sdk', 'npm', 'sdk');
+             INSERT INTO package_downloads (package, registry, date, downloads) VALUES
+                ('strands-agents', 'pypi', '2026-01-01', 100),
+                ('@strands-agents/sdk', 'npm', '2026-01-01', 40),
+                ('@strands-agents/sdk', 'npm', '2026-01-02', 7),
+                ('unmapped', 'pypi', '2026-01-01', 3);",
+        )
+        .unwrap();
+
+        let rows: Vec<(String, String, i64, i64)> = conn
+            .prepare(
+                "SELECT package_group, date, pypi_downloads, npm_downloads
+                 FROM download_registry_comparison ORDER BY package_group, date",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let row = |g: &str, d: &str, p, n| (g.to_string(), d.to_string(), p, n);
+        assert_eq!(
+            rows,
+            [
+                row("sdk", "2026-01-01", 100, 40),
+                row("sdk", "2026-01-02", 0, 7),
+                row("unmapped", "2026-01-01", 3, 0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use anyhow::Result;
+use fs2::FileExt;
+use rusqlite::{params, Connection, MAIN_DB};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Lock file next to the database, e.g. `.metrics.lock` for `metrics.db`.
+pub fn lock_path(db_path: &Path) -> PathBuf {
+    let stem = db_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "metrics".to_string());
+    db_path.with_file_name(format!(".{}.lock", stem))
+}
+
+/// Takes an exclusive lock on the database's lock file, so two instances can't write the same
+/// database at once. The lock is held until the returned file is dropped. `None` means another
+/// instance already holds it.
+pub fn try_lock_db(db_path: &Path) -> Result<Option<File>> {
+    let file = File::create(lock_path(db_path))?;
+    match file.try_lock_exclusive() {
+        Ok(()) => Ok(Some(file)),
+        Err(e) if e.kind() == fs2::lock_contended_error().kind() => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Maintenance helpers on top of the raw connection.
+pub trait DbUtils {
+    /// Runs `VACUUM` if free pages make up more than `threshold` (0..1) of the file.
+    /// Returns whether it vacuumed.
+    fn vacuum_if_fragmented(&self, threshold: f64) -> Result<bool>;
+
+    /// Copies a consistent snapshot of the database to `replica` with SQLite's backup API. The
+    /// copy is written next to it and renamed into place read-only, so readers never see a
+    /// half-written file.
+    fn write_replica(&self, replica: &Path) -> Result<()>;
+}
+
+impl DbUtils for Connection {
+    fn vacuum_if_fragmented(&self, threshold: f64) -> Result<bool> {
+        let free: i64 = self.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        let pages: i64 = self.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        if pages == 0 || (free as f64 / pages as f64) <= threshold {
+            return Ok(false);
+        }
+        self.execute_batch("VACUUM")?;
+        Ok(true)
+    }
+
+    fn write_replica(&self, replica: &Path) -> Result<()> {
+        let mut tmp = replica.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        if tmp.exists() {
+            std::fs::remove_file(&tmp)?;
+        }
+
+        self.backup(MAIN_DB, &tmp, None)?;
+        let mut permissions = std::fs::metadata(&tmp)?.permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&tmp, permissions)?;
+        std::fs::rename(&tmp, replica)?;
+        Ok(())
+    }
+}
+
+/// Free-page share above which `purge` and `prune` vacuum the file.
+pub const VACUUM_THRESHOLD: f64 = 0.2;
+
+/// Hard-deletes issues and PRs that sweep marked as deleted, along with their comments,
+/// events and labels. Returns the number of items removed.
+pub fn purge_deleted(conn: &Connection) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    for (dependent, number_col, item_type) in [
+        ("issue_comments", "issue_number", "issue"),
+        ("issue_events", "issue_number", "issue"),
+    ] {
+        tx.execute(
+            &format!(
+                "DELETE FROM {dependent} WHERE EXISTS (
+                    SELECT 1 FROM issues i
+                    WHERE i.repo = {dependent}.repo AND i.number = {dependent}.{number_col} AND i.deleted_at IS NOT NULL
+                 )"
+            ),
+            [],
+        )?;
+        tx.execute(
+            "DELETE FROM item_labels WHERE item_type = ?1 AND EXISTS (
+                SELECT 1 FROM issues i
+                WHERE i.repo = item_labels.repo AND i.number = item_labels.item_number AND i.deleted_at IS NOT NULL
+             )",
+            params![item_type],
+        )?;
+    }
+    for dependent in [
+        "pr_reviews",
+        "pr_review_comments",
+        "pr_closes_issue",
+        "review_requests",
+    ] {
+        tx.execute(
+            &format!(
+                "DELETE FROM {dependent} WHERE EXISTS (
+                    SELECT 1 FROM pull_requests p
+                    WHERE p.repo = {dependent}.repo AND p.number = {dependent}.pr_number AND p.deleted_at IS NOT NULL
+                 )"
+            ),
+            [],
+        )?;
+    }
+    tx.execute(
+        "DELETE FROM item_labels WHERE item_type = 'pr' AND EXISTS (
+            SELECT 1 FROM pull_requests p
+            WHERE p.repo = item_labels.repo AND p.number = item_labels.item_number AND p.deleted_at IS NOT NULL
+         )",
+        [],
+    )?;
+    let issues = tx.execute("DELETE FROM issues WHERE deleted_at IS NOT NULL", [])?;
+    let prs = tx.execute("DELETE FROM pull_requests WHERE deleted_at IS NOT NULL", [])?;
+    tx.commit()?;
+    Ok(issues + prs)
+}
+
+/// Tables whose `data` payload `trim_raw` shrinks, with the timestamp that decides a row's age.
+const TRIM_TABLES: &[(&str, &str)] = &[
+    ("issues", "updated_at"),
+    ("pull_requests", "updated_at"),
+    ("issue_comments", "updated_at"),
+    ("pr_review_comments", "updated_at"),
+    ("pr_reviews", "submitted_at"),
+    ("issue_events", "created_at"),
+    ("discussions", "updated_at"),
+];
+
+/// Replaces payloads last touched before `cutoff` (YYYY-MM-DD) with the few fields
+/// `compute_metrics` reads from them, keeps commits but drops their messages, and leaves the
+/// structural columns and `daily_metrics` alone. Rows updated again later are resynced with the
+/// full payload. Returns the number of rows trimmed.
+pub fn trim_raw(conn: &Connection, cutoff: &str) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut trimmed = 0;
+    for (table, age_col) in TRIM_TABLES {
+        trimmed += tx.execute(
+            &format!(
+                "UPDATE {table} SET data = json_object(
+                     'trimmed', json('true'),
+                     'author_association', json_extract(data, '$.author_association'),
+                     'state_reason', json_extract(data, '$.state_reason'),
+                     'pull_request_review_id', json_extract(data, '$.pull_request_review_id'),
+                     'head', json_object('repo', json_object('id', json_extract(data, '$.head.repo.id'))),
+                     'base', json_object(
+                         'ref', json_extract(data, '$.base.ref'),
+                         'repo', json_object('id', json_extract(data, '$.base.repo.id'))
+                     ),
+                     'merge_commit_sha', json_extract(data, '$.merge_commit_sha'),
+                     'requested_reviewer', json_object('login', json_extract(data, '$.requested_reviewer.login')),
+                     'body_length', COALESCE(
+                         json_extract(data, '$.body_length'),
+                         length(trim(COALESCE(json_extract(data, '$.body'), '')))
+                     )
+                 )
+                 WHERE date({age_col}) < ?1
+                   AND json_valid(data)
+                   AND json_extract(data, '$.trimmed') IS NULL"
+            ),
+            params![cutoff],
+        )?;
+    }
+    trimmed += tx.execute(
+        "UPDATE commits SET message = NULL WHERE date(date) < ?1 AND message IS NOT NULL",
+        params![cutoff],
+    )?;
+    tx.commit()?;
+    Ok(trimmed)
+}
+
+/// Removes every row belonging to `repo` from all tables with a `repo` column, e.g. after a
+/// repo is archived or excluded. Returns the number of rows removed.
+/// Its sync cursors in `app_state` are cleared too, so re-adding it starts a full sync.
+pub fn prune_repo(conn: &Connection, org: &str, repo: &str) -> Result<usize> {
+    let mut stmt = conn.prepare(
+        "SELECT m.name FROM sqlite_master m
+         WHERE m.type = 'table'
+           AND EXISTS (SELECT 1 FROM pragma_table_info(m.name) c WHERE c.name = 'repo')",
+    )?;
+    let tables = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let tx = conn.unchecked_transaction()?;
+    let mut removed = 0;
+    for table in &tables {
+        removed += tx.execute(
+            &format!("DELETE FROM {} WHERE repo = ?1", table),
+            params![repo],
+        )?;
+    }
+    for prefix in ["last_sync", "last_cursor_issues"] {
+        tx.execute(
+            "DELETE FROM app_state WHERE key = ?1",
+            params![format!("{}_{}_{}", prefix, org, repo)],
+        )?;
+    }
+    tx.commit()?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_lock_on_the_same_db_fails_until_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("metrics.db");
+        assert_eq!(lock_path(&db), dir.path().join(".metrics.lock"));
+
+        let first = try_lock_db(&db).unwrap();
+        assert!(first.is_some());
+        assert!(try_lock_db(&db).unwrap().is_none());
+
+        drop(first);
+        assert!(try_lock_db(&db).unwrap().is_some());
+    }
+
+    #[test]
+    fn replica_is_a_read_only_snapshot_replaced_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let replica = dir.path().join("replica.db");
+        std::fs::write(dir.path().join("replica.db.tmp"), "stale").unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (x INTEGER); INSERT INTO t VALUES (1);")
+            .unwrap();
+
+        let rows = |path: &Path| -> i64 {
+            Connection::open(path)
+                .unwrap()
+                .query_row("SELECT count(*) FROM t", [], |row| row.get(0))
+                .unwrap()
+        };
+        conn.write_replica(&replica).unwrap();
+        assert_eq!(rows(&replica), 1);
+
+        conn.execute("INSERT INTO t VALUES (2)", []).unwrap();
+        conn.write_replica(&replica).unwrap();
+        assert_eq!(rows(&replica), 2);
+        assert!(std::fs::metadata(&replica)
+            .unwrap()
+            .permissions()
+            .readonly());
+        assert!(!dir.path().join("replica.db.tmp").exists());
+    }
+
+    #[test]
+    fn trimming_payloads_leaves_recomputed_metrics_unchanged() {
+        use crate::aggregates::compute_metrics;
+        use crate::config::MetricsConfig;
+        use chrono::{Duration, Utc};
+
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let now = Utc::now();
+        let day = |d: i64| (now - Duration::days(d)).format("%Y-%m-%d").to_string();
+        let (created, merged) = ((now - Duration::days(2)).to_rfc3339(), now.to_rfc3339());
+        for (number, author, data) in [
+            (
+                1,
+                "alice",
+                serde_json::json!({ "body": "x".repeat(60), "author_association": "CONTRIBUTOR" }),
+            ),
+            (
+                2,
+                "bob",
+                serde_json::json!({ "body": "short", "author_association": "MEMBER" }),
+            ),
+        ] {
+            conn.execute(
+                "INSERT INTO pull_requests (id, repo, number, state, author, created_at, updated_at, merged_at, closed_at, data)
+                 VALUES (?1, 'r', ?1, 'closed', ?2, ?3, ?4, ?4, ?4, ?5)",
+                params![number, author, created, merged, data.to_string()],
+            )
+            .unwrap();
+        }
+        conn.execute_batch(&format!(
+            "INSERT INTO issues (id, repo, number, state, author, created_at, updated_at, closed_at, state_reason, data)
+                VALUES (3, 'r', 3, 'closed', 'carol', '{created}', '{merged}', '{merged}', 'completed', '{{\"state_reason\":\"completed\",\"body\":\"long text\"}}');
+             INSERT INTO pr_reviews (id, repo, pr_number, state, author, submitted_at, data)
+                VALUES (10, 'r', 1, 'COMMENTED', 'bob', '{merged}', '{{\"body\":\"looks good\"}}');
+             INSERT INTO pr_review_comments (id, repo, pr_number, author, created_at, updated_at, data)
+                VALUES (20, 'r', 1, 'bob', '{merged}', '{merged}', '{{\"pull_request_review_id\":10,\"body\":\"nit\"}}');
+             INSERT INTO daily_metrics (date, repo) VALUES ('{yesterday}', 'r');",
+            yesterday = day(1)
+        ))
+        .unwrap();
+
+        let snapshot = |conn: &Connection| -> Vec<Vec<String>> {
+            let mut stmt = conn
+                .prepare("SELECT * FROM daily_metrics ORDER BY date, repo")
+                .unwrap();
+            let columns = stmt.column_count();
+            stmt.query_map([], |row| {
+                (0..columns)
+                    .map(|i| row.get_ref(i).map(|v| format!("{:?}", v)))
+                    .collect()
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+        };
+        let config = MetricsConfig::default();
+        compute_metrics(&conn, &config).unwrap();
+        let before = snapshot(&conn);
+        let quality: f64 = conn
+            .query_row(
+                "SELECT pr_description_quality FROM daily_metrics WHERE date = date('now')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(quality, 0.5);
+
+        assert_eq!(trim_raw(&conn, &day(-1)).unwrap(), 5);
+        assert_eq!(trim_raw(&conn, &day(-1)).unwrap(), 0);
+        compute_metrics(&conn, &config).unwrap();
+        assert_eq!(snapshot(&conn), before);
+    }
+}
@@ -0,0 +1,503 @@
+use anyhow::{bail, Result};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+pub const KNOWN_REGISTRIES: &[&str] = &["pypi", "npm"];
+
+#[derive(Deserialize, Debug)]
+pub struct PackagesConfig {
+    pub packages: Vec<PackageEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PackageEntry {
+    pub name: String,
+    /// One of "pypi" or "npm". Shorthand for a one-element `registries`.
+    #[serde(default)]
+    pub registry: Option<String>,
+    /// Every registry the package is published to under this name.
+    #[serde(default)]
+    pub registries: Vec<String>,
+    /// Repo in the org this package is published from, if any.
+    pub repo: Option<String>,
+    /// Also fetch per-version download counts. Costs extra API calls, so it's opt-in.
+    #[serde(default)]
+    pub version_breakdown: bool,
+}
+
+impl PackagesConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)?;
+        Ok(serde_yaml::from_str(&raw)?)
+    }
+
+    /// Packages published to `registry`, whether listed under `registry` or `registries`.
+    pub fn packages_for_registry<'a>(
+        &'a self,
+        registry: &'a str,
+    ) -> impl Iterator<Item = &'a PackageEntry> + 'a {
+        self.packages
+            .iter()
+            .filter(move |p| p.registries().contains(&registry))
+    }
+
+    /// Every validation problem across all packages, one message each.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for p in &self.packages {
+            let registries = p.registries();
+            if registries.is_empty() {
+                problems.push(format!(
+                    "Package '{}': needs a registry or registries",
+                    p.name
+                ));
+            }
+            for registry in registries {
+                if !KNOWN_REGISTRIES.contains(&registry) {
+                    problems.push(format!(
+                        "Package '{}': registry must be one of {:?}, got '{}'",
+                        p.name, KNOWN_REGISTRIES, registry
+                    ));
+                }
+            }
+        }
+        problems
+    }
+}
+
+impl PackageEntry {
+    /// `registry` followed by `registries`, without duplicates.
+    pub fn registries(&self) -> Vec<&str> {
+        let mut out: Vec<&str> = Vec::new();
+        for r in self.registry.iter().chain(&self.registries) {
+            if !out.contains(&r.as_str()) {
+                out.push(r);
+            }
+        }
+        out
+    }
+}
+
+/// Records which repo each package belongs to so downloads can be joined against repo metrics.
+/// A package on several registries gets one mapping per registry.
+pub fn store_repo_mappings(conn: &Connection, config: &PackagesConfig) -> Result<()> {
+    for pkg in &config.packages {
+        for registry in pkg.registries() {
+            match &pkg.repo {
+                Some(repo) => conn.execute(
+                    "INSERT OR REPLACE INTO repo_mappings (package, registry, repo) VALUES (?1, ?2, ?3)",
+                    params![pkg.name, registry, repo],
+                )?,
+                None => conn.execute(
+                    "DELETE FROM repo_mappings WHERE package = ?1 AND registry = ?2",
+                    params![pkg.name, registry],
+                )?,
+            };
+        }
+    }
+    Ok(())
+}
+
+/// Result of one fetch made by `sync-downloads`.
+#[derive(Serialize, Debug)]
+pub struct SyncOutcome {
+    pub package: String,
+    pub registry: String,
+    /// "downloads" for daily totals, "versions" for the per-version breakdown.
+    pub step: &'static str,
+    /// Days or rows stored, when the fetch succeeded.
+    pub rows: Option<usize>,
+    pub error: Option<String>,
+}
+
+/// Every outcome of a `sync-downloads` run, used for the summary line and the exit code.
+#[derive(Serialize, Debug, Default)]
+pub struct SyncSummary {
+    pub outcomes: Vec<SyncOutcome>,
+}
+
+impl SyncSummary {
+    pub fn record(
+        &mut self,
+        package: &str,
+        registry: &str,
+        step: &'static str,
+        result: Result<usize>,
+    ) -> &SyncOutcome {
+        let (rows, error) = match result {
+            Ok(rows) => (Some(rows), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        self.outcomes.push(SyncOutcome {
+            package: package.to_string(),
+            registry: registry.to_string(),
+            step,
+            rows,
+            error,
+        });
+        self.outcomes.last().unwrap()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.error.is_some()).count()
+    }
+
+    pub fn succeeded(&self) -> usize {
+        self.outcomes.len() - self.failed()
+    }
+
+    /// Registries where every fetch failed, which usually means the registry (or its API
+    /// key) is broken rather than one package.
+    pub fn failed_registries(&self) -> Vec<&str> {
+        let mut by_registry: BTreeMap<&str, bool> = BTreeMap::new();
+        for o in &self.outcomes {
+            let all_failed = by_registry.entry(&o.registry).or_insert(true);
+            *all_failed &= o.error.is_some();
+        }
+        by_registry
+            .into_iter()
+            .filter(|(_, all_failed)| *all_failed)
+            .map(|(registry, _)| registry)
+            .collect()
+    }
+
+    /// Whether the run should exit non-zero: a whole registry failed, or with `strict`, any
+    /// single fetch did.
+    pub fn is_failure(&self, strict: bool) -> bool {
+        !self.failed_registries().is_empty() || (strict && self.failed() > 0)
+    }
+}
+
+/// Fetches daily download counts for one package from one of its registries.
+/// Returns the number of days stored.
+pub async fn sync_package(
+    http: &reqwest::Client,
+    conn: &Connection,
+    pkg: &PackageEntry,
+    registry: &str,
+) -> Result<usize> {
+    let days = match registry {
+        "pypi" => fetch_pypi_downloads(http, &pkg.name).await?,
+        "npm" => fetch_npm_downloads(http, &pkg.name).await?,
+        other => bail!("Unknown registry '{}' for package {}", other, pkg.name),
+    };
+
+    for (date, downloads) in &days {
+        conn.execute(
+            "INSERT OR REPLACE INTO package_downloads (package, registry, date, downloads) VALUES (?1, ?2, ?3, ?4)",
+            params![pkg.name, registry, date, downloads],
+        )?;
+    }
+
+    Ok(days.len())
+}
+
+async fn fetch_pypi_downloads(http: &reqwest::Client, package: &str) -> Result<Vec<(String, i64)>> {
+    let url = format!(
+        "https://pypistats.org/api/packages/{}/overall?mirrors=false",
+        package
+    );
+    let body: Value = http
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let days = body
+        .get("data")
+        .and_then(|d| d.as_array())
+        .map(|rows| {
+            rows.iter()
+                .filter(|r| r.get("category").and_then(|c| c.as_str()) == Some("without_mirrors"))
+                .filter_map(|r| {
+                    let date = r.get("date")?.as_str()?.to_string();
+                    let downloads = r.get("downloads")?.as_i64()?;
+                    Some((date, downloads))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(days)
+}
+
+async fn fetch_npm_downloads(http: &reqwest::Client, package: &str) -> Result<Vec<(String, i64)>> {
+    let url = format!(
+        "https://api.npmjs.org/downloads/range/last-year/{}",
+        package
+    );
+    let body: Value = http
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let days = body
+        .get("downloads")
+        .and_then(|d| d.as_array())
+        .map(|rows| {
+            rows.iter()
+                .filter_map(|r| {
+                    let date = r.get("day")?.as_str()?.to_string();
+                    let downloads = r.get("downloads")?.as_i64()?;
+                    Some((date, downloads))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(days)
+}
+
+/// Fetches per-version daily downloads for a PyPI package. pypistats only reports totals,
+/// so this uses the pepy.tech API, which needs an API key. Returns the number of rows stored.
+pub async fn sync_pypi_versions(
+    http: &reqwest::Client,
+    conn: &Connection,
+    package: &str,
+    api_key: &str,
+) -> Result<usize> {
+    let url = format!("https://api.pepy.tech/api/v2/projects/{}", package);
+    let body: Value = http
+        .get(&url)
+        .header("X-API-Key", api_key)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    // { "downloads": { "2024-01-01": { "1.0.0": 12, ... }, ... } }
+    let mut rows = 0;
+    if let Some(days) = body.get("downloads").and_then(|d| d.as_object()) {
+        for (date, versions) in days {
+            let Some(versions) = versions.as_object() else {
+                continue;
+            };
+            for (version, downloads) in versions {
+                conn.execute(
+                    "INSERT OR REPLACE INTO pypi_version_downloads (package, version, date, downloads) VALUES (?1, ?2, ?3, ?4)",
+                    params![package, version, date, downloads.as_i64().unwrap_or(0)],
+                )?;
+                rows += 1;
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Stores last week's per-version downloads for an npm package, keyed by the first day of the
+/// 7-day window npm reports. Returns the number of versions stored.
+pub async fn sync_npm_version_downloads(
+    http: &reqwest::Client,
+    conn: &Connection,
+    package: &str,
+) -> Result<usize> {
+    // Scoped names need the slash escaped on this endpoint.
+    let url = format!(
+        "https://api.npmjs.org/versions/{}/last-week",
+        package.replace('/', "%2F")
+    );
+    let body: Value = http
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let week_start = (chrono::Utc::now().date_naive() - chrono::Duration::days(7))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let mut rows = 0;
+    if let Some(versions) = body.get("downloads").and_then(|d| d.as_object()) {
+        for (version, downloads) in versions {
+            conn.execute(
+                "INSERT OR REPLACE INTO npm_version_downloads (package, version, week_start, downloads) VALUES (?1, ?2, ?3, ?4)",
+                params![package, version, week_start, downloads.as_i64().unwrap_or(0)],
+            )?;
+            rows += 1;
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Share of each day's downloads going to the newest version, where "newest" is the version
+/// first seen most recently as of that day. Shows how quickly users pick up a release.
+pub fn compute_version_adoption(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT package, date, version, downloads FROM pypi_version_downloads ORDER BY package, date",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut by_day: BTreeMap<(String, String), Vec<(String, i64)>> = BTreeMap::new();
+    for (package, date, version, downloads) in rows {
+        by_day
+            .entry((package, date))
+            .or_default()
+            .push((version, downloads));
+    }
+
+    conn.execute("DELETE FROM package_version_adoption", [])?;
+    let mut first_seen: HashMap<(String, String), String> = HashMap::new();
+    for ((package, date), versions) in &by_day {
+        for (version, _) in versions {
+            first_seen
+                .entry((package.clone(), version.clone()))
+                .or_insert_with(|| date.clone());
+        }
+
+        let Some((latest, _)) = versions
+            .iter()
+            .max_by_key(|(v, _)| (&first_seen[&(package.clone(), v.clone())], v))
+        else {
+            continue;
+        };
+        let total: i64 = versions.iter().map(|(_, d)| d).sum();
+        let latest_downloads: i64 = versions
+            .iter()
+            .filter(|(v, _)| v == latest)
+            .map(|(_, d)| d)
+            .sum();
+        let rate = (total > 0).then(|| latest_downloads as f64 / total as f64);
+
+        conn.execute(
+            "INSERT INTO package_version_adoption (package, date, latest_version, latest_version_adoption_rate)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![package, date, latest, rate],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Weekly downloads divided by the mapped repo's star count at the end of that week.
+/// Weeks start on Monday. Packages without a repo mapping are skipped.
+pub fn compute_download_star_ratio(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM download_star_ratio", [])?;
+    conn.execute(
+        "INSERT INTO download_star_ratio (package_name, week_start, downloads, stars, ratio)
+         SELECT package, week_start, downloads, stars, CAST(downloads AS REAL) / NULLIF(stars, 0)
+         FROM (
+            SELECT
+                w.package,
+                w.week_start,
+                w.downloads,
+                (SELECT count(*) FROM stargazers s
+                 WHERE s.repo = w.repo
+                   AND date(s.starred_at) < date(w.week_start, '+7 days')) as stars
+            FROM (
+                SELECT
+                    d.package,
+                    m.repo,
+                    date(d.date, '-6 days', 'weekday 1') as week_start,
+                    SUM(d.downloads) as downloads
+                FROM package_downloads d
+                JOIN repo_mappings m ON m.package = d.package AND m.registry = d.registry
+                GROUP BY d.package, m.repo, week_start
+            ) w
+         )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn one_failed_package_fails_only_in_strict_mode() {
+        let mut summary = SyncSummary::default();
+        summary.record("a", "pypi", "downloads", Ok(30));
+        summary.record("b", "pypi", "downloads", Err(anyhow!("timeout")));
+        summary.record("a", "npm", "downloads", Ok(30));
+
+        assert!(summary.failed_registries().is_empty());
+        assert_eq!((summary.succeeded(), summary.failed()), (2, 1));
+        assert!(!summary.is_failure(false));
+        assert!(summary.is_failure(true));
+    }
+
+    #[test]
+    fn a_registry_where_everything_failed_is_a_failure() {
+        let mut summary = SyncSummary::default();
+        summary.record("a", "pypi", "downloads", Ok(30));
+        summary.record("a", "npm", "downloads", Err(anyhow!("401")));
+        summary.record("a", "npm", "versions", Err(anyhow!("401")));
+
+        assert_eq!(summary.failed_registries(), ["npm"]);
+        assert!(summary.is_failure(false));
+    }
+
+    #[test]
+    fn download_star_ratio_is_weekly_and_null_without_stars() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        conn.execute_batch(
+            "INSERT INTO repo_mappings (package, registry, repo) VALUES ('p', 'pypi', 'r'), ('q', 'npm', 's');
+             INSERT INTO package_downloads (package, registry, date, downloads) VALUES
+                ('p', 'pypi', '2026-01-05', 100),
+                ('p', 'pypi', '2026-01-11', 50),
+                ('q', 'npm', '2026-01-05', 10);
+             INSERT INTO stargazers (repo, user, starred_at) VALUES
+                ('r', 'a', '2026-01-01T00:00:00Z'),
+                ('r', 'b', '2026-01-11T23:00:00Z'),
+                ('r', 'c', '2026-01-12T00:00:00Z');",
+        )
+        .unwrap();
+
+        compute_download_star_ratio(&conn).unwrap();
+
+        let rows: Vec<(String, String, i64, i64, Option<f64>)> = conn
+            .prepare(
+                "SELECT package_name, week_start, downloads, stars, ratio FROM download_star_ratio
+                 ORDER BY package_name",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                (
+                    "p".to_string(),
+                    "2026-01-05".to_string(),
+                    150,
+                    2,
+                    Some(75.0)
+                ),
+                ("q".to_string(), "2026-01-05".to_string(), 10, 0, None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use rusqlite::types::{ToSqlOutput, Value as SqlValue, ValueRef};
+use rusqlite::{Connection, ToSql};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Write;
+use std::ops::Deref;
+
+/// One row-level change that a sync would have made.
+#[derive(Serialize, Debug)]
+pub struct PatchOp {
+    pub op: &'static str,
+    pub table: String,
+    pub key: Map<String, Value>,
+    pub changes: Map<String, Value>,
+}
+
+/// One line of the `--audit-log` file: a write that was applied during a live sync.
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    ts: String,
+    table: &'a str,
+    op: &'static str,
+    key: &'a Map<String, Value>,
+    affected: usize,
+}
+
+/// Connection handle used by the sync client. In live mode `execute` goes straight to SQLite;
+/// in dry-run mode writes are recorded as `PatchOp`s and never applied. Reads always hit the DB.
+pub struct DryRunConnection<'a> {
+    conn: &'a Connection,
+    ops: Option<RefCell<Vec<PatchOp>>>,
+    audit: Option<RefCell<File>>,
+}
+
+impl<'a> DryRunConnection<'a> {
+    /// A store that applies every write, for commands without a dry-run mode.
+    pub fn live(conn: &'a Connection) -> Self {
+        Self::new(conn, false)
+    }
+
+    pub fn new(conn: &'a Connection, dry_run: bool) -> Self {
+        Self {
+            conn,
+            ops: dry_run.then(|| RefCell::new(Vec::new())),
+            audit: None,
+        }
+    }
+
+    /// Appends a JSONL record for every write applied in live mode. Ignored in dry-run mode,
+    /// where the patch already lists every write.
+    pub fn set_audit_log(&mut self, file: File) {
+        self.audit = Some(RefCell::new(file));
+    }
+
+    pub fn execute(&self, sql: &str, params: &[&dyn ToSql]) -> rusqlite::Result<usize> {
+        match &self.ops {
+            None if self.audit.is_some() => self.execute_audited(sql, params),
+            None => self.conn.execute(sql, params),
+            Some(ops) => {
+                let (op, affected) = self.describe(sql, params)?;
+                ops.borrow_mut().push(op);
+                Ok(affected)
+            }
+        }
+    }
+
+    /// Describes the write before running it, so an upsert is logged as `insert` or `update`
+    /// depending on whether the key already existed. A failed log write is warned about rather
+    /// than failing the sync.
+    fn execute_audited(&self, sql: &str, params: &[&dyn ToSql]) -> rusqlite::Result<usize> {
+        let (op, _) = self.describe(sql, params)?;
+        let affected = self.conn.execute(sql, params)?;
+        if let Some(audit) = &self.audit {
+            let record = AuditRecord {
+                ts: chrono::Utc::now().to_rfc3339(),
+                table: &op.table,
+                op: op.op,
+                key: &op.key,
+                affected,
+            };
+            let line = serde_json::to_string(&record).unwrap_or_default();
+            if let Err(e) = writeln!(audit.borrow_mut(), "{}", line) {
+                tracing::warn!("Failed to write audit log: {}", e);
+            }
+        }
+        Ok(affected)
+    }
+
+    /// Drains the recorded operations. Empty in live mode.
+    pub fn take_patch(&self) -> Vec<PatchOp> {
+        self.ops
+            .as_ref()
+            .map(|ops| ops.borrow_mut().drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    /// Turns one of the statement shapes the client issues into a `PatchOp`:
+    /// `INSERT [OR REPLACE] INTO t (cols) VALUES (...)`, `UPDATE t SET ... WHERE ...`
+    /// and `DELETE FROM t WHERE ...`, with `AND`-joined equality predicates.
+    fn describe(&self, sql: &str, params: &[&dyn ToSql]) -> rusqlite::Result<(PatchOp, usize)> {
+        let sql = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        let upper = sql.to_uppercase();
+
+        if upper.starts_with("INSERT") {
+            let into = upper.find(" INTO ").map(|i| i + 6).unwrap_or(0);
+            let open = sql.find('(').unwrap_or(sql.len());
+            let table = sql[into..open].trim().to_string();
+            let close = sql[open..].find(')').map(|i| open + i).unwrap_or(sql.len());
+            let columns: Vec<&str> = sql[open + 1..close].split(',').map(str::trim).collect();
+            let values_open = sql[close..]
+                .find('(')
+                .map(|i| close + i)
+                .unwrap_or(sql.len());
+            let values_close = sql.rfind(')').unwrap_or(sql.len());
+            let values: Vec<&str> = sql[values_open + 1..values_close]
+                .split(',')
+                .map(str::trim)
+                .collect();
+
+            let mut changes = Map::new();
+            for (col, expr) in columns.iter().zip(values.iter()) {
+                changes.insert(col.to_string(), resolve(expr, params));
+            }
+
+            let pk_cols = self.primary_key(&table)?;
+            let mut key = Map::new();
+            for col in &pk_cols {
+                key.insert(
+                    col.clone(),
+                    changes.get(col).cloned().unwrap_or(Value::Null),
+                );
+            }
+
+            let exists = !pk_cols.is_empty() && {
+                let predicate = pk_cols
+                    .iter()
+                    .map(|c| format!("{} = ?", c))
+                    .collect::<Vec<_>>()
+                    .join(" AND ");
+                let key_values: Vec<SqlValue> = pk_cols
+                    .iter()
+                    .map(|c| json_to_sql(changes.get(c).unwrap_or(&Value::Null)))
+                    .collect();
+                self.conn
+                    .query_row(
+                        &format!("SELECT 1 FROM {} WHERE {}", table, predicate),
+                        rusqlite::params_from_iter(key_values),
+                        |_| Ok(()),
+                    )
+                    .is_ok()
+            };
+
+            let op = if exists { "update" } else { "insert" };
+            return Ok((
+                PatchOp {
+                    op,
+                    table,
+                    key,
+                    changes,
+                },
+                1,
+            ));
+        }
+
+        let (op, table, set_clause, where_clause) = if upper.starts_with("UPDATE") {
+            let set = upper.find(" SET ").unwrap_or(sql.len());
+            let wh = upper.find(" WHERE ").unwrap_or(sql.len());
+            (
+                "update",
+                sql[7..set].trim().to_string(),
+                sql[(set + 5).min(wh)..wh].to_string(),
+                sql.get(wh + 7..).unwrap_or("").to_string(),
+            )
+        } else {
+            let from = upper.find(" FROM ").map(|i| i + 6).unwrap_or(0);
+            let wh = upper.find(" WHERE ").unwrap_or(sql.len());
+            (
+                "delete",
+                sql[from..wh].trim().to_string(),
+                String::new(),
+                sql.get(wh + 7..).unwrap_or("").to_string(),
+            )
+        };
+
+        let mut changes = Map::new();
+        for (col, expr) in split_assignments(&set_clause, ",") {
+            changes.insert(col, resolve(&expr, params));
+        }
+        let mut key = Map::new();
+        for (col, expr) in split_assignments(&where_clause, " AND ") {
+            key.insert(col, resolve(&expr, params));
+        }
+
+        let affected = if where_clause.is_empty() {
+            self.conn
+                .query_row(&format!("SELECT count(*) FROM {}", table), [], |r| {
+                    r.get::<_, i64>(0)
+                })?
+        } else {
+            self.conn.query_row(
+                &format!("SELECT count(*) FROM {} WHERE {}", table, where_clause),
+                params,
+                |r| r.get::<_, i64>(0),
+            )?
+        };
+
+        Ok((
+            PatchOp {
+                op,
+                table,
+                key,
+                changes,
+            },
+            affected as usize,
+        ))
+    }
+
+    fn primary_key(&self, table: &str) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("PRAGMA table_info({})", table))?;
+        let mut cols: Vec<(i64, String)> = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(5)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|(pk, _)| *pk > 0)
+            .collect();
+        cols.sort();
+        Ok(cols.into_iter().map(|(_, name)| name).collect())
+    }
+}
+
+impl Deref for DryRunConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+fn split_assignments(clause: &str, sep: &str) -> Vec<(String, String)> {
+    if clause.trim().is_empty() {
+        return Vec::new();
+    }
+    let upper = clause.to_uppercase();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while let Some(i) = upper[start..].find(sep) {
+        parts.push(&clause[start..start + i]);
+        start += i + sep.len();
+    }
+    parts.push(&clause[start..]);
+
+    parts
+        .into_iter()
+        .filter_map(|p| {
+            let (col, expr) = p.split_once('=')?;
+            Some((col.trim().to_string(), expr.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Resolves `?N` placeholders against the bound params; anything else is treated as a literal.
+fn resolve(expr: &str, params: &[&dyn ToSql]) -> Value {
+    if let Some(idx) = expr.strip_prefix('?').and_then(|n| n.parse::<usize>().ok()) {
+        return params
+            .get(idx.saturating_sub(1))
+            .and_then(|p| p.to_sql().ok())
+            .map(|out| match out {
+                ToSqlOutput::Borrowed(v) => value_ref_to_json(v),
+                ToSqlOutput::Owned(v) => value_ref_to_json((&v).into()),
+                _ => Value::Null,
+            })
+            .unwrap_or(Value::Null);
+    }
+    if let Some(s) = expr.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        return Value::String(s.to_string());
+    }
+    if expr.eq_ignore_ascii_case("NULL") {
+        return Value::Null;
+    }
+    expr.parse::<i64>()
+        .map(Value::from)
+        .or_else(|_| expr.parse::<f64>().map(Value::from))
+        .unwrap_or_else(|_| Value::String(expr.to_string()))
+}
+
+fn value_ref_to_json(v: ValueRef<'_>) -> Value {
+    match v {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).to_string()),
+        ValueRef::Blob(_) => Value::String("<BLOB>".to_string()),
+    }
+}
+
+/// The SQLite value for a JSON column value. Objects and arrays are stored as their JSON text.
+pub fn json_to_sql(v: &Value) -> SqlValue {
+    match v {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => n
+            .as_i64()
+            .map(SqlValue::Integer)
+            .unwrap_or_else(|| SqlValue::Real(n.as_f64().unwrap_or(0.0))),
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    #[test]
+    fn audit_log_has_one_line_per_applied_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let conn = crate::db::init_db(":memory:").unwrap();
+        {
+            let mut store = DryRunConnection::new(&conn, false);
+            store.set_audit_log(File::create(&path).unwrap());
+            let upsert = "INSERT OR REPLACE INTO app_state (key, value) VALUES (?1, ?2)";
+            store.execute(upsert, params!["k", "a"]).unwrap();
+            store.execute(upsert, params!["k", "b"]).unwrap();
+            store
+                .execute("DELETE FROM app_state WHERE key = ?1", params!["k"])
+                .unwrap();
+            assert!(store.take_patch().is_empty());
+        }
+
+        let lines: Vec<Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let summary: Vec<(&str, &str, &Value, i64)> = lines
+            .iter()
+            .map(|l| {
+                (
+                    l["op"].as_str().unwrap(),
+                    l["table"].as_str().unwrap(),
+                    &l["key"],
+                    l["affected"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        let key = serde_json::json!({ "key": "k" });
+        assert_eq!(
+            summary,
+            [
+                ("insert", "app_state", &key, 1),
+                ("update", "app_state", &key, 1),
+                ("delete", "app_state", &key, 1)
+            ]
+        );
+        assert!(lines.iter().all(|l| l["ts"].is_string()));
+        assert_eq!(
+            conn.query_row("SELECT count(*) FROM app_state WHERE key = 'k'", [], |r| {
+                r.get::<_, i64>(0)
+            })
+            .unwrap(),
+            0
+        );
+    }
+}
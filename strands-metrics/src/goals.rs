@@ -0,0 +1,454 @@
+use crate::metrics::Metric;
+use anyhow::{bail, Result};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+pub const DIRECTIONS: &[&str] = &["lower_is_better", "higher_is_better"];
+pub const UNITS: &[&str] = &["hours", "minutes", "days", "percent", "count"];
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum GoalsFormat {
+    Yaml,
+    Toml,
+}
+
+impl GoalsFormat {
+    /// `.toml` is TOML; anything else (`.yaml`, `.yml`, no extension) is treated as YAML.
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => GoalsFormat::Toml,
+            _ => GoalsFormat::Yaml,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GoalsConfig {
+    pub goals: Vec<GoalEntry>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct GoalEntry {
+    /// Column name in `daily_metrics`; must be a `Metric` in the registry.
+    pub metric: String,
+    pub goal: f64,
+    pub direction: String,
+    /// How far past the goal (as a multiplier) before the metric turns yellow.
+    #[serde(default = "default_warning_ratio")]
+    pub warning_ratio: f64,
+    /// How far past the goal (as a multiplier) before the metric turns red.
+    #[serde(default = "default_critical_ratio")]
+    pub critical_ratio: f64,
+    /// One of `UNITS`. `percent` values are stored as fractions (0.42 is 42%).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+fn default_warning_ratio() -> f64 {
+    1.25
+}
+
+fn default_critical_ratio() -> f64 {
+    1.5
+}
+
+impl GoalsConfig {
+    /// Parses and validates a goals file. `format` overrides detection from the file extension.
+    pub fn load<P: AsRef<Path>>(path: P, format: Option<GoalsFormat>) -> Result<Self> {
+        let config = Self::parse(path, format)?;
+        let problems = config.problems();
+        if !problems.is_empty() {
+            bail!("Invalid goals:\n  {}", problems.join("\n  "));
+        }
+        Ok(config)
+    }
+
+    /// Parses a goals file without validating the entries.
+    pub fn parse<P: AsRef<Path>>(path: P, format: Option<GoalsFormat>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)?;
+        let config = match format.unwrap_or_else(|| GoalsFormat::from_path(path)) {
+            GoalsFormat::Yaml => {
+                // serde_yaml resolves anchors and aliases but leaves `<<` merge keys to us.
+                let mut value: serde_yaml::Value = serde_yaml::from_str(&raw)?;
+                value.apply_merge()?;
+                serde_yaml::from_value(value)?
+            }
+            GoalsFormat::Toml => toml::from_str(&raw)?,
+        };
+        Ok(config)
+    }
+
+    /// Every validation problem across all goals, one message each.
+    pub fn problems(&self) -> Vec<String> {
+        self.goals.iter().flat_map(GoalEntry::problems).collect()
+    }
+}
+
+impl GoalEntry {
+    /// The goal's own unit, else the registry's unit for its metric.
+    pub fn display_unit(&self) -> Option<&str> {
+        self.unit
+            .as_deref()
+            .or_else(|| Metric::from_column(&self.metric).and_then(|m| m.info().unit))
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if Metric::from_column(&self.metric).is_none() {
+            problems.push(format!(
+                "Goal '{}': unknown metric (see `describe-metrics`)",
+                self.metric
+            ));
+        }
+        if !DIRECTIONS.contains(&self.direction.as_str()) {
+            problems.push(format!(
+                "Goal '{}': direction must be one of {:?}, got '{}'",
+                self.metric, DIRECTIONS, self.direction
+            ));
+        }
+        if let Some(unit) = &self.unit {
+            if !UNITS.contains(&unit.as_str()) {
+                problems.push(format!(
+                    "Goal '{}': unit must be one of {:?}, got '{}'",
+                    self.metric, UNITS, unit
+                ));
+            }
+        }
+        if self.warning_ratio < 1.0 {
+            problems.push(format!(
+                "Goal '{}': warning_ratio must be >= 1.0, got {}",
+                self.metric, self.warning_ratio
+            ));
+        }
+        if self.critical_ratio < self.warning_ratio {
+            problems.push(format!(
+                "Goal '{}': critical_ratio ({}) must be >= warning_ratio ({})",
+                self.metric, self.critical_ratio, self.warning_ratio
+            ));
+        }
+        problems
+    }
+}
+
+/// Replaces the stored goals with the contents of the config file.
+pub fn load_goals(conn: &Connection, config: &GoalsConfig) -> Result<usize> {
+    conn.execute("DELETE FROM goals", [])?;
+    for goal in &config.goals {
+        conn.execute(
+            "INSERT INTO goals (metric, goal, direction, warning_ratio, critical_ratio, unit)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                goal.metric,
+                goal.goal,
+                goal.direction,
+                goal.warning_ratio,
+                goal.critical_ratio,
+                goal.unit
+            ],
+        )?;
+    }
+    Ok(config.goals.len())
+}
+
+/// Describes what `load_goals` would change, one line per goal:
+/// `+` added, `~` changed (with old → new values), `=` unchanged, `-` removed.
+pub fn diff_goals(conn: &Connection, config: &GoalsConfig) -> Result<Vec<String>> {
+    let current = list_goals(conn)?;
+    let mut lines = Vec::new();
+
+    for new in &config.goals {
+        match current.iter().find(|g| g.metric == new.metric) {
+            None => lines.push(format!(
+                "+ {}: goal={} direction={} warning_ratio={} critical_ratio={}",
+                new.metric, new.goal, new.direction, new.warning_ratio, new.critical_ratio
+            )),
+            Some(old) if old == new => lines.push(format!("= {}", new.metric)),
+            Some(old) => {
+                let mut changes = Vec::new();
+                if old.goal != new.goal {
+                    changes.push(format!("goal {} → {}", old.goal, new.goal));
+                }
+                if old.direction != new.direction {
+                    changes.push(format!("direction {} → {}", old.direction, new.direction));
+                }
+                if old.warning_ratio != new.warning_ratio {
+                    changes.push(format!(
+                        "warning_ratio {} → {}",
+                        old.warning_ratio, new.warning_ratio
+                    ));
+                }
+                if old.critical_ratio != new.critical_ratio {
+                    changes.push(format!(
+                        "critical_ratio {} → {}",
+                        old.critical_ratio, new.critical_ratio
+                    ));
+                }
+                if old.unit != new.unit {
+                    changes.push(format!(
+                        "unit {} → {}",
+                        old.unit.as_deref().unwrap_or("-"),
+                        new.unit.as_deref().unwrap_or("-")
+                    ));
+                }
+                lines.push(format!("~ {}: {}", new.metric, changes.join(", ")));
+            }
+        }
+    }
+
+    for old in &current {
+        if !config.goals.iter().any(|g| g.metric == old.metric) {
+            lines.push(format!("- {}", old.metric));
+        }
+    }
+
+    Ok(lines)
+}
+
+pub fn list_goals(conn: &Connection) -> Result<Vec<GoalEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT metric, goal, direction, warning_ratio, critical_ratio, unit FROM goals ORDER BY metric",
+    )?;
+    let goals = stmt
+        .query_map([], |row| {
+            Ok(GoalEntry {
+                metric: row.get(0)?,
+                goal: row.get(1)?,
+                direction: row.get(2)?,
+                warning_ratio: row.get(3)?,
+                critical_ratio: row.get(4)?,
+                unit: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(goals)
+}
+
+/// Reads the `goal_thresholds` view into `{ metric: { goal, warning, critical, direction } }`,
+/// the shape Grafana's "Config from query results" expects.
+pub fn export_thresholds(conn: &Connection) -> Result<Value> {
+    let mut stmt = conn.prepare(
+        "SELECT metric, goal, warning, critical, direction, unit FROM goal_thresholds ORDER BY metric",
+    )?;
+    let mut rows = stmt.query([])?;
+
+    let mut out = Map::new();
+    while let Some(row) = rows.next()? {
+        let metric: String = row.get(0)?;
+        let goal: f64 = row.get(1)?;
+        let warning: f64 = row.get(2)?;
+        let critical: f64 = row.get(3)?;
+        let direction: String = row.get(4)?;
+        let unit: Option<String> = row.get(5)?;
+        out.insert(
+            metric,
+            json!({
+                "goal": goal,
+                "warning": warning,
+                "critical": critical,
+                "direction": direction,
+                "unit": unit,
+            }),
+        );
+    }
+    Ok(Value::Object(out))
+}
+
+/// Formats a metric value for display according to its goal unit, e.g. `18.0h` or `42.0%`.
+pub fn format_value(value: f64, unit: Option<&str>) -> String {
+    match unit {
+        Some("hours") => format!("{:.1}h", value),
+        Some("minutes") => format!("{:.1}m", value),
+        Some("days") => format!("{:.1}d", value),
+        Some("percent") => format!("{:.1}%", value * 100.0),
+        Some("count") => format!("{:.0}", value),
+        _ => format!("{:.2}", value),
+    }
+}
+
+/// One goal evaluated against the latest day of `daily_metrics`.
+pub struct GoalStatus {
+    pub goal: GoalEntry,
+    /// Average across repos on the latest date. `None` if the metric has no data.
+    pub value: Option<f64>,
+    /// `ok`, `warning`, `critical`, or `no data`.
+    pub status: &'static str,
+}
+
+/// Compares each goal's metric, averaged across repos on the most recent date, with its thresholds.
+/// Goals whose metric isn't in the registry (stored before it was validated) are `no data`.
+pub fn check_goals(conn: &Connection) -> Result<Vec<GoalStatus>> {
+    let mut out = Vec::new();
+    for goal in list_goals(conn)? {
+        let value: Option<f64> = if Metric::from_column(&goal.metric).is_some() {
+            conn.query_row(
+                &format!(
+                    "SELECT AVG({0}) FROM daily_metrics
+                     WHERE date = (SELECT max(date) FROM daily_metrics) AND {0} IS NOT NULL",
+                    goal.metric
+                ),
+                [],
+                |row| row.get(0),
+            )?
+        } else {
+            None
+        };
+
+        let status = match value {
+            None => "no data",
+            Some(v) if goal.direction == "higher_is_better" => {
+                if v < goal.goal / goal.critical_ratio {
+                    "critical"
+                } else if v < goal.goal / goal.warning_ratio {
+                    "warning"
+                } else {
+                    "ok"
+                }
+            }
+            Some(v) => {
+                if v > goal.goal * goal.critical_ratio {
+                    "critical"
+                } else if v > goal.goal * goal.warning_ratio {
+                    "warning"
+                } else {
+                    "ok"
+                }
+            }
+        };
+
+        out.push(GoalStatus {
+            goal,
+            value,
+            status,
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    #[test]
+    fn export_thresholds_keys_goals_by_metric() {
+        let conn = init_db(":memory:").unwrap();
+        conn.execute_batch(
+            "INSERT INTO goals (metric, goal, direction, warning_ratio, critical_ratio, unit) VALUES
+                ('time_to_first_response', 24, 'lower_is_better', 1.5, 2, 'hours'),
+                ('prs_merged', 10, 'higher_is_better', 2, 4, NULL);",
+        )
+        .unwrap();
+
+        assert_eq!(
+            export_thresholds(&conn).unwrap(),
+            json!({
+                "prs_merged": {
+                    "goal": 10.0, "warning": 5.0, "critical": 2.5,
+                    "direction": "higher_is_better", "unit": null,
+                },
+                "time_to_first_response": {
+                    "goal": 24.0, "warning": 36.0, "critical": 48.0,
+                    "direction": "lower_is_better", "unit": "hours",
+                },
+            })
+        );
+    }
+
+    fn goal(metric: &str, goal: f64) -> GoalEntry {
+        GoalEntry {
+            metric: metric.to_string(),
+            goal,
+            direction: "lower_is_better".to_string(),
+            warning_ratio: default_warning_ratio(),
+            critical_ratio: default_critical_ratio(),
+            unit: None,
+        }
+    }
+
+    #[test]
+    fn diff_goals_describes_changes_without_writing() {
+        let conn = init_db(":memory:").unwrap();
+        let stored = GoalsConfig {
+            goals: vec![
+                goal("ci_failures", 2.0),
+                goal("time_to_first_response", 24.0),
+            ],
+        };
+        load_goals(&conn, &stored).unwrap();
+
+        let proposed = GoalsConfig {
+            goals: vec![
+                goal("time_to_first_response", 12.0),
+                goal("open_prs_count", 30.0),
+            ],
+        };
+        assert_eq!(
+            diff_goals(&conn, &proposed).unwrap(),
+            [
+                "~ time_to_first_response: goal 24 → 12",
+                "+ open_prs_count: goal=30 direction=lower_is_better warning_ratio=1.25 critical_ratio=1.5",
+                "- ci_failures",
+            ]
+        );
+        assert_eq!(list_goals(&conn).unwrap(), stored.goals);
+    }
+
+    #[test]
+    fn format_value_follows_the_unit() {
+        assert_eq!(format_value(18.04, Some("hours")), "18.0h");
+        assert_eq!(format_value(7.26, Some("minutes")), "7.3m");
+        assert_eq!(format_value(2.0, Some("days")), "2.0d");
+        assert_eq!(format_value(0.42, Some("percent")), "42.0%");
+        assert_eq!(format_value(12.6, Some("count")), "13");
+        assert_eq!(format_value(1.0 / 3.0, None), "0.33");
+    }
+
+    #[test]
+    fn goal_unit_overrides_the_registry_unit() {
+        let mut g = goal("time_to_first_response", 24.0);
+        assert_eq!(g.display_unit(), Some("hours"));
+        g.unit = Some("days".to_string());
+        assert_eq!(g.display_unit(), Some("days"));
+        g.unit = Some("weeks".to_string());
+        assert_eq!(
+            g.problems(),
+            [format!(
+                "Goal 'time_to_first_response': unit must be one of {:?}, got 'weeks'",
+                UNITS
+            )]
+        );
+    }
+
+    #[test]
+    fn yaml_anchors_aliases_and_merge_keys_are_resolved() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/goals/anchors.yaml");
+        let config = GoalsConfig::load(&path, None).unwrap();
+
+        let latency = |metric: &str, goal: f64, critical_ratio: f64| GoalEntry {
+            metric: metric.to_string(),
+            goal,
+            direction: "lower_is_better".to_string(),
+            warning_ratio: 1.1,
+            critical_ratio,
+            unit: Some("hours".to_string()),
+        };
+        assert_eq!(
+            config.goals,
+            [
+                latency("time_to_first_response", 24.0, 1.3),
+                latency("time_to_merge_internal", 48.0, 2.0),
+                GoalEntry {
+                    metric: "prs_merged".to_string(),
+                    goal: 5.0,
+                    direction: "higher_is_better".to_string(),
+                    warning_ratio: 1.25,
+                    critical_ratio: 1.5,
+                    unit: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::metrics::Metric;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::Path;
+
+const DATASOURCE_UID: &str = "strands-metrics-sqlite";
+
+/// Metrics that get a panel on the starter dashboard.
+const KEY_METRICS: &[Metric] = &[
+    Metric::PrsOpened,
+    Metric::PrsMerged,
+    Metric::IssuesOpened,
+    Metric::IssuesClosed,
+    Metric::OpenIssuesCount,
+    Metric::OpenPrsCount,
+    Metric::TimeToFirstResponse,
+    Metric::AvgIssueResolutionTime,
+    Metric::AvgPrResolutionTime,
+    Metric::TimeToMergeInternal,
+    Metric::TimeToMergeExternal,
+    Metric::CiFailures,
+    Metric::Stars,
+    Metric::CommunityHealthScore,
+];
+
+/// Writes `datasource.json` (frser-sqlite-datasource pointed at `db_path`) and a starter
+/// `dashboard.json` with one time series panel per `KEY_METRICS` column.
+pub fn export_grafana_config(db_path: &Path, output_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(output_dir)?;
+
+    let db_path = std::fs::canonicalize(db_path).unwrap_or_else(|_| db_path.to_path_buf());
+    let datasource = json!({
+        "name": "GitHub Metrics (SQLite)",
+        "uid": DATASOURCE_UID,
+        "type": "frser-sqlite-datasource",
+        "access": "proxy",
+        "isDefault": true,
+        "jsonData": {
+            "path": db_path.to_string_lossy(),
+            "mode": "ro",
+        },
+    });
+    std::fs::write(
+        output_dir.join("datasource.json"),
+        serde_json::to_string_pretty(&datasource)?,
+    )?;
+
+    let panels: Vec<Value> = KEY_METRICS
+        .iter()
+        .enumerate()
+        .map(|(i, metric)| panel(i, metric.column()))
+        .collect();
+    let dashboard = json!({
+        "title": "Strands Metrics",
+        "uid": "strands-metrics-starter",
+        "editable": true,
+        "schemaVersion": 42,
+        "time": { "from": "now-30d", "to": "now" },
+        "templating": {
+            "list": [{
+                "name": "repo",
+                "type": "query",
+                "datasource": { "type": "frser-sqlite-datasource", "uid": DATASOURCE_UID },
+                "query": "SELECT DISTINCT repo FROM daily_metrics",
+                "definition": "SELECT DISTINCT repo FROM daily_metrics",
+                "includeAll": true,
+                "multi": true,
+                "refresh": 1,
+                "sort": 1,
+            }],
+        },
+        "panels": panels,
+    });
+    std::fs::write(
+        output_dir.join("dashboard.json"),
+        serde_json::to_string_pretty(&dashboard)?,
+    )?;
+
+    Ok(())
+}
+
+/// Time series of one column, one line per repo, laid out two panels per row.
+fn panel(index: usize, metric: &str) -> Value {
+    let query = format!(
+        "SELECT CAST(strftime('%s', date) as INTEGER) as time, repo as metric, {} as value\n\
+         FROM daily_metrics\n\
+         WHERE repo IN (${{repo:singlequote}})\n\
+         ORDER BY date ASC",
+        metric
+    );
+    json!({
+        "id": index + 1,
+        "type": "timeseries",
+        "title": metric,
+        "datasource": { "type": "frser-sqlite-datasource", "uid": DATASOURCE_UID },
+        "gridPos": { "h": 8, "w": 12, "x": (index % 2) * 12, "y": (index / 2) * 8 },
+        "targets": [{
+            "refId": "A",
+            "queryType": "table",
+            "rawQueryText": query,
+            "queryText": query,
+            "timeColumns": ["time", "ts"],
+        }],
+    })
+}
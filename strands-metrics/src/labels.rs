@@ -0,0 +1,41 @@
+use crate::store::MetricsStore;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// Replaces the stored label set for a single issue or PR.
+/// `item_type` is either "issue" or "pr".
+pub fn upsert_item_labels(
+    conn: &dyn MetricsStore,
+    repo: &str,
+    item_type: &str,
+    number: i64,
+    labels: &[String],
+) -> Result<()> {
+    let key = [
+        ("repo", json!(repo)),
+        ("item_type", json!(item_type)),
+        ("item_number", json!(number)),
+    ];
+    conn.delete_rows("item_labels", &key)?;
+
+    for label in labels {
+        let mut row = key.to_vec();
+        row.push(("label", json!(label)));
+        conn.upsert_row("item_labels", &row)?;
+    }
+
+    Ok(())
+}
+
+/// Pulls label names out of a raw GitHub issue/PR payload.
+pub fn label_names(item: &Value) -> Vec<String> {
+    item.get("labels")
+        .and_then(|l| l.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|l| l.get("name").and_then(|n| n.as_str()))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
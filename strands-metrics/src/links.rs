@@ -0,0 +1,84 @@
+use crate::store::MetricsStore;
+use anyhow::Result;
+use regex::Regex;
+use serde_json::json;
+use std::sync::LazyLock;
+
+/// GitHub's closing keywords followed by a same-repo reference, e.g. "Fixes #12".
+static CLOSING_REF: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?:close[sd]?|fix(?:e[sd])?|resolve[sd]?)\s*:?\s+#(\d+)").unwrap()
+});
+
+/// Issue numbers a PR body says it closes, in order of appearance without duplicates.
+pub fn closing_issue_refs(body: &str) -> Vec<i64> {
+    let mut refs = Vec::new();
+    for cap in CLOSING_REF.captures_iter(body) {
+        if let Ok(n) = cap[1].parse::<i64>() {
+            if !refs.contains(&n) {
+                refs.push(n);
+            }
+        }
+    }
+    refs
+}
+
+/// Replaces the stored set of issues a PR closes.
+pub fn upsert_pr_closes_issue(
+    conn: &dyn MetricsStore,
+    repo: &str,
+    pr_number: i64,
+    issues: &[i64],
+) -> Result<()> {
+    conn.delete_rows(
+        "pr_closes_issue",
+        &[("repo", json!(repo)), ("pr_number", json!(pr_number))],
+    )?;
+
+    for issue in issues {
+        conn.upsert_row(
+            "pr_closes_issue",
+            &[
+                ("repo", json!(repo)),
+                ("pr_number", json!(pr_number)),
+                ("issue_number", json!(issue)),
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closing_refs_cover_keywords_colons_and_duplicates() {
+        let body = "Closes #1, fixed #2 and RESOLVES: #3.\n\
+                    Fixes #1 again. See #4, refs #5, fix other/repo#6.";
+        assert_eq!(closing_issue_refs(body), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn closing_refs_need_a_keyword() {
+        assert!(closing_issue_refs("Related to #12, prefix #13").is_empty());
+        assert_eq!(closing_issue_refs("close #7\nresolved #8"), vec![7, 8]);
+    }
+
+    #[test]
+    fn upsert_replaces_previous_links() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let store = crate::dry_run::DryRunConnection::new(&conn, false);
+        upsert_pr_closes_issue(&store, "r", 1, &[10, 11]).unwrap();
+        upsert_pr_closes_issue(&store, "r", 1, &[11, 12]).unwrap();
+
+        let issues: Vec<i64> = store
+            .prepare("SELECT issue_number FROM pr_closes_issue ORDER BY issue_number")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(issues, vec![11, 12]);
+    }
+}
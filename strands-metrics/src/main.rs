@@ -1,16 +1,48 @@
 mod aggregates;
 mod client;
+mod config;
+mod contributors;
+mod cost;
 mod db;
+mod db_utils;
+mod downloads;
+mod dry_run;
+mod goals;
+mod grafana;
+mod labels;
+mod links;
+mod metrics;
+#[cfg(test)]
+mod mock_github;
+mod preflight;
+mod progress;
+mod redact;
+mod reextract;
+mod releases;
+mod repo_filter;
+mod report;
+mod runs;
+mod state;
+mod store;
+mod team;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use client::GitHubClient;
+use config::MetricsConfig;
 use db::init_db;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use db_utils::DbUtils;
+use dry_run::DryRunConnection;
+use http::header::USER_AGENT;
+use indicatif::MultiProgress;
 use octocrab::OctocrabBuilder;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use store::MetricsStore;
 use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::prelude::*;
 
 const ORG: &str = "strands-agents";
 
@@ -19,6 +51,16 @@ const ORG: &str = "strands-agents";
 struct Cli {
     #[clap(long, short, default_value = "metrics.db")]
     db_path: PathBuf,
+    #[clap(long, default_value = ".strands.toml")]
+    config: PathBuf,
+    /// Spinner for `sync` / `sweep`, or plain log lines (`auto` picks by whether stderr is a
+    /// terminal).
+    #[clap(long, value_enum, default_value_t)]
+    progress: progress::ProgressMode,
+    /// After `sync` or `compute` recomputes metrics, copy the database here as a read-only
+    /// snapshot for dashboards, so they never read the file being written.
+    #[clap(long, value_name = "PATH")]
+    replica_path: Option<PathBuf>,
     #[clap(subcommand)]
     command: Commands,
 }
@@ -26,94 +68,872 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Smart sync. Grabs only what is new.
-    Sync,
+    Sync {
+        /// Print the changes as a JSON patch instead of writing them.
+        #[clap(long)]
+        dry_run: bool,
+        /// Skip recomputing daily metrics. Run `compute` afterwards.
+        #[clap(long)]
+        no_metrics: bool,
+        /// Append a JSON line for every row written to this file. Verbose; for debugging.
+        #[clap(long, value_name = "PATH")]
+        audit_log: Option<PathBuf>,
+        /// Don't store titles or bodies in the `data` JSON of issues, PRs, reviews, comments,
+        /// discussions and events. Body lengths are kept for the description metrics.
+        #[clap(long)]
+        redact_bodies: bool,
+        /// Order to visit repos in, so the important ones land first if the rate limit runs out.
+        #[clap(long, value_enum, default_value_t)]
+        repo_order: repo_filter::RepoOrder,
+        /// How many days back a repo's first sync reaches, so a new database doesn't page
+        /// through every repo's entire history.
+        #[clap(long, value_name = "DAYS", default_value_t = 365)]
+        initial_window: u32,
+        /// Sync full history: no window on first syncs, and backfill repos whose first sync
+        /// was windowed.
+        #[clap(long, conflicts_with = "initial_window")]
+        full: bool,
+        #[clap(flatten)]
+        repos: repo_filter::RepoSelection,
+    },
     /// Garbage collection. Checks open items against reality and marks missing ones as deleted.
-    Sweep,
+    Sweep {
+        /// Print the changes as a JSON patch instead of writing them.
+        #[clap(long)]
+        dry_run: bool,
+        /// Append a JSON line for every row written to this file. Verbose; for debugging.
+        #[clap(long, value_name = "PATH")]
+        audit_log: Option<PathBuf>,
+        #[clap(flatten)]
+        repos: repo_filter::RepoSelection,
+    },
+    /// Recompute daily metrics from the already-synced data.
+    Compute,
+    /// Pull package download counts from PyPI and npm.
+    SyncDownloads {
+        #[clap(long, default_value = "packages.yaml")]
+        config: PathBuf,
+        /// Fetch per-version downloads for every package, not just those with
+        /// `version_breakdown: true`. PyPI versions need PEPY_API_KEY.
+        #[clap(long)]
+        version_breakdown: bool,
+        /// Exit non-zero if any package fails, not only when a whole registry does.
+        #[clap(long)]
+        strict: bool,
+        /// Print per-package outcomes as JSON instead of one line each.
+        #[clap(long)]
+        json: bool,
+    },
+    /// Replace the stored goals with the contents of a goals file.
+    LoadGoals {
+        #[clap(default_value = "goals.yaml")]
+        path: PathBuf,
+        /// File format. Detected from the extension when omitted.
+        #[clap(long, value_enum)]
+        format: Option<goals::GoalsFormat>,
+        /// Print what would change instead of writing.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Print the stored goals.
+    ListGoals,
+    /// Compare the latest daily metrics against the stored goals.
+    CheckGoals {
+        #[clap(long, value_enum, default_value_t)]
+        format: report::OutputFormat,
+    },
+    /// List every daily metric with its unit, direction, description and stored goal, if any.
+    DescribeMetrics {
+        #[clap(long, value_enum, default_value_t)]
+        format: report::OutputFormat,
+    },
+    /// Replace the members of a team. Team members count as internal contributors.
+    LoadTeam {
+        /// Comma-separated GitHub logins.
+        #[clap(long, value_delimiter = ',', required = true)]
+        members: Vec<String>,
+        #[clap(long, default_value = "core")]
+        team: String,
+        /// Print what would change instead of writing.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Replace the members of each team with its members on GitHub. The team is stored under
+    /// its slug. Needs a token with `read:org`.
+    #[clap(name = "load-team-from-github")]
+    LoadTeamFromGitHub {
+        /// GitHub Team slug. Repeat for several teams.
+        #[clap(long = "team", required = true)]
+        team_slugs: Vec<String>,
+        /// Print what would change instead of writing.
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Replace the stored contributor roles with the contents of a YAML file.
+    LoadContributors {
+        #[clap(default_value = "contributors.yaml")]
+        path: PathBuf,
+    },
+    /// PRs merged, issues closed and commits in each repo's latest release and since it.
+    ReleaseReport {
+        #[clap(long, value_enum, default_value_t)]
+        format: report::OutputFormat,
+    },
+    /// Repos with merged PRs and stars over the last 30 days, with a sparkline of each.
+    ListRepos {
+        #[clap(long, value_enum, default_value_t)]
+        format: report::OutputFormat,
+    },
+    /// API calls recorded per sync phase, to see which steps use the most quota.
+    CostReport {
+        #[clap(long, value_enum, default_value_t)]
+        format: report::OutputFormat,
+    },
+    /// Write goal thresholds as JSON for Grafana provisioning.
+    ExportThresholds {
+        #[clap(long, short, default_value = "thresholds.json")]
+        out: PathBuf,
+    },
+    /// Generate a Grafana SQLite datasource and a starter dashboard for this database.
+    ExportGrafanaConfig {
+        #[clap(long, default_value = "grafana")]
+        output_dir: PathBuf,
+    },
+    /// Validate the goals and packages files without touching the database.
+    ConfigCheck {
+        #[clap(long, default_value = "goals.yaml")]
+        goals: PathBuf,
+        #[clap(long, default_value = "packages.yaml")]
+        packages: PathBuf,
+        #[clap(long, default_value = "contributors.yaml")]
+        contributors: PathBuf,
+    },
+    /// Compare the database's tables and columns with the schema this version expects,
+    /// without migrating it.
+    CheckSchema,
+    /// Hard-delete issues and PRs that sweep marked as deleted.
+    Purge {
+        /// Don't VACUUM afterwards, even if the file is fragmented.
+        #[clap(long)]
+        no_vacuum: bool,
+    },
+    /// Shrink raw payloads and commit messages older than a cutoff, keeping metrics and counts.
+    Trim {
+        /// Age in days, by last update, after which a row's payload is trimmed.
+        #[clap(long)]
+        older_than: u32,
+        /// Don't VACUUM afterwards, even if the file is fragmented.
+        #[clap(long)]
+        no_vacuum: bool,
+    },
+    /// Rebuild derived tables (labels, closing links) from stored payloads without re-fetching.
+    /// Assignees and reactions aren't stored in tables of their own, so there's nothing to
+    /// rebuild for them; query them from the `data` JSON.
+    Reextract {
+        #[clap(value_enum, default_value_t)]
+        what: reextract::ReextractTarget,
+    },
+    /// Remove all stored data for a repo.
+    Prune {
+        repo: String,
+        /// Don't VACUUM afterwards, even if the file is fragmented.
+        #[clap(long)]
+        no_vacuum: bool,
+    },
+    /// Inspect or edit sync cursors and other `app_state` entries.
+    State {
+        #[clap(subcommand)]
+        command: StateCommand,
+    },
     /// Run raw SQL.
-    Query { sql: String },
+    Query {
+        sql: String,
+        #[clap(long, value_enum, default_value_t)]
+        format: report::OutputFormat,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateCommand {
+    /// Print the value stored under a key.
+    Get { key: String },
+    /// Store a value. `last_sync_*` keys must be RFC3339 timestamps.
+    Set { key: String, value: String },
+    /// Print every key and value.
+    List,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_max_level(LevelFilter::WARN)
-        .init();
+    // octocrab and reqwest share rustls; pick its crypto provider once for both.
+    let _ = rustls::crypto::ring::default_provider().install_default();
 
     let args = Cli::parse();
-    let mut conn = init_db(&args.db_path)?;
+
+    // Without a spinner, this crate's status lines are logged at INFO instead.
+    let own_level = if args.progress.spinner_enabled_here() {
+        LevelFilter::WARN
+    } else {
+        LevelFilter::INFO
+    };
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_ansi(std::io::stderr().is_terminal()),
+        )
+        .with(
+            Targets::new()
+                .with_default(LevelFilter::WARN)
+                .with_target("strands_metrics", own_level),
+        )
+        .init();
+
+    if let Commands::ConfigCheck {
+        goals,
+        packages,
+        contributors,
+    } = &args.command
+    {
+        let problems = config_check(&args.config, goals, packages, contributors);
+        for p in &problems {
+            eprintln!("error: {}", p);
+        }
+        if !problems.is_empty() {
+            std::process::exit(1);
+        }
+        println!("Config OK.");
+        return Ok(());
+    }
+
+    let Some(_lock) = db_utils::try_lock_db(&args.db_path)? else {
+        eprintln!(
+            "error: another strands-metrics instance is using {} (lock file {})",
+            args.db_path.display(),
+            db_utils::lock_path(&args.db_path).display()
+        );
+        std::process::exit(1);
+    };
+    if let Commands::CheckSchema = args.command {
+        let conn = rusqlite::Connection::open_with_flags(
+            &args.db_path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+        )?;
+        let problems = db::schema_drift(&conn)?;
+        for p in &problems {
+            eprintln!("error: {}", p);
+        }
+        if !problems.is_empty() {
+            eprintln!(
+                "Run any other command (e.g. `compute`) against this database to apply migrations."
+            );
+            std::process::exit(1);
+        }
+        println!("Schema OK.");
+        return Ok(());
+    }
+
+    let conn = init_db(&args.db_path)?;
+    let metrics_config = MetricsConfig::load(&args.config)?;
 
     match args.command {
-        Commands::Sync => {
-            let gh_token = std::env::var("GITHUB_TOKEN").expect("GITHUB_TOKEN must be set");
-            let octocrab = OctocrabBuilder::new().personal_token(gh_token).build()?;
+        Commands::Sync {
+            dry_run,
+            no_metrics,
+            audit_log,
+            redact_bodies,
+            repo_order,
+            initial_window,
+            full,
+            repos,
+        } => {
+            let filter = repo_filter::RepoFilter::from_selection(&repos)?;
+            let gh_token = github_token()?;
+            let octocrab = OctocrabBuilder::new()
+                .personal_token(gh_token)
+                .add_header(USER_AGENT, metrics_config.client.user_agent.clone())
+                .build()?;
 
             let m = Arc::new(MultiProgress::new());
-            let sty = ProgressStyle::with_template("{spinner:.green} {msg}")
-                .unwrap()
-                .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ ");
+            let pb = progress::spinner(args.progress, &m, "Initializing Sync...");
 
-            let pb = m.add(ProgressBar::new_spinner());
-            pb.set_style(sty.clone());
-            pb.enable_steady_tick(std::time::Duration::from_millis(120));
-            pb.set_message("Initializing Sync...");
+            let run_id = (!dry_run)
+                .then(|| runs::start_run(&conn, "sync"))
+                .transpose()?;
 
-            let mut client = GitHubClient::new(octocrab, &mut conn, pb.clone());
+            let mut store = DryRunConnection::new(&conn, dry_run);
+            if let Some(path) = &audit_log {
+                store.set_audit_log(open_audit_log(path)?);
+            }
+            let mut client = GitHubClient::new(octocrab, &mut store, pb.clone());
+            client.set_repo_filter(filter);
+            client.set_user_agent(&metrics_config.client.user_agent);
+            client.set_redact_bodies(redact_bodies);
+            client.set_repo_order(repo_order);
+            client.set_initial_window((!full).then_some(initial_window));
 
-            client.sync_org(ORG).await?;
+            let result = client.sync_org(ORG).await;
 
-            pb.set_message("Calculating metrics...");
-            aggregates::compute_metrics(&conn)?;
+            if dry_run {
+                result?;
+                let patch = client.take_patch();
+                pb.finish_and_clear();
+                println!("{}", serde_json::to_string_pretty(&patch)?);
+                return Ok(());
+            }
 
-            pb.finish_with_message("Done!");
+            let repos_synced = client.repos_synced();
+            if let Some(run_id) = &run_id {
+                runs::finish_run(&conn, run_id, repos_synced, result.is_ok())?;
+            }
+            result?;
+
+            refresh_metrics(
+                &conn,
+                &metrics_config,
+                no_metrics,
+                args.replica_path.as_deref(),
+                &pb,
+            )?;
+
+            progress::finish(&pb, "Done!".to_string());
+        }
+        Commands::Compute => {
+            aggregates::compute_metrics(&conn, &metrics_config)?;
+            if let Some(replica) = &args.replica_path {
+                conn.write_replica(replica)?;
+            }
+            println!("Metrics computed.");
         }
-        Commands::Sweep => {
-            let gh_token = std::env::var("GITHUB_TOKEN").expect("GITHUB_TOKEN must be set");
-            let octocrab = OctocrabBuilder::new().personal_token(gh_token).build()?;
+        Commands::Sweep {
+            dry_run,
+            audit_log,
+            repos,
+        } => {
+            let filter = repo_filter::RepoFilter::from_selection(&repos)?;
+            let gh_token = github_token()?;
+            let octocrab = OctocrabBuilder::new()
+                .personal_token(gh_token)
+                .add_header(USER_AGENT, metrics_config.client.user_agent.clone())
+                .build()?;
 
             let m = Arc::new(MultiProgress::new());
-            let sty = ProgressStyle::with_template("{spinner:.green} {msg}")
-                .unwrap()
-                .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ ");
-
-            let pb = m.add(ProgressBar::new_spinner());
-            pb.set_style(sty);
-            pb.enable_steady_tick(std::time::Duration::from_millis(120));
-            pb.set_message("Starting Sweep...");
-
-            let mut client = GitHubClient::new(octocrab, &mut conn, pb.clone());
-            client.sweep_org(ORG).await?;
-
-            pb.finish_with_message("Sweep complete.");
-        }
-        Commands::Query { sql } => {
-            let mut stmt = conn.prepare(&sql)?;
-            let column_count = stmt.column_count();
-            let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
-
-            println!("{}", names.join(" | "));
-            println!("{}", "-".repeat(names.len() * 15));
-
-            let mut rows = stmt.query([])?;
-            while let Some(row) = rows.next()? {
-                let mut row_values = Vec::new();
-                for i in 0..column_count {
-                    let val = row.get_ref(i)?;
-                    let text = match val {
-                        rusqlite::types::ValueRef::Null => "NULL".to_string(),
-                        rusqlite::types::ValueRef::Integer(i) => i.to_string(),
-                        rusqlite::types::ValueRef::Real(f) => f.to_string(),
-                        rusqlite::types::ValueRef::Text(t) => {
-                            String::from_utf8_lossy(t).to_string()
+            let pb = progress::spinner(args.progress, &m, "Starting Sweep...");
+
+            let run_id = (!dry_run)
+                .then(|| runs::start_run(&conn, "sweep"))
+                .transpose()?;
+
+            let mut store = DryRunConnection::new(&conn, dry_run);
+            if let Some(path) = &audit_log {
+                store.set_audit_log(open_audit_log(path)?);
+            }
+            let mut client = GitHubClient::new(octocrab, &mut store, pb.clone());
+            client.set_repo_filter(filter);
+            client.set_user_agent(&metrics_config.client.user_agent);
+            let result = client.sweep_org(ORG).await;
+            let api_requests = client.api_requests();
+
+            if dry_run {
+                result?;
+                let patch = client.take_patch();
+                pb.finish_and_clear();
+                println!("{}", serde_json::to_string_pretty(&patch)?);
+                return Ok(());
+            }
+
+            let repos_synced = client.repos_synced();
+            if let Some(run_id) = &run_id {
+                runs::finish_run(&conn, run_id, repos_synced, result.is_ok())?;
+            }
+            result?;
+
+            progress::finish(
+                &pb,
+                format!("Sweep complete. {} API requests.", api_requests),
+            );
+        }
+        Commands::SyncDownloads {
+            config,
+            version_breakdown,
+            strict,
+            json,
+        } => {
+            let config = downloads::PackagesConfig::load(&config)?;
+            let http = reqwest::Client::builder()
+                .user_agent(&metrics_config.client.user_agent)
+                .build()?;
+
+            downloads::store_repo_mappings(&conn, &config)?;
+            let mut summary = downloads::SyncSummary::default();
+
+            for pkg in &config.packages {
+                for registry in pkg.registries() {
+                    let result = downloads::sync_package(&http, &conn, pkg, registry).await;
+                    let outcome = summary.record(&pkg.name, registry, "downloads", result);
+                    if !json {
+                        match (&outcome.rows, &outcome.error) {
+                            (Some(days), _) => {
+                                println!("{} ({}): {} days", pkg.name, registry, days)
+                            }
+                            (_, Some(e)) => eprintln!("{} ({}): failed: {}", pkg.name, registry, e),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            downloads::compute_download_star_ratio(&conn)?;
+
+            for registry in downloads::KNOWN_REGISTRIES {
+                for pkg in config
+                    .packages_for_registry(registry)
+                    .filter(|p| version_breakdown || p.version_breakdown)
+                {
+                    let result = match *registry {
+                        "pypi" => match std::env::var("PEPY_API_KEY") {
+                            Ok(api_key) => {
+                                downloads::sync_pypi_versions(&http, &conn, &pkg.name, &api_key)
+                                    .await
+                            }
+                            Err(_) => Err(anyhow::anyhow!("PEPY_API_KEY must be set")),
+                        },
+                        "npm" => {
+                            downloads::sync_npm_version_downloads(&http, &conn, &pkg.name).await
                         }
-                        rusqlite::types::ValueRef::Blob(_) => "<BLOB>".to_string(),
+                        other => Err(anyhow::anyhow!("Unknown registry '{}'", other)),
                     };
-                    row_values.push(text);
+                    let outcome = summary.record(&pkg.name, registry, "versions", result);
+                    if !json {
+                        match (&outcome.rows, &outcome.error) {
+                            (Some(rows), _) => {
+                                println!("{} ({}) versions: {} rows", pkg.name, registry, rows)
+                            }
+                            (_, Some(e)) => {
+                                eprintln!("{} ({}) versions: failed: {}", pkg.name, registry, e)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            downloads::compute_version_adoption(&conn)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&summary)?);
+            } else {
+                println!(
+                    "{} succeeded, {} failed",
+                    summary.succeeded(),
+                    summary.failed()
+                );
+            }
+            for registry in summary.failed_registries() {
+                eprintln!("error: every {} fetch failed", registry);
+            }
+            if summary.is_failure(strict) {
+                std::process::exit(1);
+            }
+        }
+        Commands::LoadGoals {
+            path,
+            format,
+            dry_run,
+        } => {
+            let config = goals::GoalsConfig::load(&path, format)?;
+            if dry_run {
+                for line in goals::diff_goals(&conn, &config)? {
+                    println!("{}", line);
+                }
+                return Ok(());
+            }
+            let count = goals::load_goals(&conn, &config)?;
+            println!("Loaded {} goals from {}", count, path.display());
+        }
+        Commands::ListGoals => {
+            println!("metric | goal | direction | warning_ratio | critical_ratio | unit");
+            for g in goals::list_goals(&conn)? {
+                println!(
+                    "{} | {} | {} | {} | {} | {}",
+                    g.metric,
+                    g.goal,
+                    g.direction,
+                    g.warning_ratio,
+                    g.critical_ratio,
+                    g.unit.as_deref().unwrap_or("")
+                );
+            }
+        }
+        Commands::CheckGoals { format } => {
+            let headers: Vec<String> = ["metric", "value", "goal", "status"]
+                .map(String::from)
+                .to_vec();
+            let rows: Vec<Vec<Option<String>>> = goals::check_goals(&conn)?
+                .into_iter()
+                .map(|c| {
+                    let unit = c.goal.display_unit();
+                    vec![
+                        Some(c.goal.metric.clone()),
+                        c.value.map(|v| goals::format_value(v, unit)),
+                        Some(goals::format_value(c.goal.goal, unit)),
+                        Some(c.status.to_string()),
+                    ]
+                })
+                .collect();
+
+            report::print_table(format, &headers, &rows);
+        }
+        Commands::DescribeMetrics { format } => {
+            let goals = goals::list_goals(&conn)?;
+            let headers: Vec<String> = ["metric", "unit", "direction", "goal", "description"]
+                .map(String::from)
+                .to_vec();
+            let rows: Vec<Vec<Option<String>>> = metrics::Metric::ALL
+                .iter()
+                .map(|m| {
+                    let info = m.info();
+                    let goal = goals.iter().find(|g| g.metric == info.column);
+                    vec![
+                        Some(info.column.to_string()),
+                        info.unit.map(String::from),
+                        info.direction.map(String::from),
+                        goal.map(|g| goals::format_value(g.goal, g.display_unit())),
+                        Some(info.description.to_string()),
+                    ]
+                })
+                .collect();
+
+            report::print_table(format, &headers, &rows);
+        }
+        Commands::LoadTeam {
+            members,
+            team,
+            dry_run,
+        } => {
+            if dry_run {
+                for line in team::diff_team(&conn, &team, &members)? {
+                    println!("{}", line);
+                }
+                return Ok(());
+            }
+            let count = team::load_team(&conn, &team, &members)?;
+            println!("Loaded {} members into team {}", count, team);
+        }
+        Commands::LoadTeamFromGitHub {
+            team_slugs,
+            dry_run,
+        } => {
+            let gh_token = github_token()?;
+            let octocrab = OctocrabBuilder::new()
+                .personal_token(gh_token)
+                .add_header(USER_AGENT, metrics_config.client.user_agent.clone())
+                .build()?;
+            for slug in &team_slugs {
+                let members = team::fetch_team_members(&octocrab, ORG, slug).await?;
+                if dry_run {
+                    println!("{}:", slug);
+                    for line in team::diff_team(&conn, slug, &members)? {
+                        println!("  {}", line);
+                    }
+                    continue;
+                }
+                let count = team::load_team(&conn, slug, &members)?;
+                println!("Loaded {} members into team {}", count, slug);
+            }
+        }
+        Commands::LoadContributors { path } => {
+            let config = contributors::ContributorsConfig::load(&path)?;
+            let count = contributors::load_contributors(&conn, &config)?;
+            println!("Loaded {} contributor roles", count);
+        }
+        Commands::ListRepos { format } => {
+            let headers: Vec<String> = [
+                "repo",
+                "prs_merged_30d",
+                "prs_merged_trend",
+                "stars",
+                "stars_trend",
+            ]
+            .map(String::from)
+            .to_vec();
+            let rows: Vec<Vec<Option<String>>> = report::repo_trends(&conn)?
+                .into_iter()
+                .map(|t| {
+                    vec![
+                        Some(t.repo.clone()),
+                        Some(t.prs_merged_total().to_string()),
+                        Some(report::sparkline(&t.prs_merged)),
+                        Some(t.current_stars().to_string()),
+                        Some(report::sparkline(&t.stars)),
+                    ]
+                })
+                .collect();
+
+            report::print_table(format, &headers, &rows);
+        }
+        Commands::ReleaseReport { format } => {
+            let headers: Vec<String> = [
+                "repo",
+                "release",
+                "from",
+                "to",
+                "prs_merged",
+                "issues_closed",
+                "commits",
+            ]
+            .map(String::from)
+            .to_vec();
+            let rows: Vec<Vec<Option<String>>> = releases::release_windows(&conn)?
+                .into_iter()
+                .map(|w| {
+                    vec![
+                        Some(w.repo),
+                        Some(w.release.unwrap_or_else(|| "unreleased".to_string())),
+                        w.from,
+                        w.to,
+                        Some(w.prs_merged.to_string()),
+                        Some(w.issues_closed.to_string()),
+                        Some(w.commits.to_string()),
+                    ]
+                })
+                .collect();
+
+            report::print_table(format, &headers, &rows);
+        }
+        Commands::CostReport { format } => {
+            let headers: Vec<String> = ["phase", "calls", "pct_of_total", "avg_per_sync"]
+                .map(String::from)
+                .to_vec();
+            let rows: Vec<Vec<Option<String>>> = cost::phase_costs(&conn)?
+                .into_iter()
+                .map(|c| {
+                    vec![
+                        Some(c.phase.to_string()),
+                        Some(c.calls.to_string()),
+                        Some(format!("{:.1}", c.pct_of_total)),
+                        Some(format!("{:.1}", c.avg_per_sync)),
+                    ]
+                })
+                .collect();
+
+            report::print_table(format, &headers, &rows);
+        }
+        Commands::ExportThresholds { out } => {
+            let thresholds = goals::export_thresholds(&conn)?;
+            std::fs::write(&out, serde_json::to_string_pretty(&thresholds)?)?;
+            println!("Wrote thresholds to {}", out.display());
+        }
+        Commands::Purge { no_vacuum } => {
+            let removed = db_utils::purge_deleted(&conn)?;
+            println!("Purged {} deleted items", removed);
+            if !no_vacuum && conn.vacuum_if_fragmented(db_utils::VACUUM_THRESHOLD)? {
+                println!("Vacuumed database");
+            }
+        }
+        Commands::Trim {
+            older_than,
+            no_vacuum,
+        } => {
+            let cutoff = (chrono::Utc::now() - chrono::Duration::days(older_than as i64))
+                .format("%Y-%m-%d")
+                .to_string();
+            let trimmed = db_utils::trim_raw(&conn, &cutoff)?;
+            println!("Trimmed {} rows older than {}", trimmed, cutoff);
+            if !no_vacuum && conn.vacuum_if_fragmented(db_utils::VACUUM_THRESHOLD)? {
+                println!("Vacuumed database");
+            }
+        }
+        Commands::Reextract { what } => {
+            // Dropping the transaction on error rolls back, so a failed run leaves no item
+            // half rebuilt.
+            let tx = conn.unchecked_transaction()?;
+            let stats = reextract::reextract(&DryRunConnection::live(&tx), what)?;
+            tx.commit()?;
+            println!(
+                "Re-extracted {} items ({} trimmed or unreadable skipped, {} redacted PRs kept their links)",
+                stats.rebuilt, stats.skipped, stats.links_skipped
+            );
+        }
+        Commands::Prune { repo, no_vacuum } => {
+            let removed = db_utils::prune_repo(&conn, ORG, &repo)?;
+            println!("Removed {} rows for {}", removed, repo);
+            if !no_vacuum && conn.vacuum_if_fragmented(db_utils::VACUUM_THRESHOLD)? {
+                println!("Vacuumed database");
+            }
+        }
+        Commands::State { command } => match command {
+            StateCommand::Get { key } => match state::get(&conn, &key)? {
+                Some(value) => println!("{}", value),
+                None => {
+                    eprintln!("No value for {}", key);
+                    std::process::exit(1);
+                }
+            },
+            StateCommand::Set { key, value } => {
+                state::set(&conn, &key, &value)?;
+                println!("{} = {}", key, value);
+            }
+            StateCommand::List => {
+                for (key, value) in state::list(&conn)? {
+                    println!("{} | {}", key, value);
                 }
-                println!("{}", row_values.join(" | "));
             }
+        },
+        Commands::ExportGrafanaConfig { output_dir } => {
+            grafana::export_grafana_config(&args.db_path, &output_dir)?;
+            println!("Wrote Grafana config to {}", output_dir.display());
+        }
+        Commands::ConfigCheck { .. } => unreachable!("handled before opening the database"),
+        Commands::CheckSchema => unreachable!("handled before migrating the database"),
+        Commands::Query { sql, format } => {
+            let (names, table) = DryRunConnection::live(&conn).query_metrics(&sql)?;
+            report::print_table(format, &names, &table);
         }
     }
 
     Ok(())
 }
+
+/// The tail of `sync`: recompute metrics and refresh the replica, unless `--no-metrics` left
+/// both to a later `compute`.
+fn refresh_metrics(
+    conn: &rusqlite::Connection,
+    config: &MetricsConfig,
+    no_metrics: bool,
+    replica_path: Option<&Path>,
+    pb: &indicatif::ProgressBar,
+) -> Result<()> {
+    if no_metrics {
+        return Ok(());
+    }
+    progress::set_status(pb, "Calculating metrics...".to_string());
+    aggregates::compute_metrics(conn, config)?;
+    if let Some(replica) = replica_path {
+        progress::set_status(pb, "Writing replica...".to_string());
+        conn.write_replica(replica)?;
+    }
+    Ok(())
+}
+
+/// Reads the GitHub token from the environment, failing cleanly when it is missing or blank.
+fn github_token() -> Result<String> {
+    match std::env::var("GITHUB_TOKEN") {
+        Ok(token) if !token.trim().is_empty() => Ok(token),
+        Ok(_) => anyhow::bail!("GITHUB_TOKEN is set but empty"),
+        Err(_) => anyhow::bail!("GITHUB_TOKEN is not set"),
+    }
+}
+
+fn open_audit_log(path: &Path) -> Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open audit log {}", path.display()))
+}
+
+/// Collects every problem in the config files instead of stopping at the first one.
+fn config_check(
+    config: &Path,
+    goals: &Path,
+    packages: &Path,
+    contributors_path: &Path,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if let Err(e) = MetricsConfig::load(config) {
+        problems.push(format!("{}: {}", config.display(), e));
+    }
+
+    match goals::GoalsConfig::parse(goals, None) {
+        Ok(cfg) => problems.extend(
+            cfg.problems()
+                .into_iter()
+                .map(|p| format!("{}: {}", goals.display(), p)),
+        ),
+        Err(e) => problems.push(format!("{}: {}", goals.display(), e)),
+    }
+
+    match downloads::PackagesConfig::load(packages) {
+        Ok(cfg) => problems.extend(
+            cfg.problems()
+                .into_iter()
+                .map(|p| format!("{}: {}", packages.display(), p)),
+        ),
+        Err(e) => problems.push(format!("{}: {}", packages.display(), e)),
+    }
+
+    match contributors::ContributorsConfig::parse(contributors_path) {
+        Ok(cfg) => problems.extend(
+            cfg.problems()
+                .into_iter()
+                .map(|p| format!("{}: {}", contributors_path.display(), p)),
+        ),
+        Err(e) => problems.push(format!("{}: {}", contributors_path.display(), e)),
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indicatif::ProgressBar;
+
+    #[test]
+    fn sync_parses_no_metrics() {
+        let cli = Cli::try_parse_from(["strands-metrics", "sync", "--no-metrics"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Sync {
+                no_metrics: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn no_metrics_skips_compute_and_replica() {
+        let dir = tempfile::tempdir().unwrap();
+        let replica = dir.path().join("replica.db");
+        let conn = init_db(":memory:").unwrap();
+        conn.execute(
+            "INSERT INTO issues (id, repo, number, state, author, created_at, updated_at, data)
+             VALUES (1, 'r', 1, 'open', 'alice', '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z', '{}')",
+            [],
+        )
+        .unwrap();
+
+        let config = MetricsConfig::default();
+        refresh_metrics(&conn, &config, true, Some(&replica), &ProgressBar::hidden()).unwrap();
+
+        let rows: i64 = conn
+            .query_row("SELECT count(*) FROM daily_metrics", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 0);
+        assert!(!replica.exists());
+    }
+
+    #[test]
+    fn sync_window_defaults_to_a_year_and_conflicts_with_full() {
+        let cli = Cli::try_parse_from(["strands-metrics", "sync"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Sync {
+                initial_window: 365,
+                full: false,
+                ..
+            }
+        ));
+        let cli = Cli::try_parse_from(["strands-metrics", "sync", "--full"]).unwrap();
+        assert!(matches!(cli.command, Commands::Sync { full: true, .. }));
+        assert!(Cli::try_parse_from([
+            "strands-metrics",
+            "sync",
+            "--full",
+            "--initial-window",
+            "30"
+        ])
+        .is_err());
+    }
+}
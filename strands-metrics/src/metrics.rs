@@ -0,0 +1,171 @@
+/// What the registry knows about one `daily_metrics` column.
+pub struct MetricInfo {
+    pub column: &'static str,
+    /// One of `goals::UNITS`, or `None` for unitless scores and ratios of ratios.
+    pub unit: Option<&'static str>,
+    /// Default goal direction; `None` for volume counts that are neither good nor bad.
+    pub direction: Option<&'static str>,
+    pub description: &'static str,
+}
+
+const HOURS: Option<&str> = Some("hours");
+const MINUTES: Option<&str> = Some("minutes");
+const PERCENT: Option<&str> = Some("percent");
+const COUNT: Option<&str> = Some("count");
+const LOWER: Option<&str> = Some("lower_is_better");
+const HIGHER: Option<&str> = Some("higher_is_better");
+
+macro_rules! metrics {
+    ($($variant:ident => $column:literal, $unit:expr, $direction:expr, $description:literal;)*) => {
+        /// Every per-repo, per-day metric `compute_metrics` writes to `daily_metrics`.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Metric {
+            $($variant),*
+        }
+
+        impl Metric {
+            pub const ALL: &'static [Metric] = &[$(Metric::$variant),*];
+
+            pub fn info(self) -> MetricInfo {
+                match self {
+                    $(Metric::$variant => MetricInfo {
+                        column: $column,
+                        unit: $unit,
+                        direction: $direction,
+                        description: $description,
+                    }),*
+                }
+            }
+        }
+    };
+}
+
+metrics! {
+    PrsOpened => "prs_opened", COUNT, None, "PRs opened";
+    PrsMerged => "prs_merged", COUNT, None, "PRs merged";
+    IssuesOpened => "issues_opened", COUNT, None, "Issues opened";
+    IssuesClosed => "issues_closed", COUNT, None, "Issues closed, for any reason";
+    ChurnAdditions => "churn_additions", COUNT, None, "Lines added by commits authored that day";
+    ChurnDeletions => "churn_deletions", COUNT, None, "Lines deleted by commits authored that day";
+    CiFailures => "ci_failures", COUNT, LOWER, "Workflow runs that failed";
+    CiRuns => "ci_runs", COUNT, None, "Workflow runs started";
+    Stars => "stars", COUNT, HIGHER, "Total stars as of that day";
+    OpenItemsCount => "open_items_count", COUNT, LOWER, "Open issues and PRs at the end of the day";
+    OpenIssuesCount => "open_issues_count", COUNT, LOWER, "Open issues at the end of the day";
+    OpenPrsCount => "open_prs_count", COUNT, LOWER, "Open PRs at the end of the day";
+    TimeToFirstResponse => "time_to_first_response", HOURS, LOWER, "Hours from opening to the first reply by someone other than the author";
+    AvgIssueResolutionTime => "avg_issue_resolution_time", HOURS, LOWER, "Hours from opening to close for issues resolved that day, stale-bot closes excluded";
+    AvgPrResolutionTime => "avg_pr_resolution_time", HOURS, LOWER, "Hours from opening to merge or close for PRs finished that day";
+    TimeToMergeInternal => "time_to_merge_internal", HOURS, LOWER, "Hours from opening to merge for team members' PRs";
+    TimeToMergeExternal => "time_to_merge_external", HOURS, LOWER, "Hours from opening to merge for external contributors' PRs";
+    PrsMergedInternal => "prs_merged_internal", COUNT, None, "Merged PRs by team members";
+    PrsMergedExternal => "prs_merged_external", COUNT, None, "Merged PRs by external contributors";
+    IssuesStaleClosed => "issues_stale_closed", COUNT, LOWER, "Issues closed by a stale bot";
+    IssuesResolvedClosed => "issues_resolved_closed", COUNT, HIGHER, "Issues closed by a person";
+    QaDiscussionsOpened => "qa_discussions_opened", COUNT, None, "Q&A discussions opened";
+    QaDiscussionsAnswered => "qa_discussions_answered", COUNT, HIGHER, "Q&A discussions that got an accepted answer";
+    DiscussionsAnsweredRate => "discussions_answered_rate", PERCENT, HIGHER, "Answered share of Q&A discussions opened";
+    PrsMergedXs => "prs_merged_xs", COUNT, None, "Merged PRs in the XS size bucket";
+    PrsMergedS => "prs_merged_s", COUNT, None, "Merged PRs in the S size bucket";
+    PrsMergedM => "prs_merged_m", COUNT, None, "Merged PRs in the M size bucket";
+    PrsMergedL => "prs_merged_l", COUNT, None, "Merged PRs in the L size bucket";
+    PrsMergedXl => "prs_merged_xl", COUNT, None, "Merged PRs in the XL size bucket";
+    OpenItemsOver7d => "open_items_over_7d", COUNT, LOWER, "Open issues and PRs older than 7 days";
+    OpenItemsOver30d => "open_items_over_30d", COUNT, LOWER, "Open issues and PRs older than 30 days";
+    OpenItemsOver90d => "open_items_over_90d", COUNT, LOWER, "Open issues and PRs older than 90 days";
+    PrDescriptionQuality => "pr_description_quality", PERCENT, HIGHER, "Share of merged PRs with a complete description";
+    CommunityHealthScore => "community_health_score", None, HIGHER, "Weighted composite of responsiveness, merge rate, star growth and diversity";
+    AvgTimeToAssignHours => "avg_time_to_assign_hours", HOURS, LOWER, "Hours from opening to first assignment";
+    ReviewRequestFulfillmentRate => "review_request_fulfillment_rate", PERCENT, HIGHER, "Share of individual review requests on merged PRs that got a review";
+    AvgIssueCommentLagHours => "avg_issue_comment_lag_hours", HOURS, LOWER, "Average gap between successive comments on issues";
+    AvgCiQueueTime => "avg_ci_queue_time", MINUTES, LOWER, "Minutes workflow runs waited before starting";
+    AvgCiDuration => "avg_ci_duration", MINUTES, LOWER, "Minutes workflow runs took once started";
+    AvgCommitMessageLength => "avg_commit_message_length", COUNT, None, "Average commit subject length in characters";
+    ConventionalCommitRate => "conventional_commit_rate", PERCENT, HIGHER, "Share of commits with a conventional-commit subject";
+    IssueResponseRate => "issue_response_rate", PERCENT, HIGHER, "Share of issues opened that day that got a reply from someone other than the author or a bot";
+    AvgTimeToMergeAfterApproveHours => "avg_time_to_merge_after_approve_hours", HOURS, LOWER, "Hours from first approval to merge";
+    ExternalCiFailureRate => "external_ci_failure_rate", PERCENT, LOWER, "Failure share of check runs from external apps";
+    AbandonedPrs => "abandoned_prs", COUNT, LOWER, "Open PRs with no comments or reviews past the abandonment age";
+    AvgPrChurnPerFile => "avg_pr_churn_per_file", COUNT, None, "Lines changed per file touched, averaged over merged PRs";
+    TimeToFirstResponseBusiness => "time_to_first_response_business", HOURS, LOWER, "First response time counting business days only";
+    OpenCriticalVulnerabilities => "open_critical_vulnerabilities", COUNT, LOWER, "Open critical Dependabot alerts";
+    OpenHighVulnerabilities => "open_high_vulnerabilities", COUNT, LOWER, "Open high-severity Dependabot alerts";
+    PrsWithLinkedIssues => "prs_with_linked_issues", COUNT, HIGHER, "Merged PRs that close an issue";
+    LinkedIssueCloseRate => "linked_issue_close_rate", PERCENT, HIGHER, "Share of merged PRs that close an issue";
+    PrTemplateComplianceRate => "pr_template_compliance_rate", PERCENT, HIGHER, "Share of merged PRs that filled out the repo's PR template";
+    ExternalContributorRetentionRate => "external_contributor_retention_rate", PERCENT, HIGHER, "Share of first-time external contributors who opened a second PR within 180 days";
+    IssuesCompleted => "issues_completed", COUNT, None, "Issues closed as completed";
+    IssuesNotPlanned => "issues_not_planned", COUNT, None, "Issues closed as not planned";
+    IssuesClosedUnknownReason => "issues_closed_unknown_reason", COUNT, None, "Issues closed without a recorded reason";
+    AvgReviewDepth => "avg_review_depth", COUNT, HIGHER, "Inline comments per submitted review";
+    AvgPrAgeAtMerge => "avg_pr_age_at_merge", HOURS, LOWER, "Hours from opening to merge for PRs merged that day";
+    AvgPrAgeAtCloseUnmerged => "avg_pr_age_at_close_unmerged", HOURS, LOWER, "Hours from opening to close for PRs closed unmerged that day";
+    TriageSlaMetRate => "triage_sla_met_rate", PERCENT, HIGHER, "Share of issues labeled or answered within the triage SLA";
+    AvgCommitToReleaseHours => "avg_commit_to_release_hours", HOURS, LOWER, "Hours from commit to the first release that ships it";
+    BranchCleanupRate => "branch_cleanup_rate", PERCENT, HIGHER, "Share of merged same-repo PRs whose head branch was deleted";
+    MaxAuthorWip => "max_author_wip", COUNT, LOWER, "Most PRs any one author had open at the end of the day";
+    AvgAuthorWip => "avg_author_wip", COUNT, LOWER, "PRs open per author, over authors with any open";
+    BypassedReviews => "bypassed_reviews", COUNT, LOWER, "Merged PRs with an individual review request still unanswered at merge";
+}
+
+impl Metric {
+    pub fn column(self) -> &'static str {
+        self.info().column
+    }
+
+    pub fn from_column(column: &str) -> Option<Metric> {
+        Metric::ALL.iter().copied().find(|m| m.column() == column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn every_metric_is_a_daily_metrics_column() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let columns: HashSet<String> = conn
+            .prepare("SELECT name FROM pragma_table_info('daily_metrics')")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let missing: Vec<&str> = Metric::ALL
+            .iter()
+            .map(|m| m.column())
+            .filter(|c| !columns.contains(*c))
+            .collect();
+        assert!(missing.is_empty(), "not in daily_metrics: {:?}", missing);
+    }
+
+    #[test]
+    fn from_column_round_trips_unique_columns() {
+        let unique: HashSet<&str> = Metric::ALL.iter().map(|m| m.column()).collect();
+        assert_eq!(unique.len(), Metric::ALL.len());
+        for &metric in Metric::ALL {
+            assert_eq!(Metric::from_column(metric.column()), Some(metric));
+        }
+        assert_eq!(Metric::from_column("prs_merged"), Some(Metric::PrsMerged));
+        assert_eq!(Metric::from_column("no_such_metric"), None);
+    }
+
+    #[test]
+    fn units_and_directions_are_ones_goals_accept() {
+        for metric in Metric::ALL {
+            let info = metric.info();
+            if let Some(unit) = info.unit {
+                assert!(crate::goals::UNITS.contains(&unit), "{}", info.column);
+            }
+            if let Some(direction) = info.direction {
+                assert!(
+                    crate::goals::DIRECTIONS.contains(&direction),
+                    "{}",
+                    info.column
+                );
+            }
+        }
+    }
+}
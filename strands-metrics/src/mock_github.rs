@@ -0,0 +1,173 @@
+use octocrab::{Octocrab, OctocrabBuilder};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// One canned response. `route` matches a request's path and query exactly, or its path
+/// alone when `route` has no query. The first matching route wins.
+pub struct Route {
+    route: String,
+    status: u16,
+    body: Value,
+    headers: Vec<(String, String)>,
+}
+
+impl Route {
+    pub fn new(route: &str, status: u16, body: Value) -> Self {
+        Self {
+            route: route.to_string(),
+            status,
+            body,
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn ok(route: &str, body: Value) -> Self {
+        Self::new(route, 200, body)
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Adds a `Link: <next>; rel="next"` header; `next` is a path on the mock server.
+    pub fn next_page(self, server: &MockGitHub, next: &str) -> Self {
+        let link = format!("<{}{}>; rel=\"next\"", server.uri, next);
+        self.header("link", &link)
+    }
+
+    fn matches(&self, target: &str) -> bool {
+        target == self.route
+            || (!self.route.contains('?') && target.split('?').next() == Some(&self.route))
+    }
+}
+
+/// A canned-response HTTP server standing in for api.github.com in client tests.
+pub struct MockGitHub {
+    pub uri: String,
+    routes: Arc<Mutex<Vec<Route>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockGitHub {
+    /// Starts the server with a generous `/rate_limit` already routed. Unrouted requests get a
+    /// GitHub-style 404.
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let uri = format!("http://{}", listener.local_addr().unwrap());
+        let routes = Arc::new(Mutex::new(vec![Route::ok("/rate_limit", rate_limit())]));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let (r, q) = (routes.clone(), requests.clone());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let (routes, requests) = (r.clone(), q.clone());
+                tokio::spawn(async move {
+                    let Some(target) = read_request_target(&mut stream).await else {
+                        return;
+                    };
+                    let response = {
+                        let routes = routes.lock().unwrap();
+                        match routes.iter().find(|route| route.matches(&target)) {
+                            Some(route) => render(route.status, &route.body, &route.headers),
+                            None => render(404, &json!({ "message": "Not Found" }), &[]),
+                        }
+                    };
+                    if !target.starts_with("/rate_limit") {
+                        requests.lock().unwrap().push(target);
+                    }
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        Self {
+            uri,
+            routes,
+            requests,
+        }
+    }
+
+    pub fn route(&self, route: Route) {
+        self.routes.lock().unwrap().push(route);
+    }
+
+    /// Request targets received so far, in order, leaving out rate-limit checks.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// `requests` without their query strings.
+    pub fn paths(&self) -> Vec<String> {
+        self.requests()
+            .iter()
+            .map(|r| r.split('?').next().unwrap_or(r).to_string())
+            .collect()
+    }
+
+    pub fn client(&self) -> Octocrab {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        OctocrabBuilder::new()
+            .base_uri(self.uri.as_str())
+            .unwrap()
+            .personal_token("test-token".to_string())
+            .build()
+            .unwrap()
+    }
+}
+
+fn rate_limit() -> Value {
+    let rate = json!({ "limit": 5000, "used": 0, "remaining": 5000, "reset": 0 });
+    json!({ "resources": { "core": rate, "search": rate }, "rate": rate })
+}
+
+/// Reads the request head (and any body) and returns the target, e.g. `/repos/o/r?page=2`.
+async fn read_request_target(stream: &mut tokio::net::TcpStream) -> Option<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let content_length = head
+        .lines()
+        .find_map(|l| {
+            let (name, value) = l.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse::<usize>().ok())?
+        })
+        .unwrap_or(0);
+    while buf.len() < head_end + content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    head.split_whitespace().nth(1).map(String::from)
+}
+
+fn render(status: u16, body: &Value, headers: &[(String, String)]) -> String {
+    let body = body.to_string();
+    let mut response = format!(
+        "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n",
+        status,
+        body.len()
+    );
+    for (name, value) in headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+    response.push_str(&body);
+    response
+}
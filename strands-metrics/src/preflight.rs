@@ -0,0 +1,60 @@
+/// Response header listing a classic token's OAuth scopes. Fine-grained tokens and app
+/// tokens don't send it.
+pub const OAUTH_SCOPES_HEADER: &str = "x-oauth-scopes";
+
+/// Optional sync stages and the scopes, any one of which, lets them run. Without one the
+/// stage is skipped per repo with a `sync_errors` row rather than failing the sync.
+pub const OPTIONAL_STAGE_SCOPES: &[(&str, &[&str])] = &[
+    ("dependabot_alerts", &["security_events", "repo"]),
+    ("branch_protection", &["repo"]),
+];
+
+/// Splits an `X-OAuth-Scopes` value (`"repo, read:org"`) into scope names.
+pub fn parse_scopes(header: &str) -> Vec<String> {
+    header
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Optional stages none of whose scopes were granted, with the scopes that would enable them.
+pub fn stages_missing_scopes(granted: &[String]) -> Vec<(&'static str, &'static [&'static str])> {
+    OPTIONAL_STAGE_SCOPES
+        .iter()
+        .filter(|(_, needed)| !needed.iter().any(|n| granted.iter().any(|g| g == n)))
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_are_split_and_trimmed() {
+        assert_eq!(
+            parse_scopes("repo, read:org,  ,workflow"),
+            ["repo", "read:org", "workflow"]
+        );
+        assert!(parse_scopes("").is_empty());
+    }
+
+    #[test]
+    fn stages_report_only_scopes_not_granted() {
+        let stages = |header: &str| -> Vec<&str> {
+            stages_missing_scopes(&parse_scopes(header))
+                .into_iter()
+                .map(|(stage, _)| stage)
+                .collect()
+        };
+        assert_eq!(stages("repo"), Vec::<&str>::new());
+        assert_eq!(stages("security_events, read:org"), ["branch_protection"]);
+        assert_eq!(
+            stages("public_repo"),
+            ["dependabot_alerts", "branch_protection"]
+        );
+        assert_eq!(stages_missing_scopes(&[]), OPTIONAL_STAGE_SCOPES.to_vec());
+    }
+}
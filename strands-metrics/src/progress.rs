@@ -0,0 +1,82 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+
+/// Whether `sync` / `sweep` draw a spinner or log plain status lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum ProgressMode {
+    /// Always log plain lines, e.g. for CI.
+    Never,
+    /// Spinner when stderr is a terminal, plain lines otherwise.
+    #[default]
+    Auto,
+    /// Always draw the spinner.
+    Always,
+}
+
+impl ProgressMode {
+    pub fn spinner_enabled(self, stderr_is_terminal: bool) -> bool {
+        match self {
+            ProgressMode::Never => false,
+            ProgressMode::Auto => stderr_is_terminal,
+            ProgressMode::Always => true,
+        }
+    }
+
+    /// Decides against the real stderr, which is where indicatif draws.
+    pub fn spinner_enabled_here(self) -> bool {
+        self.spinner_enabled(std::io::stderr().is_terminal())
+    }
+}
+
+/// A ticking spinner, or a hidden bar when the spinner is disabled so `set_status` logs instead.
+pub fn spinner(mode: ProgressMode, multi: &MultiProgress, message: &str) -> ProgressBar {
+    if !mode.spinner_enabled_here() {
+        let pb = ProgressBar::hidden();
+        set_status(&pb, message.to_string());
+        return pb;
+    }
+    let sty = ProgressStyle::with_template("{spinner:.green} {msg}")
+        .unwrap()
+        .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ ");
+    let pb = multi.add(ProgressBar::new_spinner());
+    pb.set_style(sty);
+    pb.enable_steady_tick(std::time::Duration::from_millis(120));
+    pb.set_message(message.to_string());
+    pb
+}
+
+/// Finishes the spinner with a final message, logging it instead when the spinner is hidden.
+pub fn finish(pb: &ProgressBar, message: String) {
+    if pb.is_hidden() {
+        tracing::info!("{}", message);
+    }
+    pb.finish_with_message(message);
+}
+
+/// Updates the spinner, or logs the message as a plain line when the spinner is hidden.
+pub fn set_status(pb: &ProgressBar, message: String) {
+    if pb.is_hidden() {
+        tracing::info!("{}", message);
+    }
+    pb.set_message(message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_follows_whether_stderr_is_a_terminal() {
+        assert!(!ProgressMode::Auto.spinner_enabled(false));
+        assert!(ProgressMode::Auto.spinner_enabled(true));
+        assert!(!ProgressMode::Never.spinner_enabled(true));
+        assert!(ProgressMode::Always.spinner_enabled(false));
+    }
+
+    #[test]
+    fn disabled_spinner_is_a_hidden_bar() {
+        let pb = spinner(ProgressMode::Never, &MultiProgress::new(), "Syncing...");
+        assert!(pb.is_hidden());
+        assert_eq!(pb.message(), "Syncing...");
+    }
+}
@@ -0,0 +1,70 @@
+use serde_json::Value;
+
+/// Field left in a redacted payload in place of `body`, so body-length metrics keep working.
+pub const BODY_LENGTH_FIELD: &str = "body_length";
+
+/// Free-text fields dropped from stored payloads under `--redact-bodies`. The `title` columns
+/// are kept, since duplicate detection reads them.
+const REDACTED_FIELDS: &[&str] = &["body", "body_text", "body_html", "bodyText", "title"];
+
+/// Strips the free-text fields from an API payload and every object nested in it (the `issue`
+/// inside an issue event, the `pull_request` and `comment` inside an org event payload),
+/// keeping everything structural (association, labels, timestamps, reactions). Each `body` is
+/// replaced by its length after the same space trimming SQLite's `trim()` does, which is what
+/// the metrics compare.
+pub fn redact_payload(value: &mut Value) {
+    match value {
+        Value::Object(obj) => {
+            if let Some(body) = obj.get("body").and_then(|b| b.as_str()) {
+                let length = body.trim_matches(' ').chars().count();
+                obj.insert(BODY_LENGTH_FIELD.to_string(), Value::from(length));
+            }
+            for field in REDACTED_FIELDS {
+                obj.remove(*field);
+            }
+            for nested in obj.values_mut() {
+                redact_payload(nested);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_payload),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn strips_bodies_and_keeps_structure() {
+        let mut event = json!({
+            "event": "labeled",
+            "issue": {
+                "title": "Secret title",
+                "body": " secret body ",
+                "author_association": "CONTRIBUTOR",
+                "labels": [{"name": "bug"}],
+            },
+            "comments": [{"body_html": "<p>x</p>", "author_association": "MEMBER"}],
+        });
+        redact_payload(&mut event);
+
+        let text = event.to_string();
+        assert!(!text.contains("secret"), "{}", text);
+        assert!(!text.contains("Secret"), "{}", text);
+        assert!(!text.contains("<p>"), "{}", text);
+        assert_eq!(event["issue"]["author_association"], "CONTRIBUTOR");
+        assert_eq!(event["issue"]["labels"][0]["name"], "bug");
+        assert_eq!(event["issue"][BODY_LENGTH_FIELD], 11);
+        assert_eq!(event["comments"][0]["author_association"], "MEMBER");
+        assert_eq!(event["event"], "labeled");
+    }
+
+    #[test]
+    fn null_body_gets_no_length() {
+        let mut pr = json!({"body": null, "author_association": "OWNER"});
+        redact_payload(&mut pr);
+        assert_eq!(pr, json!({"author_association": "OWNER"}));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::labels::{label_names, upsert_item_labels};
+use crate::links::{closing_issue_refs, upsert_pr_closes_issue};
+use crate::store::MetricsStore;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// Derived tables `reextract` can rebuild from stored payloads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum ReextractTarget {
+    /// `item_labels`, for issues and PRs.
+    Labels,
+    /// `pr_closes_issue` and `pull_requests.linked_issues`, from PR bodies.
+    Links,
+    #[default]
+    All,
+}
+
+impl ReextractTarget {
+    fn labels(self) -> bool {
+        matches!(self, ReextractTarget::Labels | ReextractTarget::All)
+    }
+
+    fn links(self) -> bool {
+        matches!(self, ReextractTarget::Links | ReextractTarget::All)
+    }
+}
+
+/// Rows visited by `reextract`.
+#[derive(Debug, Default)]
+pub struct ReextractStats {
+    pub rebuilt: usize,
+    /// Trimmed or unparseable payloads, left as they were.
+    pub skipped: usize,
+    /// PRs whose payload was stored redacted, so their links were kept rather than rebuilt.
+    pub links_skipped: usize,
+}
+
+/// Re-runs the sync-time extraction over the `data` JSON already stored for issues and PRs,
+/// replacing each item's derived rows, so a parsing fix doesn't need a re-fetch. Trimmed
+/// payloads no longer carry labels or bodies and are skipped rather than emptied. Only labels
+/// and links are rebuilt: sync doesn't normalise assignees or reactions into tables.
+pub fn reextract(store: &dyn MetricsStore, what: ReextractTarget) -> Result<ReextractStats> {
+    let mut stats = ReextractStats::default();
+
+    for item_type in ["issue", "pr"] {
+        for (repo, number, data) in store.item_payloads(item_type)? {
+            let Ok(item) = serde_json::from_str::<Value>(&data) else {
+                stats.skipped += 1;
+                continue;
+            };
+            if item.get("trimmed").is_some() {
+                stats.skipped += 1;
+                continue;
+            }
+
+            if what.labels() {
+                upsert_item_labels(store, &repo, item_type, number, &label_names(&item))?;
+            }
+            if what.links() && item_type == "pr" {
+                // A redacted payload has no `body` key at all; a PR without a body has `null`.
+                match item.get("body") {
+                    None => stats.links_skipped += 1,
+                    Some(body) => {
+                        let closes = closing_issue_refs(body.as_str().unwrap_or(""));
+                        upsert_pr_closes_issue(store, &repo, number, &closes)?;
+                        store.update_rows(
+                            "pull_requests",
+                            &[("linked_issues", json!(serde_json::to_string(&closes)?))],
+                            &[("repo", json!(repo)), ("number", json!(number))],
+                        )?;
+                    }
+                }
+            }
+            stats.rebuilt += 1;
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dry_run::DryRunConnection;
+    use rusqlite::params;
+
+    fn rows(store: &dyn MetricsStore, sql: &str) -> Vec<Vec<Option<String>>> {
+        store.query_metrics(sql).unwrap().1
+    }
+
+    #[test]
+    fn reextract_rebuilds_labels_and_links_and_skips_trimmed_or_redacted() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let store = DryRunConnection::new(&conn, false);
+        for (table, number, data) in [
+            (
+                "issues",
+                1,
+                json!({ "labels": [{ "name": "bug" }] }).to_string(),
+            ),
+            (
+                "pull_requests",
+                2,
+                json!({ "body": "Fixes #1", "labels": [{ "name": "enhancement" }] }).to_string(),
+            ),
+            ("pull_requests", 3, json!({ "labels": [] }).to_string()),
+            ("pull_requests", 4, json!({ "trimmed": true }).to_string()),
+            ("issues", 5, "not json".to_string()),
+        ] {
+            store
+                .execute(
+                    &format!(
+                        "INSERT INTO {table} (id, repo, number, state, author, created_at, updated_at, data)
+                         VALUES (?1, 'r', ?1, 'open', 'alice', '2026-01-01', '2026-01-01', ?2)"
+                    ),
+                    params![number, data],
+                )
+                .unwrap();
+        }
+        upsert_item_labels(&store, "r", "issue", 1, &["stale".to_string()]).unwrap();
+        upsert_item_labels(&store, "r", "pr", 4, &["kept".to_string()]).unwrap();
+        upsert_pr_closes_issue(&store, "r", 3, &[9]).unwrap();
+
+        let stats = reextract(&store, ReextractTarget::All).unwrap();
+        assert_eq!(
+            (stats.rebuilt, stats.skipped, stats.links_skipped),
+            (3, 2, 1)
+        );
+
+        let text = |s: &str| Some(s.to_string());
+        assert_eq!(
+            rows(
+                &store,
+                "SELECT item_type, item_number, label FROM item_labels ORDER BY item_number"
+            ),
+            [
+                vec![text("issue"), text("1"), text("bug")],
+                vec![text("pr"), text("2"), text("enhancement")],
+                vec![text("pr"), text("4"), text("kept")],
+            ]
+        );
+        assert_eq!(
+            rows(
+                &store,
+                "SELECT pr_number, issue_number FROM pr_closes_issue ORDER BY pr_number"
+            ),
+            [vec![text("2"), text("1")], vec![text("3"), text("9")]]
+        );
+        assert_eq!(
+            rows(
+                &store,
+                "SELECT linked_issues FROM pull_requests WHERE number = 2"
+            ),
+            [vec![text("[1]")]]
+        );
+    }
+
+    #[test]
+    fn labels_target_leaves_links_alone() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let store = DryRunConnection::new(&conn, false);
+        store
+            .execute(
+                "INSERT INTO pull_requests (id, repo, number, state, author, created_at, updated_at, data)
+                 VALUES (1, 'r', 1, 'open', 'alice', '2026-01-01', '2026-01-01', ?1)",
+                params![json!({ "body": "Closes #7", "labels": [{ "name": "bug" }] }).to_string()],
+            )
+            .unwrap();
+
+        reextract(&store, ReextractTarget::Labels).unwrap();
+        assert_eq!(rows(&store, "SELECT label FROM item_labels").len(), 1);
+        assert!(rows(&store, "SELECT * FROM pr_closes_issue").is_empty());
+    }
+}
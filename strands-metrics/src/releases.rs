@@ -0,0 +1,149 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+/// Activity between two releases of a repo, changelog-style.
+pub struct ReleaseWindow {
+    pub repo: String,
+    /// Tag the window ends at, or `None` for work merged since the latest release.
+    pub release: Option<String>,
+    /// Start of the window: the previous release's publish time, or `None` if there is none.
+    pub from: Option<String>,
+    /// End of the window: the release's publish time, or `None` for "now".
+    pub to: Option<String>,
+    pub prs_merged: i64,
+    pub issues_closed: i64,
+    pub commits: i64,
+}
+
+/// For each repo with at least one published release: stats for its latest release (since the
+/// previous one, or since the beginning if it only has one) and for what has landed since.
+/// Drafts are ignored; repos without releases are left out.
+pub fn release_windows(conn: &Connection) -> Result<Vec<ReleaseWindow>> {
+    let mut stmt = conn.prepare(
+        "SELECT repo, tag_name, published_at FROM releases
+         WHERE draft = 0 AND published_at IS NOT NULL
+         ORDER BY repo, published_at DESC",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut windows = Vec::new();
+    let mut i = 0;
+    while i < rows.len() {
+        let (repo, latest_tag, latest_at) = &rows[i];
+        let previous_at = rows
+            .get(i + 1)
+            .filter(|(r, _, _)| r == repo)
+            .map(|(_, _, at)| at.clone());
+
+        windows.push(window(
+            conn,
+            repo,
+            Some(latest_tag.clone()),
+            previous_at,
+            Some(latest_at.clone()),
+        )?);
+        windows.push(window(conn, repo, None, Some(latest_at.clone()), None)?);
+
+        while i < rows.len() && &rows[i].0 == repo {
+            i += 1;
+        }
+    }
+    Ok(windows)
+}
+
+/// Counts activity with `from < timestamp <= to`. Open ends are unbounded.
+fn window(
+    conn: &Connection,
+    repo: &str,
+    release: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<ReleaseWindow> {
+    let count = |table: &str, column: &str| -> Result<i64> {
+        Ok(conn.query_row(
+            &format!(
+                "SELECT count(*) FROM {0}
+                 WHERE repo = ?1 AND {1} IS NOT NULL
+                   AND (?2 IS NULL OR julianday({1}) > julianday(?2))
+                   AND (?3 IS NULL OR julianday({1}) <= julianday(?3))",
+                table, column
+            ),
+            params![repo, from, to],
+            |row| row.get(0),
+        )?)
+    };
+
+    Ok(ReleaseWindow {
+        repo: repo.to_string(),
+        prs_merged: count("pull_requests", "merged_at")?,
+        issues_closed: count("issues", "closed_at")?,
+        commits: count("commits", "date")?,
+        release,
+        from,
+        to,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    #[test]
+    fn stats_fall_between_release_dates() {
+        let conn = init_db(":memory:").unwrap();
+        conn.execute_batch(
+            "INSERT INTO releases (id, repo, tag_name, draft, created_at, published_at) VALUES
+                (1, 'r', 'v1', 0, '2026-01-01T00:00:00Z', '2026-01-01T00:00:00Z'),
+                (2, 'r', 'v2', 0, '2026-02-01T00:00:00Z', '2026-02-01T00:00:00Z'),
+                (3, 'r', 'v3', 1, '2026-03-01T00:00:00Z', '2026-03-01T00:00:00Z'),
+                (4, 's', 'v0.1', 0, '2026-01-05T00:00:00Z', '2026-01-05T00:00:00Z');
+             INSERT INTO pull_requests (id, repo, number, state, author, created_at, updated_at, merged_at, data) VALUES
+                (1, 'r', 1, 'closed', 'a', '2025-12-01T00:00:00Z', '2025-12-01T00:00:00Z', '2025-12-15T00:00:00Z', '{}'),
+                (2, 'r', 2, 'closed', 'a', '2026-01-10T00:00:00Z', '2026-01-10T00:00:00Z', '2026-01-15T00:00:00Z', '{}'),
+                (3, 'r', 3, 'closed', 'a', '2026-01-20T00:00:00Z', '2026-01-20T00:00:00Z', '2026-02-01T00:00:00Z', '{}'),
+                (4, 'r', 4, 'closed', 'a', '2026-02-02T00:00:00Z', '2026-02-02T00:00:00Z', '2026-02-10T00:00:00Z', '{}'),
+                (5, 's', 1, 'closed', 'a', '2025-12-30T00:00:00Z', '2025-12-30T00:00:00Z', '2026-01-01T00:00:00Z', '{}');
+             INSERT INTO issues (id, repo, number, state, author, created_at, updated_at, closed_at, data) VALUES
+                (1, 'r', 10, 'closed', 'a', '2026-01-02T00:00:00Z', '2026-01-02T00:00:00Z', '2026-01-20T00:00:00Z', '{}');
+             INSERT INTO commits (sha, repo, author, date) VALUES
+                ('c1', 'r', 'a', '2026-01-10T00:00:00Z'),
+                ('c2', 'r', 'a', '2026-02-05T00:00:00Z');",
+        )
+        .unwrap();
+
+        let windows: Vec<_> = release_windows(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|w| {
+                (
+                    w.repo,
+                    w.release,
+                    w.from.is_some(),
+                    w.to.is_some(),
+                    [w.prs_merged, w.issues_closed, w.commits],
+                )
+            })
+            .collect();
+        let s = |v: &str| Some(v.to_string());
+        assert_eq!(
+            windows,
+            [
+                // v2 covers (v1, v2]; the draft v3 is ignored.
+                ("r".to_string(), s("v2"), true, true, [2, 1, 1]),
+                ("r".to_string(), None, true, false, [1, 0, 1]),
+                // A single release counts from the beginning.
+                ("s".to_string(), s("v0.1"), false, true, [1, 0, 0]),
+                ("s".to_string(), None, true, false, [0, 0, 0]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use clap::Args;
+use regex::Regex;
+use std::path::PathBuf;
+
+/// Which org repos `sync` and `sweep` process.
+#[derive(Args, Debug, Default)]
+pub struct RepoSelection {
+    /// Only process repos listed in this file, one name per line. `#` starts a comment.
+    #[clap(long)]
+    pub repos_file: Option<PathBuf>,
+    /// Skip repos matching these globs (`*` and `?`), comma-separated.
+    #[clap(long, value_delimiter = ',')]
+    pub exclude_repos: Vec<String>,
+}
+
+/// Order in which `sync` visits repos, so the ones that matter most land first if the rate
+/// limit runs out part-way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum RepoOrder {
+    /// Alphabetical.
+    #[default]
+    Name,
+    /// Most recently pushed first.
+    Activity,
+    /// Least recently synced first; never-synced repos come before all others.
+    LastSynced,
+}
+
+#[derive(Debug, Default)]
+pub struct RepoFilter {
+    only: Option<Vec<String>>,
+    exclude: Vec<Regex>,
+}
+
+impl RepoFilter {
+    pub fn from_selection(selection: &RepoSelection) -> Result<Self> {
+        let only = match &selection.repos_file {
+            Some(path) => Some(
+                std::fs::read_to_string(path)?
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                    .map(String::from)
+                    .collect(),
+            ),
+            None => None,
+        };
+        let exclude = selection
+            .exclude_repos
+            .iter()
+            .map(|g| glob_regex(g))
+            .collect::<Result<_>>()?;
+        Ok(Self { only, exclude })
+    }
+
+    pub fn allows(&self, repo: &str) -> bool {
+        if let Some(only) = &self.only {
+            if !only.iter().any(|r| r == repo) {
+                return false;
+            }
+        }
+        !self.exclude.iter().any(|re| re.is_match(repo))
+    }
+}
+
+fn glob_regex(glob: &str) -> Result<Regex> {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Ok(Regex::new(&pattern)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_whole_names_only() {
+        let re = glob_regex("sdk-?y*").unwrap();
+        assert!(re.is_match("sdk-python"));
+        assert!(!re.is_match("sdk-java"));
+        assert!(!re.is_match("my-sdk-python"));
+        assert!(glob_regex("a.b").unwrap().is_match("a.b"));
+        assert!(!glob_regex("a.b").unwrap().is_match("axb"));
+    }
+
+    #[test]
+    fn repos_file_and_excludes_combine() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repos.txt");
+        std::fs::write(&path, "# core\nsdk-python\n\n  tools  \ndocs\n").unwrap();
+        let filter = RepoFilter::from_selection(&RepoSelection {
+            repos_file: Some(path),
+            exclude_repos: vec!["doc*".to_string()],
+        })
+        .unwrap();
+
+        assert!(filter.allows("sdk-python"));
+        assert!(filter.allows("tools"));
+        assert!(!filter.allows("docs"));
+        assert!(!filter.allows("samples"));
+        assert!(RepoFilter::default().allows("anything"));
+    }
+}
@@ -0,0 +1,247 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+
+/// How tabular command output is printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Pipe-separated columns for reading in a terminal.
+    #[default]
+    Plain,
+    /// A GitHub-flavoured Markdown table for pasting into issues or docs.
+    Markdown,
+}
+
+/// Shown for a `None` cell in plain output, so later cells stay under their headers.
+pub const EMPTY_CELL: &str = "-";
+
+/// Prints a command's table in the chosen format. Each row has one cell per header.
+pub fn print_table(format: OutputFormat, headers: &[String], rows: &[Vec<Option<String>>]) {
+    match format {
+        OutputFormat::Plain => print!("{}", plain_table(headers, rows)),
+        OutputFormat::Markdown => print!("{}", markdown_table(headers, rows)),
+    }
+}
+
+/// Pipe-separated rows under a header line, with `None` cells shown as [`EMPTY_CELL`].
+fn plain_table(headers: &[String], rows: &[Vec<Option<String>>]) -> String {
+    let mut out = format!("{}\n", headers.join(" | "));
+    for row in rows {
+        let cells: Vec<&str> = row
+            .iter()
+            .map(|c| c.as_deref().unwrap_or(EMPTY_CELL))
+            .collect();
+        out.push_str(&format!("{}\n", cells.join(" | ")));
+    }
+    out
+}
+
+/// Renders a Markdown table padded to the widest cell in each column. Columns where every
+/// non-empty cell is a number are right-aligned, everything else left-aligned. `None` cells are
+/// left empty, pipes are escaped and newlines are collapsed to spaces so multiline values
+/// (e.g. raw `data` JSON) stay on one row.
+pub fn markdown_table(headers: &[String], rows: &[Vec<Option<String>>]) -> String {
+    let headers: Vec<String> = headers.iter().map(|h| markdown_cell(h)).collect();
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|c| markdown_cell(c.as_deref().unwrap_or("")))
+                .collect()
+        })
+        .collect();
+
+    let columns = headers.len();
+    let numeric: Vec<bool> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i).filter(|c| !c.is_empty()))
+                .all(|c| c.parse::<f64>().is_ok())
+        })
+        .collect();
+    // GFM needs at least three dashes per separator cell.
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .chain([&headers[i]])
+                .map(|c| c.chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    let render = |cells: &[String]| {
+        let padded: Vec<String> = (0..columns)
+            .map(|i| {
+                let cell = cells.get(i).map(String::as_str).unwrap_or("");
+                if numeric[i] {
+                    format!("{:>w$}", cell, w = widths[i])
+                } else {
+                    format!("{:<w$}", cell, w = widths[i])
+                }
+            })
+            .collect();
+        format!("| {} |\n", padded.join(" | "))
+    };
+
+    let separator: Vec<String> = (0..columns)
+        .map(|i| {
+            if numeric[i] {
+                format!("{}:", "-".repeat(widths[i] - 1))
+            } else {
+                "-".repeat(widths[i])
+            }
+        })
+        .collect();
+
+    let mut out = render(&headers);
+    out.push_str(&format!("| {} |\n", separator.join(" | ")));
+    for row in &rows {
+        out.push_str(&render(row));
+    }
+    out
+}
+
+fn markdown_cell(value: &str) -> String {
+    value
+        .split(['\r', '\n'])
+        .filter(|line| !line.trim().is_empty())
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+}
+
+const SPARK_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One character per value, scaled between the series' min and max. `None` (no data that day)
+/// renders as a space; a flat series renders as the lowest block.
+pub fn sparkline(values: &[Option<f64>]) -> String {
+    let present = values.iter().flatten();
+    let min = present.clone().copied().fold(f64::INFINITY, f64::min);
+    let max = present.copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|v| match v {
+            None => ' ',
+            Some(_) if max <= min => SPARK_BLOCKS[0],
+            Some(v) => {
+                let level = ((v - min) / (max - min) * (SPARK_BLOCKS.len() - 1) as f64).round();
+                SPARK_BLOCKS[level as usize]
+            }
+        })
+        .collect()
+}
+
+/// Days covered by `repo_trends`.
+pub const TREND_DAYS: i64 = 30;
+
+/// A repo's recent activity for `list-repos`: one entry per day of the last `TREND_DAYS`,
+/// oldest first, `None` before the repo's first `daily_metrics` row.
+pub struct RepoTrend {
+    pub repo: String,
+    pub prs_merged: Vec<Option<f64>>,
+    pub stars: Vec<Option<f64>>,
+}
+
+impl RepoTrend {
+    pub fn prs_merged_total(&self) -> i64 {
+        self.prs_merged.iter().flatten().sum::<f64>() as i64
+    }
+
+    /// Stars on the latest day with data.
+    pub fn current_stars(&self) -> i64 {
+        self.stars
+            .iter()
+            .rev()
+            .flatten()
+            .next()
+            .copied()
+            .unwrap_or(0.0) as i64
+    }
+}
+
+/// Per repo, daily merged PRs and star totals over the last `TREND_DAYS` days, by repo name.
+pub fn repo_trends(conn: &Connection) -> Result<Vec<RepoTrend>> {
+    let first_day = (Utc::now() - Duration::days(TREND_DAYS - 1)).date_naive();
+    let mut stmt = conn.prepare(
+        "SELECT repo, date, prs_merged, stars FROM daily_metrics
+         WHERE date >= ?1 ORDER BY repo, date",
+    )?;
+    let rows = stmt
+        .query_map(params![first_day.format("%Y-%m-%d").to_string()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<f64>>(2)?,
+                row.get::<_, Option<f64>>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut by_repo: BTreeMap<String, RepoTrend> = BTreeMap::new();
+    for (repo, date, prs_merged, stars) in rows {
+        let Ok(day) = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+            continue;
+        };
+        let index = (day - first_day).num_days();
+        if !(0..TREND_DAYS).contains(&index) {
+            continue;
+        }
+        let trend = by_repo.entry(repo.clone()).or_insert_with(|| RepoTrend {
+            repo,
+            prs_merged: vec![None; TREND_DAYS as usize],
+            stars: vec![None; TREND_DAYS as usize],
+        });
+        trend.prs_merged[index as usize] = Some(prs_merged.unwrap_or(0.0));
+        trend.stars[index as usize] = Some(stars.unwrap_or(0.0));
+    }
+    Ok(by_repo.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_table_keeps_columns_for_empty_cells() {
+        let headers = vec!["repo".to_string(), "from".to_string(), "n".to_string()];
+        let rows = vec![vec![Some("a".to_string()), None, Some("3".to_string())]];
+        assert_eq!(plain_table(&headers, &rows), "repo | from | n\na | - | 3\n");
+    }
+
+    #[test]
+    fn markdown_table_is_valid_gfm() {
+        let headers = vec!["repo".to_string(), "n".to_string()];
+        let rows = vec![
+            vec![Some("a|b".to_string()), Some("12".to_string())],
+            vec![Some("line one\n  line two\r\n".to_string()), None],
+        ];
+        assert_eq!(
+            markdown_table(&headers, &rows),
+            "| repo              |   n |\n\
+             | ----------------- | --: |\n\
+             | a\\|b              |  12 |\n\
+             | line one line two |     |\n"
+        );
+    }
+
+    #[test]
+    fn sparkline_scales_between_min_and_max() {
+        let values = [0.0, 7.0, 14.0, 3.5].map(Some);
+        assert_eq!(sparkline(&values), "▁▅█▃");
+        assert_eq!(sparkline(&[Some(-2.0), Some(2.0)]), "▁█");
+    }
+
+    #[test]
+    fn sparkline_renders_gaps_and_flat_series() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[None, None]), "  ");
+        assert_eq!(sparkline(&[Some(4.0), None, Some(4.0)]), "▁ ▁");
+        assert_eq!(sparkline(&[None, Some(1.0), None, Some(3.0)]), " ▁ █");
+    }
+}
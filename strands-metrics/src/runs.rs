@@ -0,0 +1,63 @@
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+
+/// Records the start of a sync-style invocation and returns its run id.
+/// The row stays `running` until `finish_run` stamps it, so a crashed run is visible as such.
+pub fn start_run(conn: &Connection, command: &str) -> Result<String> {
+    let run_id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO sync_runs (run_id, started_at, command, status) VALUES (?1, ?2, ?3, 'running')",
+        params![run_id, Utc::now().to_rfc3339(), command],
+    )?;
+    Ok(run_id)
+}
+
+/// Stamps the finish time and outcome (`success` or `failed`) of a run.
+pub fn finish_run(conn: &Connection, run_id: &str, repos_synced: usize, ok: bool) -> Result<()> {
+    conn.execute(
+        "UPDATE sync_runs SET finished_at = ?1, repos_synced = ?2, status = ?3 WHERE run_id = ?4",
+        params![
+            Utc::now().to_rfc3339(),
+            repos_synced as i64,
+            if ok { "success" } else { "failed" },
+            run_id
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    fn run(conn: &Connection, run_id: &str) -> (String, Option<String>, Option<i64>, String) {
+        conn.query_row(
+            "SELECT command, finished_at, repos_synced, status FROM sync_runs WHERE run_id = ?1",
+            params![run_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn run_is_running_until_finished() {
+        let conn = init_db(":memory:").unwrap();
+        let ok = start_run(&conn, "sync").unwrap();
+        let failed = start_run(&conn, "sweep").unwrap();
+        assert_ne!(ok, failed);
+        assert_eq!(
+            run(&conn, &ok),
+            ("sync".to_string(), None, None, "running".to_string())
+        );
+
+        finish_run(&conn, &ok, 3, true).unwrap();
+        finish_run(&conn, &failed, 1, false).unwrap();
+
+        let (_, finished_at, repos, status) = run(&conn, &ok);
+        assert!(finished_at.is_some());
+        assert_eq!((repos, status.as_str()), (Some(3), "success"));
+        assert_eq!(run(&conn, &failed).3, "failed");
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::{bail, Result};
+use chrono::DateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub fn get(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT value FROM app_state WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Writes a key. `last_sync_*` values must be RFC3339 timestamps, since sync parses them as such
+/// and silently falls back to "now" otherwise.
+pub fn set(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    if key.starts_with("last_sync_") && DateTime::parse_from_rfc3339(value).is_err() {
+        bail!(
+            "{} must be an RFC3339 timestamp (e.g. 2024-01-01T00:00:00Z), got '{}'",
+            key,
+            value
+        );
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO app_state (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT key, value FROM app_state ORDER BY key")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    #[test]
+    fn last_sync_values_must_be_rfc3339() {
+        let conn = init_db(":memory:").unwrap();
+        let err = set(&conn, "last_sync_o_r", "2024-01-01").unwrap_err();
+        assert!(err.to_string().contains("must be an RFC3339 timestamp"));
+        assert_eq!(get(&conn, "last_sync_o_r").unwrap(), None);
+
+        set(&conn, "last_sync_o_r", "2024-01-01T00:00:00Z").unwrap();
+        set(&conn, "last_cursor_issues_o_r", "not a date").unwrap();
+        assert_eq!(
+            get(&conn, "last_sync_o_r").unwrap().as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            get(&conn, "last_cursor_issues_o_r").unwrap().as_deref(),
+            Some("not a date")
+        );
+    }
+}
@@ -0,0 +1,519 @@
+use crate::dry_run::{json_to_sql, DryRunConnection, PatchOp};
+use anyhow::Result;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, OptionalExtension, ToSql};
+use serde_json::{json, Value};
+
+/// Column names and rows as text, `None` for NULL.
+pub type QueryResult = (Vec<String>, Vec<Vec<Option<String>>>);
+
+/// `(column, value)` pairs for the row-level writes. Values are JSON so callers don't depend
+/// on a database crate.
+pub type Columns<'c> = [(&'c str, Value)];
+
+/// One row of `issues`.
+pub struct IssueRow<'r> {
+    pub id: i64,
+    pub repo: &'r str,
+    pub number: i64,
+    pub state: &'r str,
+    pub author: &'r str,
+    pub title: &'r str,
+    pub created_at: &'r str,
+    pub updated_at: &'r str,
+    pub closed_at: Option<&'r str>,
+    /// "completed", "not_planned", ... for closed issues; `None` on issues closed before
+    /// GitHub recorded a reason.
+    pub state_reason: Option<&'r str>,
+    /// Raw API payload as JSON.
+    pub data: &'r str,
+}
+
+/// One row of `pull_requests`.
+pub struct PullRequestRow<'r> {
+    pub id: i64,
+    pub repo: &'r str,
+    pub number: i64,
+    pub state: &'r str,
+    pub author: &'r str,
+    pub title: &'r str,
+    pub created_at: &'r str,
+    pub updated_at: &'r str,
+    pub merged_at: Option<&'r str>,
+    pub closed_at: Option<&'r str>,
+    /// Issue numbers the body says it closes, as a JSON array.
+    pub linked_issues: &'r str,
+    /// Raw API payload as JSON.
+    pub data: &'r str,
+}
+
+/// The last stored branch protection snapshot for a branch.
+#[derive(Debug, PartialEq)]
+pub struct BranchProtectionRow {
+    pub required_reviewers: i64,
+    pub require_status_checks: bool,
+    pub require_linear_history: bool,
+    pub allow_force_push: bool,
+}
+
+/// Storage used by the sync client, `reextract` and the `query` command. `DryRunConnection` is
+/// the SQLite implementation. No method takes or returns a database crate's types, so another
+/// backend only has to implement these.
+pub trait MetricsStore {
+    /// Writes recorded instead of applied. Empty unless running dry.
+    fn take_patch(&self) -> Vec<PatchOp>;
+
+    /// Inserts one row, replacing any row with the same primary key. Taking `(column, value)`
+    /// pairs keeps the column list and the values from drifting apart when a table grows a
+    /// column.
+    fn upsert_row(&self, table: &str, values: &Columns) -> Result<()>;
+
+    /// Sets `values` on the rows matching every `key` column. Returns the rows matched.
+    fn update_rows(&self, table: &str, values: &Columns, key: &Columns) -> Result<usize>;
+
+    /// Deletes the rows matching every `key` column. Returns the rows matched.
+    fn delete_rows(&self, table: &str, key: &Columns) -> Result<usize>;
+
+    fn get_state(&self, key: &str) -> Result<Option<String>>;
+
+    fn set_state(&self, key: &str, value: &str) -> Result<()> {
+        self.upsert_row("app_state", &[("key", json!(key)), ("value", json!(value))])
+    }
+
+    fn delete_state(&self, key: &str) -> Result<()> {
+        self.delete_rows("app_state", &[("key", json!(key))])?;
+        Ok(())
+    }
+
+    fn upsert_issue(&self, row: &IssueRow) -> Result<()> {
+        self.upsert_row(
+            "issues",
+            &[
+                ("id", json!(row.id)),
+                ("repo", json!(row.repo)),
+                ("number", json!(row.number)),
+                ("state", json!(row.state)),
+                ("author", json!(row.author)),
+                ("title", json!(row.title)),
+                ("created_at", json!(row.created_at)),
+                ("updated_at", json!(row.updated_at)),
+                ("closed_at", json!(row.closed_at)),
+                ("state_reason", json!(row.state_reason)),
+                ("data", json!(row.data)),
+            ],
+        )
+    }
+
+    fn upsert_pull_request(&self, row: &PullRequestRow) -> Result<()> {
+        self.upsert_row(
+            "pull_requests",
+            &[
+                ("id", json!(row.id)),
+                ("repo", json!(row.repo)),
+                ("number", json!(row.number)),
+                ("state", json!(row.state)),
+                ("author", json!(row.author)),
+                ("title", json!(row.title)),
+                ("created_at", json!(row.created_at)),
+                ("updated_at", json!(row.updated_at)),
+                ("merged_at", json!(row.merged_at)),
+                ("closed_at", json!(row.closed_at)),
+                ("linked_issues", json!(row.linked_issues)),
+                ("data", json!(row.data)),
+            ],
+        )
+    }
+
+    /// Numbers of the repo's issues still stored as open, for sweep to recheck.
+    fn open_issue_numbers(&self, repo: &str) -> Result<Vec<i64>>;
+
+    fn has_commit(&self, sha: &str) -> Result<bool>;
+
+    /// SHAs of the repo's commits dated at or after `since` (RFC3339).
+    fn commit_shas_since(&self, repo: &str, since: &str) -> Result<Vec<String>>;
+
+    /// The workflow run id paging can stop at: just below the oldest run created since `since`
+    /// that is still in progress, otherwise the newest stored run.
+    fn workflow_run_stop_id(&self, repo: &str, since: &str) -> Result<Option<i64>>;
+
+    /// Whether the repo's PR template was already checked today.
+    fn pr_template_fetched_today(&self, repo: &str) -> Result<bool>;
+
+    fn latest_branch_protection(
+        &self,
+        repo: &str,
+        branch: &str,
+    ) -> Result<Option<BranchProtectionRow>>;
+
+    fn stargazers(&self, repo: &str) -> Result<Vec<String>>;
+
+    /// `(repo, number, data)` for every stored issue (`item_type` "issue") or PR ("pr") that
+    /// isn't marked deleted.
+    fn item_payloads(&self, item_type: &str) -> Result<Vec<(String, i64, String)>>;
+
+    /// Runs a read query, e.g. for the `query` command.
+    fn query_metrics(&self, sql: &str) -> Result<QueryResult>;
+}
+
+/// Runs one write with JSON values bound as `?1`, `?2`, ... in order.
+fn execute_json<'v>(
+    store: &DryRunConnection,
+    sql: &str,
+    values: impl Iterator<Item = &'v Value>,
+) -> Result<usize> {
+    let values: Vec<SqlValue> = values.map(json_to_sql).collect();
+    let params: Vec<&dyn ToSql> = values.iter().map(|v| v as &dyn ToSql).collect();
+    Ok(store.execute(sql, &params)?)
+}
+
+/// `a = ?n AND b = ?n+1 ...`, numbering from `first`.
+fn predicates(columns: &Columns, first: usize, sep: &str) -> String {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, (c, _))| format!("{} = ?{}", c, first + i))
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+impl MetricsStore for DryRunConnection<'_> {
+    fn take_patch(&self) -> Vec<PatchOp> {
+        DryRunConnection::take_patch(self)
+    }
+
+    fn upsert_row(&self, table: &str, values: &Columns) -> Result<()> {
+        let columns = values.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+        let placeholders = (1..=values.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>();
+        execute_json(
+            self,
+            &format!(
+                "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+                table,
+                columns.join(", "),
+                placeholders.join(", ")
+            ),
+            values.iter().map(|(_, v)| v),
+        )?;
+        Ok(())
+    }
+
+    fn update_rows(&self, table: &str, values: &Columns, key: &Columns) -> Result<usize> {
+        execute_json(
+            self,
+            &format!(
+                "UPDATE {} SET {} WHERE {}",
+                table,
+                predicates(values, 1, ", "),
+                predicates(key, values.len() + 1, " AND ")
+            ),
+            values.iter().chain(key).map(|(_, v)| v),
+        )
+    }
+
+    fn delete_rows(&self, table: &str, key: &Columns) -> Result<usize> {
+        execute_json(
+            self,
+            &format!(
+                "DELETE FROM {} WHERE {}",
+                table,
+                predicates(key, 1, " AND ")
+            ),
+            key.iter().map(|(_, v)| v),
+        )
+    }
+
+    fn get_state(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .query_row(
+                "SELECT value FROM app_state WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn open_issue_numbers(&self, repo: &str) -> Result<Vec<i64>> {
+        let mut stmt = self.prepare(
+            "SELECT number FROM issues
+             WHERE repo = ?1 AND state = 'open' AND closed_at IS NULL AND deleted_at IS NULL",
+        )?;
+        let numbers = stmt
+            .query_map(params![repo], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(numbers)
+    }
+
+    fn has_commit(&self, sha: &str) -> Result<bool> {
+        Ok(self
+            .query_row("SELECT 1 FROM commits WHERE sha = ?1", params![sha], |_| {
+                Ok(())
+            })
+            .optional()?
+            .is_some())
+    }
+
+    fn commit_shas_since(&self, repo: &str, since: &str) -> Result<Vec<String>> {
+        let mut stmt = self.prepare("SELECT sha FROM commits WHERE repo = ?1 AND date >= ?2")?;
+        let shas = stmt
+            .query_map(params![repo, since], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(shas)
+    }
+
+    fn workflow_run_stop_id(&self, repo: &str, since: &str) -> Result<Option<i64>> {
+        Ok(self.query_row(
+            "SELECT COALESCE(
+                (SELECT MIN(id) - 1 FROM workflow_runs
+                 WHERE repo = ?1 AND conclusion = 'in_progress' AND created_at >= ?2),
+                (SELECT MAX(id) FROM workflow_runs WHERE repo = ?1)
+             )",
+            params![repo, since],
+            |row| row.get(0),
+        )?)
+    }
+
+    fn pr_template_fetched_today(&self, repo: &str) -> Result<bool> {
+        Ok(self
+            .query_row(
+                "SELECT 1 FROM pr_templates WHERE repo = ?1 AND date(fetched_at) = date('now')",
+                params![repo],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    fn latest_branch_protection(
+        &self,
+        repo: &str,
+        branch: &str,
+    ) -> Result<Option<BranchProtectionRow>> {
+        Ok(self
+            .query_row(
+                "SELECT required_reviewers, require_status_checks, require_linear_history, allow_force_push
+                 FROM branch_protection_snapshots
+                 WHERE repo = ?1 AND branch = ?2
+                 ORDER BY synced_at DESC LIMIT 1",
+                params![repo, branch],
+                |row| {
+                    Ok(BranchProtectionRow {
+                        required_reviewers: row.get(0)?,
+                        require_status_checks: row.get(1)?,
+                        require_linear_history: row.get(2)?,
+                        allow_force_push: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    fn stargazers(&self, repo: &str) -> Result<Vec<String>> {
+        let mut stmt = self.prepare("SELECT user FROM stargazers WHERE repo = ?1")?;
+        let users = stmt
+            .query_map(params![repo], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(users)
+    }
+
+    fn item_payloads(&self, item_type: &str) -> Result<Vec<(String, i64, String)>> {
+        let table = match item_type {
+            "issue" => "issues",
+            "pr" => "pull_requests",
+            other => anyhow::bail!("Unknown item type '{}'", other),
+        };
+        let mut stmt = self.prepare(&format!(
+            "SELECT repo, number, data FROM {table} WHERE deleted_at IS NULL"
+        ))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn query_metrics(&self, sql: &str) -> Result<QueryResult> {
+        let mut stmt = self.prepare(sql)?;
+        let column_count = stmt.column_count();
+        let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+
+        let mut rows = stmt.query([])?;
+        let mut table = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut row_values = Vec::new();
+            for i in 0..column_count {
+                let text = match row.get_ref(i)? {
+                    ValueRef::Null => None,
+                    ValueRef::Integer(i) => Some(i.to_string()),
+                    ValueRef::Real(f) => Some(f.to_string()),
+                    ValueRef::Text(t) => Some(String::from_utf8_lossy(t).to_string()),
+                    ValueRef::Blob(_) => Some("<BLOB>".to_string()),
+                };
+                row_values.push(text);
+            }
+            table.push(row_values);
+        }
+        Ok((names, table))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue<'r>(title: &'r str, closed_at: Option<&'r str>) -> IssueRow<'r> {
+        IssueRow {
+            id: 1,
+            repo: "r",
+            number: 7,
+            state: if closed_at.is_some() {
+                "closed"
+            } else {
+                "open"
+            },
+            author: "alice",
+            title,
+            created_at: "2024-01-01T00:00:00Z",
+            updated_at: "2024-01-02T00:00:00Z",
+            closed_at,
+            state_reason: closed_at.map(|_| "completed"),
+            data: "{}",
+        }
+    }
+
+    #[test]
+    fn state_round_trips_and_deletes() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let store = DryRunConnection::new(&conn, false);
+
+        assert_eq!(store.get_state("cursor").unwrap(), None);
+        store.set_state("cursor", "a").unwrap();
+        store.set_state("cursor", "b").unwrap();
+        assert_eq!(store.get_state("cursor").unwrap().as_deref(), Some("b"));
+        store.delete_state("cursor").unwrap();
+        assert_eq!(store.get_state("cursor").unwrap(), None);
+    }
+
+    #[test]
+    fn upserts_replace_rows_and_query_reports_nulls() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let store = DryRunConnection::new(&conn, false);
+
+        store.upsert_issue(&issue("first", None)).unwrap();
+        store
+            .upsert_issue(&issue("second", Some("2024-01-03T00:00:00Z")))
+            .unwrap();
+        store
+            .upsert_pull_request(&PullRequestRow {
+                id: 2,
+                repo: "r",
+                number: 8,
+                state: "open",
+                author: "bob",
+                title: "pr",
+                created_at: "2024-01-01T00:00:00Z",
+                updated_at: "2024-01-01T00:00:00Z",
+                merged_at: None,
+                closed_at: None,
+                linked_issues: "[7]",
+                data: "{}",
+            })
+            .unwrap();
+
+        let (names, rows) = store
+            .query_metrics("SELECT number, title, state_reason FROM issues")
+            .unwrap();
+        assert_eq!(names, vec!["number", "title", "state_reason"]);
+        assert_eq!(
+            rows,
+            vec![vec![
+                Some("7".to_string()),
+                Some("second".to_string()),
+                Some("completed".to_string()),
+            ]]
+        );
+
+        let (_, rows) = store
+            .query_metrics("SELECT merged_at, linked_issues FROM pull_requests")
+            .unwrap();
+        assert_eq!(rows, vec![vec![None, Some("[7]".to_string())]]);
+    }
+
+    #[test]
+    fn dry_run_records_writes_without_applying_them() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let store = DryRunConnection::new(&conn, true);
+
+        store.set_state("cursor", "a").unwrap();
+        store.upsert_issue(&issue("first", None)).unwrap();
+
+        let patch = store.take_patch();
+        let tables: Vec<&str> = patch.iter().map(|op| op.table.as_str()).collect();
+        assert_eq!(tables, vec!["app_state", "issues"]);
+        assert_eq!(store.get_state("cursor").unwrap(), None);
+        let (_, rows) = store.query_metrics("SELECT id FROM issues").unwrap();
+        assert!(rows.is_empty());
+        assert!(store.take_patch().is_empty());
+    }
+
+    #[test]
+    fn upsert_row_binds_values_to_their_columns() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let store = DryRunConnection::new(&conn, false);
+        let upsert = |value: &str| {
+            store
+                .upsert_row(
+                    "app_state",
+                    &[("value", json!(value)), ("key", json!("cursor"))],
+                )
+                .unwrap()
+        };
+        upsert("a");
+        upsert("b");
+
+        let (_, rows) = store
+            .query_metrics("SELECT key, value FROM app_state WHERE key = 'cursor'")
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![vec![Some("cursor".to_string()), Some("b".to_string())]]
+        );
+    }
+
+    #[test]
+    fn dry_run_upsert_row_records_columns_and_key() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let store = DryRunConnection::new(&conn, true);
+        store
+            .upsert_row(
+                "app_state",
+                &[("key", json!("cursor")), ("value", json!("a"))],
+            )
+            .unwrap();
+
+        let patch = store.take_patch();
+        assert_eq!(patch.len(), 1);
+        assert_eq!(patch[0].op, "insert");
+        assert_eq!(patch[0].key["key"], "cursor");
+        assert_eq!(patch[0].changes["value"], "a");
+    }
+
+    #[test]
+    fn update_and_delete_rows_match_every_key_column() {
+        let conn = crate::db::init_db(":memory:").unwrap();
+        let store = DryRunConnection::new(&conn, false);
+        store.upsert_issue(&issue("first", None)).unwrap();
+        let key = |number: i64| [("repo", json!("r")), ("number", json!(number))];
+
+        let set = [
+            ("state", json!("closed")),
+            ("closed_at", json!("2024-01-03")),
+        ];
+        assert_eq!(store.update_rows("issues", &set, &key(8)).unwrap(), 0);
+        assert_eq!(store.update_rows("issues", &set, &key(7)).unwrap(), 1);
+        assert_eq!(store.open_issue_numbers("r").unwrap(), Vec::<i64>::new());
+
+        assert_eq!(store.delete_rows("issues", &key(7)).unwrap(), 1);
+        assert!(store.item_payloads("issue").unwrap().is_empty());
+    }
+}
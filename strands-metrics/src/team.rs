@@ -0,0 +1,90 @@
+use anyhow::Result;
+use octocrab::Octocrab;
+use rusqlite::{params, Connection};
+use serde_json::Value;
+
+/// Replaces the members of `team` with `members`. Logins are stored lowercased.
+pub fn load_team(conn: &Connection, team: &str, members: &[String]) -> Result<usize> {
+    conn.execute("DELETE FROM team_members WHERE team = ?1", params![team])?;
+    for login in members {
+        conn.execute(
+            "INSERT OR IGNORE INTO team_members (login, team) VALUES (?1, ?2)",
+            params![login.to_lowercase(), team],
+        )?;
+    }
+    Ok(members.len())
+}
+
+pub fn list_team(conn: &Connection, team: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT login FROM team_members WHERE team = ?1 ORDER BY login")?;
+    let logins = stmt
+        .query_map(params![team], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(logins)
+}
+
+/// Describes what `load_team` would change, one line per login:
+/// `+` added, `=` unchanged, `-` removed.
+pub fn diff_team(conn: &Connection, team: &str, members: &[String]) -> Result<Vec<String>> {
+    let current = list_team(conn, team)?;
+    let members: Vec<String> = members.iter().map(|m| m.to_lowercase()).collect();
+    let mut lines = Vec::new();
+
+    for login in &members {
+        if current.contains(login) {
+            lines.push(format!("= {}", login));
+        } else {
+            lines.push(format!("+ {}", login));
+        }
+    }
+    for login in &current {
+        if !members.contains(login) {
+            lines.push(format!("- {}", login));
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Logins of a GitHub Team's members, including members of child teams.
+/// Needs a token with `read:org`.
+pub async fn fetch_team_members(gh: &Octocrab, org: &str, slug: &str) -> Result<Vec<String>> {
+    let route = format!("/orgs/{}/teams/{}/members", org, slug);
+    let mut page: octocrab::Page<Value> = gh
+        .get(&route, Some(&serde_json::json!({ "per_page": 100 })))
+        .await?;
+
+    let mut logins = Vec::new();
+    loop {
+        logins.extend(
+            page.items
+                .iter()
+                .filter_map(|m| m.get("login").and_then(|l| l.as_str()))
+                .map(String::from),
+        );
+        match gh.get_page(&page.next).await? {
+            Some(next) => page = next,
+            None => break,
+        }
+    }
+    Ok(logins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_db;
+
+    #[test]
+    fn diff_team_describes_changes_without_writing() {
+        let conn = init_db(":memory:").unwrap();
+        load_team(&conn, "core", &["Alice".to_string(), "bob".to_string()]).unwrap();
+
+        let proposed = ["alice".to_string(), "Carol".to_string()];
+        assert_eq!(
+            diff_team(&conn, "core", &proposed).unwrap(),
+            ["= alice", "+ carol", "- bob"]
+        );
+        assert_eq!(list_team(&conn, "core").unwrap(), ["alice", "bob"]);
+    }
+}
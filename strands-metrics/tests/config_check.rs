@@ -0,0 +1,52 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn config_check(dir: &Path, goals: &str, packages: &str, contributors: &str) -> Output {
+    std::fs::write(dir.join("goals.yaml"), goals).unwrap();
+    std::fs::write(dir.join("packages.yaml"), packages).unwrap();
+    std::fs::write(dir.join("contributors.yaml"), contributors).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_strands-metrics"))
+        .current_dir(dir)
+        .arg("config-check")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn valid_config_passes() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = config_check(
+        dir.path(),
+        "goals:\n  - { metric: prs_merged, goal: 5, direction: higher_is_better }\n",
+        "packages:\n  - { name: strands-agents, registry: pypi }\n",
+        "contributors:\n  alice: maintainer\n",
+    );
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "Config OK.\n");
+}
+
+#[test]
+fn every_problem_is_reported_and_exit_is_non_zero() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = config_check(
+        dir.path(),
+        "goals:\n  - { metric: prs_merged, goal: 5, direction: up, warning_ratio: 0.5 }\n",
+        "packages:\n  - { name: strands-agents, registry: cargo }\n",
+        "contributors:\n  alice: maintainer\n",
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    for expected in [
+        "Goal 'prs_merged': direction must be one of",
+        "Goal 'prs_merged': warning_ratio must be >= 1.0, got 0.5",
+        "Package 'strands-agents': registry must be one of [\"pypi\", \"npm\"], got 'cargo'",
+    ] {
+        assert!(
+            stderr.contains(expected),
+            "missing {:?} in:\n{}",
+            expected,
+            stderr
+        );
+    }
+    assert!(!dir.path().join("metrics.db").exists());
+}
@@ -0,0 +1,26 @@
+use std::process::{Command, Output};
+
+fn sync(token: Option<&str>) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_strands-metrics"));
+    cmd.current_dir(dir.path()).arg("sync");
+    match token {
+        Some(token) => cmd.env("GITHUB_TOKEN", token),
+        None => cmd.env_remove("GITHUB_TOKEN"),
+    };
+    cmd.output().unwrap()
+}
+
+#[test]
+fn missing_token_fails_cleanly() {
+    let out = sync(None);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("GITHUB_TOKEN is not set"));
+}
+
+#[test]
+fn blank_token_fails_cleanly() {
+    let out = sync(Some("  "));
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("GITHUB_TOKEN is set but empty"));
+}